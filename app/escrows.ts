@@ -0,0 +1,114 @@
+import { Program, ProgramAccount, IdlAccounts } from '@coral-xyz/anchor';
+import { AccountMeta, PublicKey, TransactionInstruction } from '@solana/web3.js';
+import {
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+import { Escrow } from '../target/types/escrow';
+
+// Offset of `maker` in the escrow account: 8 byte discriminator + 8 byte seed
+const MAKER_OFFSET = 16;
+
+// A legacy transaction is at most 1232 bytes. One holding a single `bulk_refund`
+// signed by the maker spends 211 of them on the signature, header, blockhash,
+// the maker, token program and escrow program keys and the instruction data.
+// Each escrow adds four accounts, 32 bytes for the key and 1 for the index
+// each when none of them is shared with another escrow.
+const TRANSACTION_SIZE = 1232;
+const BULK_REFUND_BASE_SIZE = 211;
+const BULK_REFUND_ESCROW_SIZE = 4 * (32 + 1);
+
+// Escrows refunded by each `bulk_refund` instruction, the most that fit in a
+// legacy transaction: 7.
+export const BULK_REFUND_CHUNK = Math.floor(
+  (TRANSACTION_SIZE - BULK_REFUND_BASE_SIZE) / BULK_REFUND_ESCROW_SIZE
+);
+
+type EscrowAccount = ProgramAccount<IdlAccounts<Escrow>['escrow']>;
+
+// Finds every open escrow made by `maker`.
+// Escrows that still use the pre-version layout have a different size and are
+// left out, they must be migrated before they can be decoded (and refunded).
+export async function fetchMakerEscrows(
+  program: Program<Escrow>,
+  maker: PublicKey
+): Promise<EscrowAccount[]> {
+  return program.account.escrow.all([
+    { dataSize: program.account.escrow.size },
+    { memcmp: { offset: MAKER_OFFSET, bytes: maker.toBase58() } },
+  ]);
+}
+
+// Whether `bulk_refund` accepts the escrow: plain or conditional offers
+// without payment options, allowlists, gates, splits or a market listing.
+// The rest have to go through `refund` or their own instructions.
+export function isBulkRefundable({ account }: EscrowAccount): boolean {
+  return (
+    ('offer' in account.kind || 'conditional' in account.kind) &&
+    !account.acceptsOtherMints &&
+    account.market.equals(PublicKey.default) &&
+    !account.allowlisted &&
+    !account.gated &&
+    !account.split
+  );
+}
+
+// Builds the `bulk_refund` instructions closing all of `maker`'s refundable
+// escrows whose mint A belongs to `tokenProgram`, BULK_REFUND_CHUNK escrows
+// each so every instruction can be sent in its own transaction.
+export async function bulkRefundInstructions(
+  program: Program<Escrow>,
+  maker: PublicKey,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+): Promise<TransactionInstruction[]> {
+  const escrows = (await fetchMakerEscrows(program, maker)).filter(
+    isBulkRefundable
+  );
+
+  // `bulk_refund` takes a single token program
+  const mints = await program.provider.connection.getMultipleAccountsInfo(
+    escrows.map(({ account }) => account.tokenMintA)
+  );
+  const refundable = escrows.filter((_, i) =>
+    mints[i]?.owner.equals(tokenProgram)
+  );
+
+  const instructions: TransactionInstruction[] = [];
+  for (let i = 0; i < refundable.length; i += BULK_REFUND_CHUNK) {
+    const remainingAccounts: AccountMeta[] = refundable
+      .slice(i, i + BULK_REFUND_CHUNK)
+      .flatMap(({ publicKey, account }) => [
+        { pubkey: publicKey, isSigner: false, isWritable: true },
+        {
+          pubkey: getAssociatedTokenAddressSync(
+            account.tokenMintA,
+            publicKey,
+            true,
+            tokenProgram
+          ),
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: account.tokenMintA, isSigner: false, isWritable: false },
+        {
+          pubkey: getAssociatedTokenAddressSync(
+            account.tokenMintA,
+            maker,
+            false,
+            tokenProgram
+          ),
+          isSigner: false,
+          isWritable: true,
+        },
+      ]);
+
+    instructions.push(
+      await program.methods
+        .bulkRefund()
+        .accounts({ maker, tokenProgram })
+        .remainingAccounts(remainingAccounts)
+        .instruction()
+    );
+  }
+  return instructions;
+}
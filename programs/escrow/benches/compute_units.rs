@@ -0,0 +1,108 @@
+//Records the compute units used by each instruction in benches/compute_units.md
+//
+//cargo bench --bench compute_units
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use mollusk_svm_bencher::MolluskComputeUnitBencher;
+use solana_sdk::{account::Account, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+const SEED: u64 = 1;
+const RECEIVE_AMOUNT: u64 = 10_000;
+const DEPOSIT_AMOUNT: u64 = 5_000;
+const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+fn main() {
+    let mollusk = setup();
+
+    let user = || Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID);
+    let escrow = escrow_pda(&MAKER, SEED).0;
+    let vault = get_associated_token_address(&escrow, &MINT_X);
+    let open_escrow = escrow_account(
+        &mollusk,
+        &escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE_AMOUNT),
+    );
+
+    //Maker offers X for Y
+    let make = make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE_AMOUNT, DEPOSIT_AMOUNT);
+    let make_accounts: Vec<(Pubkey, Account)> = vec![
+        (MAKER, user()),
+        (MINT_X, mint_account(&mollusk, 100_000_000, 6)),
+        (MINT_Y, mint_account(&mollusk, 100_000_000, 6)),
+        (
+            get_associated_token_address(&MAKER, &MINT_X),
+            token_account(&mollusk, MINT_X, MAKER, 20_000),
+        ),
+        (escrow, Account::default()),
+        (vault, Account::default()),
+    ]
+    .into_iter()
+    .chain(program_accounts())
+    .collect();
+
+    //Taker fills it, paying for their own ATA of X (the worst case)
+    let exchange = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+    let exchange_accounts: Vec<(Pubkey, Account)> = vec![
+        (TAKER, user()),
+        (MAKER, user()),
+        (MINT_X, mint_account(&mollusk, 100_000_000, 6)),
+        (MINT_Y, mint_account(&mollusk, 100_000_000, 6)),
+        (
+            get_associated_token_address(&TAKER, &MINT_X),
+            Account::default(),
+        ),
+        (
+            get_associated_token_address(&TAKER, &MINT_Y),
+            token_account(&mollusk, MINT_Y, TAKER, RECEIVE_AMOUNT),
+        ),
+        (
+            get_associated_token_address(&MAKER, &MINT_Y),
+            token_account(&mollusk, MINT_Y, MAKER, 0),
+        ),
+        (escrow, open_escrow.clone()),
+        (
+            vault,
+            token_account(&mollusk, MINT_X, escrow, DEPOSIT_AMOUNT),
+        ),
+    ]
+    .into_iter()
+    .chain(program_accounts())
+    .collect();
+
+    //Maker cancels it
+    let refund = refund_ix(MAKER, MAKER, MINT_X, SEED);
+    let refund_accounts: Vec<(Pubkey, Account)> = vec![
+        (MAKER, user()),
+        (MINT_X, mint_account(&mollusk, 100_000_000, 6)),
+        (
+            get_associated_token_address(&MAKER, &MINT_X),
+            token_account(&mollusk, MINT_X, MAKER, 0),
+        ),
+        (escrow, open_escrow),
+        (
+            vault,
+            token_account(&mollusk, MINT_X, escrow, DEPOSIT_AMOUNT),
+        ),
+    ]
+    .into_iter()
+    .chain(program_accounts())
+    .collect();
+
+    let benches = [
+        ("make", &make, &make_accounts),
+        ("exchange", &exchange, &exchange_accounts),
+        ("refund", &refund, &refund_accounts),
+    ];
+
+    let mut bencher = MolluskComputeUnitBencher::new(setup()).must_pass(true);
+    for (name, instruction, accounts) in &benches {
+        bencher = bencher.bench((name, instruction, accounts.as_slice()));
+    }
+    bencher.execute();
+}
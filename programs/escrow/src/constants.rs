@@ -1,3 +1,25 @@
+#[allow(unused_imports)]
 use anchor_lang::prelude::*;
 
-pub const ANCHOR_DESCRIMINATOR: usize = 8;
\ No newline at end of file
+pub const ANCHOR_DESCRIMINATOR: usize = 8;
+
+//how many times the price of an exponential dutch auction halves its distance to the floor
+pub const EXPONENTIAL_HALVINGS: u64 = 8;
+
+//most mints a basket can offer, and most it can ask for
+pub const MAX_BASKET_LEGS: usize = 4;
+
+//most mints an offer can accept on top of `token_mint_b`
+pub const MAX_PAYMENT_MINTS: usize = 4;
+
+//most escrows a side of a market's book can hold
+pub const MAX_BOOK_ORDERS: usize = 32;
+
+//longest Merkle proof `exchange_allowlisted` accepts, enough for 2^20 allowlisted takers
+pub const MAX_PROOF_LEN: usize = 20;
+
+//most wallets an offer can split its proceeds between
+pub const MAX_RECIPIENTS: usize = 8;
+
+//largest exponent, either way, a conditional order's trigger price can have
+pub const MAX_TRIGGER_EXPO: i32 = 18;
@@ -2,6 +2,154 @@ use anchor_lang::prelude::*;
 
 #[error_code]
 pub enum ErrorCode {
-  #[msg("Your custom message here.")]
-  CustomError,
-}
\ No newline at end of file
+  #[msg("The account is not an escrow created by this program.")]
+  InvalidEscrowAccount,
+  #[msg("The escrow already has the current layout.")]
+  EscrowUpToDate,
+  #[msg("Remaining accounts must be passed as escrow, vault, maker token account groups.")]
+  InvalidRemainingAccounts,
+  #[msg("The escrow does not match the accounts it was passed with.")]
+  EscrowMismatch,
+  #[msg("The taker would receive less than the minimum amount.")]
+  SlippageExceeded,
+  #[msg("Arithmetic overflow.")]
+  MathOverflow,
+  #[msg("A dutch auction needs a floor below its start price and an end time after its start time.")]
+  InvalidPriceCurve,
+  #[msg("This escrow can't be used as a plain offer.")]
+  NotAnOffer,
+  #[msg("This escrow is not an english auction.")]
+  NotAnAuction,
+  #[msg("The auction needs an end time in the future.")]
+  InvalidAuction,
+  #[msg("The auction has ended.")]
+  AuctionEnded,
+  #[msg("The auction has not ended yet.")]
+  AuctionNotEnded,
+  #[msg("The bid is below the reserve price or the minimum increment.")]
+  BidTooLow,
+  #[msg("You already hold the top bid.")]
+  AlreadyTopBidder,
+  #[msg("The accounts of the bid being outbid are missing.")]
+  MissingPreviousBid,
+  #[msg("The auction has bids.")]
+  AuctionHasBids,
+  #[msg("The auction has no bids.")]
+  AuctionHasNoBids,
+  #[msg("This escrow is not a sealed-bid auction.")]
+  NotASealedAuction,
+  #[msg("The reveal window must end after the commit window, which must end in the future.")]
+  InvalidSealedAuction,
+  #[msg("The commit window is over.")]
+  CommitPhaseOver,
+  #[msg("Bids can only be revealed during the reveal window.")]
+  NotInRevealPhase,
+  #[msg("The bid and salt don't match the commitment.")]
+  CommitmentMismatch,
+  #[msg("The bid has already been revealed.")]
+  AlreadyRevealed,
+  #[msg("The auction has not been settled yet.")]
+  AuctionNotSettled,
+  #[msg("The auction has already been settled.")]
+  AuctionSettled,
+  #[msg("The price feed is missing, malformed or has a non-positive price.")]
+  InvalidPriceFeed,
+  #[msg("The oracle price is older than the offer allows.")]
+  StalePrice,
+  #[msg("The oracle's confidence interval is wider than the offer allows.")]
+  PriceTooUncertain,
+  #[msg("Oracle-priced offers need their price feed and can only be taken with `exchange`.")]
+  OraclePriced,
+  #[msg("The trigger needs a positive price, an exponent within 18 either way, a tip below the deposit and a confidence limit of at most 100%.")]
+  InvalidTrigger,
+  #[msg("This escrow is not a conditional order.")]
+  NotConditional,
+  #[msg("The oracle price hasn't crossed the trigger.")]
+  TriggerNotReached,
+  #[msg("This conditional order hasn't been triggered yet.")]
+  NotTriggered,
+  #[msg("A basket needs 1 to 4 distinct mints on each side and one deposit per offered mint.")]
+  InvalidBasket,
+  #[msg("Payment mints must be 1 to 4 distinct mints other than token_mint_b.")]
+  InvalidPaymentMints,
+  #[msg("The offer doesn't accept this mint as payment.")]
+  PaymentMintNotAccepted,
+  #[msg("Offers accepting several mints must be taken or refunded on their own.")]
+  HasPaymentOptions,
+  #[msg("Only fixed-price offers of opposite directions on the same pair can be matched.")]
+  InvalidMatch,
+  #[msg("The offers' prices don't cross.")]
+  OffersDontCross,
+  #[msg("Only the program's upgrade authority can do this.")]
+  Unauthorized,
+  #[msg("A market needs two different mints and non-zero tick, lot and minimum order sizes.")]
+  InvalidMarket,
+  #[msg("The order is below the market's minimum size or off its tick and lot grid.")]
+  OffMarketGrid,
+  #[msg("This side of the book is full of better-priced orders.")]
+  BookFull,
+  #[msg("Escrows listed on a market must be taken or refunded with their book.")]
+  ListedOnMarket,
+  #[msg("This escrow is not a crowd fill.")]
+  NotACrowdFill,
+  #[msg("A crowd fill needs a future deadline and something to raise.")]
+  InvalidCrowdFill,
+  #[msg("The crowd fill is already filled or past its deadline.")]
+  CrowdFillClosed,
+  #[msg("The crowd fill hasn't raised its target yet.")]
+  CrowdFillNotFilled,
+  #[msg("The crowd fill has been filled, or is still running.")]
+  CrowdFillNotExpired,
+  #[msg("The crowd fill has outstanding contributions.")]
+  HasContributions,
+  #[msg("This escrow is not a hash time-locked contract.")]
+  NotAnHtlc,
+  #[msg("A hash time lock needs a timelock in the future.")]
+  InvalidHtlc,
+  #[msg("The preimage doesn't match the hashlock.")]
+  WrongPreimage,
+  #[msg("The timelock has expired.")]
+  TimelockExpired,
+  #[msg("The timelock hasn't expired yet.")]
+  TimelockNotExpired,
+  #[msg("The taker isn't on the escrow's allowlist.")]
+  NotAllowlisted,
+  #[msg("The fill would take the taker over their allowlist cap.")]
+  CapExceeded,
+  #[msg("Allowlisted escrows can only be taken with `exchange_allowlisted`.")]
+  Allowlisted,
+  #[msg("The account is not the Metaplex metadata of the mint.")]
+  InvalidMetadata,
+  #[msg("A balance gate needs a non-zero minimum amount.")]
+  InvalidTokenGate,
+  #[msg("The taker doesn't hold what the escrow's token gate requires.")]
+  GateNotMet,
+  #[msg("Token-gated escrows can only be taken with `exchange`.")]
+  TokenGated,
+  #[msg("The deployment's mint policy rejects one of the escrow's mints.")]
+  RiskyMint,
+  #[msg("The mint policy names an unknown risk.")]
+  InvalidMintPolicy,
+  #[msg("The mint is not a programmable NFT.")]
+  NotAProgrammableNft,
+  #[msg("The NFT owes royalties: pass its metadata and its creators' token accounts.")]
+  RoyaltiesOwed,
+  #[msg("Only the NFT's sole creator can waive its royalties.")]
+  NotACreator,
+  #[msg("Recipients need positive shares adding up to 10000 bps and distinct wallets.")]
+  InvalidRecipients,
+  #[msg("The escrow splits its proceeds: pass its recipients and their token accounts.")]
+  RecipientsMissing,
+  #[msg("Escrows splitting their proceeds can only be taken with `exchange`.")]
+  SplitsProceeds,
+  #[msg("The token account is not of the escrow's mint or not held by the taker.")]
+  InvalidDestination,
+  #[msg("Escrows paying a custom destination can only be taken with `exchange` or `batch_exchange`.")]
+  CustomDestination,
+  #[msg("The taker would pay more than their maximum.")]
+  MaxPayExceeded,
+  #[msg("The offer accepts other mints: pass its payment options.")]
+  PaymentOptionsMissing,
+  #[msg("Escrows paying their proceeds to a destination can't split them.")]
+  SplitWithDestination,
+}
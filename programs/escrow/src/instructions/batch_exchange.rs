@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::{get_associated_token_address_with_program_id, AssociatedToken};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, PriceCurve};
+
+
+//Fills several escrows of the same pair in one instruction.
+//remaining_accounts: [escrow, vault, maker_token_account_b] for every escrow, in fill order,
+//where the maker's token account is its `proceeds_account`
+#[derive(Accounts)]
+pub struct BatchTakeOffer<'info> {
+
+  #[account(mut)]
+  pub taker: Signer<'info>,
+
+  pub token_mint_a: InterfaceAccount<'info, Mint>,
+  pub token_mint_b: InterfaceAccount<'info, Mint>,
+
+  #[account(
+    init_if_needed,
+    payer = taker,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = taker,
+  )]
+  pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = taker,
+  )]
+  pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> BatchTakeOffer<'info> {
+  //Fills the escrows in order until `total` of mint B is spent. Every escrow the
+  //budget covers is filled and closed, the last one may be filled partially.
+  pub fn fill(&mut self, remaining_accounts: &'info [AccountInfo<'info>], total: u64, min_receive: u64) -> Result<()> {
+
+    let groups = remaining_accounts.chunks_exact(3);
+    require!(
+      !remaining_accounts.is_empty() && groups.remainder().is_empty(),
+      ErrorCode::InvalidRemainingAccounts
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut budget = total;
+    let mut received: u64 = 0;
+
+    for accounts in groups {
+      if budget == 0 {
+        break;
+      }
+
+      let mut escrow = Account::<Escrow>::try_from(&accounts[0])?;
+      let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+      let maker_token_account_b = &accounts[2];
+
+      self.validate(&escrow, &vault, maker_token_account_b)?;
+
+      let price = escrow.receive_amount_at(now);
+      let pay = budget.min(price);
+
+      // Only fixed price offers can be filled partially
+      if pay < price && escrow.price_curve != PriceCurve::Fixed {
+        break;
+      }
+
+      let amount = escrow
+        .fill_amount(vault.amount, pay)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+      // What is left of the budget doesn't buy a single unit of this escrow
+      if amount == 0 {
+        break;
+      }
+
+      self.send_wanted_tokens_to_maker(maker_token_account_b, pay)?;
+      self.withdraw(&escrow, &vault, amount)?;
+
+      if pay == price {
+        self.close_vault(&escrow, &vault)?;
+        escrow.close(self.taker.to_account_info())?;
+      } else {
+        escrow.receive_amount -= pay;
+        escrow.exit(&crate::ID)?;
+      }
+
+      budget -= pay;
+      received = received.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    require!(received >= min_receive, ErrorCode::SlippageExceeded);
+
+    Ok(())
+  }
+
+  fn validate(&self, escrow: &Account<'info, Escrow>, vault: &InterfaceAccount<'info, TokenAccount>, maker_token_account_b: &AccountInfo<'info>) -> Result<()> {
+
+    let seed_bytes = escrow.seed.to_le_bytes();
+    let escrow_key = Pubkey::create_program_address(
+      &[b"escrow", escrow.maker.as_ref(), seed_bytes.as_ref(), &[escrow.bump]],
+      &crate::ID,
+    ).map_err(|_| ErrorCode::EscrowMismatch)?;
+
+    let token_program = self.token_program.key();
+
+    require!(escrow.kind == EscrowKind::Offer, ErrorCode::NotAnOffer);
+    require!(!escrow.accepts_other_mints, ErrorCode::HasPaymentOptions);
+    require!(escrow.market == Pubkey::default(), ErrorCode::ListedOnMarket);
+    require!(!escrow.allowlisted, ErrorCode::Allowlisted);
+    require!(!escrow.gated, ErrorCode::TokenGated);
+    require!(!escrow.split, ErrorCode::SplitsProceeds);
+    require!(escrow.price_curve != PriceCurve::Oracle, ErrorCode::OraclePriced);
+    require!(!escrow.owes_royalties(&self.token_mint_a), ErrorCode::RoyaltiesOwed);
+
+    require_keys_eq!(escrow_key, escrow.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(escrow.token_mint_a, self.token_mint_a.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(escrow.token_mint_b, self.token_mint_b.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(
+      vault.key(),
+      get_associated_token_address_with_program_id(&escrow_key, &escrow.token_mint_a, &token_program),
+      ErrorCode::EscrowMismatch
+    );
+    require_keys_eq!(
+      maker_token_account_b.key(),
+      escrow.proceeds_account(&escrow.token_mint_b, &token_program),
+      ErrorCode::EscrowMismatch
+    );
+
+    Ok(())
+  }
+
+  fn send_wanted_tokens_to_maker(&self, maker_token_account_b: &AccountInfo<'info>, amount: u64) -> Result<()> {
+
+    let cpi_accounts = TransferChecked {
+      from: self.taker_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: maker_token_account_b.clone(),
+      authority: self.taker.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, amount, self.token_mint_b.decimals)
+  }
+
+  fn withdraw(&self, escrow: &Account<'info, Escrow>, vault: &InterfaceAccount<'info, TokenAccount>, amount: u64) -> Result<()> {
+
+    let seed_bytes = escrow.seed.to_le_bytes();
+    let seeds = &[b"escrow", escrow.maker.as_ref(), seed_bytes.as_ref(), &[escrow.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: vault.to_account_info(),
+      mint: self.token_mint_a.to_account_info(),
+      to: self.taker_token_account_a.to_account_info(),
+      authority: escrow.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, amount, self.token_mint_a.decimals)
+  }
+
+  fn close_vault(&self, escrow: &Account<'info, Escrow>, vault: &InterfaceAccount<'info, TokenAccount>) -> Result<()> {
+
+    let seed_bytes = escrow.seed.to_le_bytes();
+    let seeds = &[b"escrow", escrow.maker.as_ref(), seed_bytes.as_ref(), &[escrow.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = CloseAccount {
+      account: vault.to_account_info(),
+      destination: self.taker.to_account_info(),
+      authority: escrow.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)
+  }
+}
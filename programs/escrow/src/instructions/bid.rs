@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Auction, Bid, Escrow, EscrowKind};
+
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+
+  #[account(mut)]
+  pub bidder: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bidder,
+  )]
+  pub bidder_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::EnglishAuction @ ErrorCode::NotAnAuction,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"auction", escrow.key().as_ref()],
+    bump = auction.bump,
+    has_one = escrow,
+  )]
+  pub auction: Box<Account<'info, Auction>>,
+
+  #[account(
+    init,
+    payer = bidder,
+    space = ANCHOR_DESCRIMINATOR + Bid::INIT_SPACE,
+    seeds = [b"bid", escrow.key().as_ref(), bidder.key().as_ref()],
+    bump,
+  )]
+  pub bid: Box<Account<'info, Bid>>,
+
+  #[account(
+    init,
+    payer = bidder,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bid,
+  )]
+  pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  //The top bid being outbid, only needed once the auction has a bid.
+  //Can be a PDA of another program, so it isn't required to be a system account
+  /// CHECK: pinned to `auction.top_bidder`, only receives the refund and rent
+  #[account(
+    mut,
+    address = auction.top_bidder,
+  )]
+  pub previous_bidder: Option<UncheckedAccount<'info>>,
+
+  #[account(
+    mut,
+    seeds = [b"bid", escrow.key().as_ref(), auction.top_bidder.as_ref()],
+    bump = previous_bid.bump,
+  )]
+  pub previous_bid: Option<Box<Account<'info, Bid>>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = previous_bid,
+  )]
+  pub previous_bid_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+  //created if needed, so closing it can't block anyone from outbidding
+  #[account(
+    init_if_needed,
+    payer = bidder,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = previous_bidder,
+  )]
+  pub previous_bidder_token_account_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceBid<'info> {
+  pub fn place_bid(&mut self, amount: u64, bumps: PlaceBidBumps) -> Result<()> {
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now < self.auction.end_time, ErrorCode::AuctionEnded);
+
+    if self.auction.has_bids() {
+      require_keys_neq!(self.bidder.key(), self.auction.top_bidder, ErrorCode::AlreadyTopBidder);
+      let min_bid = self.auction.top_bid
+        .checked_add(self.auction.min_increment)
+        .ok_or(ErrorCode::MathOverflow)?;
+      require!(amount >= min_bid, ErrorCode::BidTooLow);
+
+      self.refund_previous_bid()?;
+    } else {
+      require!(amount >= self.escrow.receive_amount, ErrorCode::BidTooLow);
+    }
+
+    // Lock the bid
+    let cpi_accounts = TransferChecked {
+      from: self.bidder_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.bid_vault.to_account_info(),
+      authority: self.bidder.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, amount, self.token_mint_b.decimals)?;
+
+    self.bid.set_inner(Bid {
+      escrow: self.escrow.key(),
+      bidder: self.bidder.key(),
+      amount,
+      bump: bumps.bid,
+    });
+
+    self.auction.top_bid = amount;
+    self.auction.top_bidder = self.bidder.key();
+
+    // Anti-sniping: a late bid keeps the auction open for another `extension`
+    if self.auction.end_time - now < self.auction.extension {
+      self.auction.end_time = now + self.auction.extension;
+    }
+
+    Ok(())
+  }
+
+  fn refund_previous_bid(&mut self) -> Result<()> {
+
+    let (Some(previous_bidder), Some(previous_bid), Some(previous_bid_vault), Some(previous_bidder_token_account_b)) = (
+      self.previous_bidder.as_ref(),
+      self.previous_bid.as_ref(),
+      self.previous_bid_vault.as_ref(),
+      self.previous_bidder_token_account_b.as_ref(),
+    ) else {
+      return err!(ErrorCode::MissingPreviousBid);
+    };
+
+    let escrow_key = self.escrow.key();
+    let seeds = &[b"bid", escrow_key.as_ref(), previous_bid.bidder.as_ref(), &[previous_bid.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: previous_bid_vault.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: previous_bidder_token_account_b.to_account_info(),
+      authority: previous_bid.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, previous_bid_vault.amount, self.token_mint_b.decimals)?;
+
+    let cpi_accounts = CloseAccount {
+      account: previous_bid_vault.to_account_info(),
+      destination: previous_bidder.to_account_info(),
+      authority: previous_bid.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)?;
+
+    previous_bid.close(previous_bidder.to_account_info())
+  }
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::withdraw_and_close_vault;
+use crate::state::{Escrow, EscrowKind};
+
+
+//Refunds and closes many of the maker's escrows in one instruction.
+//remaining_accounts: [escrow, vault, token_mint_a, maker_token_account_a] for every escrow
+#[derive(Accounts)]
+pub struct BulkRefundOffer<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> BulkRefundOffer<'info> {
+  pub fn refund_all(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+
+    let groups = remaining_accounts.chunks_exact(4);
+    require!(
+      !remaining_accounts.is_empty() && groups.remainder().is_empty(),
+      ErrorCode::InvalidRemainingAccounts
+    );
+
+    for accounts in groups {
+      let escrow = Account::<Escrow>::try_from(&accounts[0])?;
+      let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+      let token_mint_a = InterfaceAccount::<Mint>::try_from(&accounts[2])?;
+      let maker_token_account_a = &accounts[3];
+
+      self.validate(&escrow, &vault, &token_mint_a, maker_token_account_a)?;
+      withdraw_and_close_vault(
+        &escrow,
+        &vault,
+        &token_mint_a,
+        maker_token_account_a.clone(),
+        self.maker.to_account_info(),
+        &self.token_program,
+      )?;
+
+      escrow.close(self.maker.to_account_info())?;
+    }
+
+    Ok(())
+  }
+
+  fn validate(&self, escrow: &Account<'info, Escrow>, vault: &InterfaceAccount<'info, TokenAccount>, token_mint_a: &InterfaceAccount<'info, Mint>, maker_token_account_a: &AccountInfo<'info>) -> Result<()> {
+
+    let seed_bytes = escrow.seed.to_le_bytes();
+    let escrow_key = Pubkey::create_program_address(
+      &[b"escrow", self.maker.key().as_ref(), seed_bytes.as_ref(), &[escrow.bump]],
+      &crate::ID,
+    ).map_err(|_| ErrorCode::EscrowMismatch)?;
+
+    let token_program = self.token_program.key();
+
+    require!(matches!(escrow.kind, EscrowKind::Offer | EscrowKind::Conditional), ErrorCode::NotAnOffer);
+    require!(!escrow.accepts_other_mints, ErrorCode::HasPaymentOptions);
+    require!(escrow.market == Pubkey::default(), ErrorCode::ListedOnMarket);
+    require!(!escrow.allowlisted, ErrorCode::Allowlisted);
+    require!(!escrow.gated, ErrorCode::TokenGated);
+    require!(!escrow.split, ErrorCode::SplitsProceeds);
+
+    require_keys_eq!(escrow.maker, self.maker.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(escrow_key, escrow.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(escrow.token_mint_a, token_mint_a.key(), ErrorCode::EscrowMismatch);
+    require_keys_eq!(
+      vault.key(),
+      get_associated_token_address_with_program_id(&escrow_key, &escrow.token_mint_a, &token_program),
+      ErrorCode::EscrowMismatch
+    );
+    require_keys_eq!(
+      maker_token_account_a.key(),
+      get_associated_token_address_with_program_id(&escrow.maker, &escrow.token_mint_a, &token_program),
+      ErrorCode::EscrowMismatch
+    );
+
+    Ok(())
+  }
+}
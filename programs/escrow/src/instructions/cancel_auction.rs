@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund;
+use crate::state::{Auction, Escrow, EscrowKind};
+
+
+//The maker can take an auction down as long as nobody has bid on it
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::EnglishAuction @ ErrorCode::NotAnAuction,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"auction", escrow.key().as_ref()],
+    bump = auction.bump,
+    has_one = escrow,
+    constraint = !auction.has_bids() @ ErrorCode::AuctionHasBids,
+    close = maker,
+  )]
+  pub auction: Box<Account<'info, Auction>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: InterfaceAccount<'info, TokenAccount>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelAuction<'info> {
+  pub fn withdraw_and_close_vault(&self) -> Result<()> {
+    refund::withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::instructions::refund;
+use crate::state::{CrowdFill, Escrow, EscrowKind};
+
+
+//The maker can take a crowd fill down before anyone contributes, or once every
+//contribution to an expired one has been reclaimed. Whatever was sent to the pool
+//outside of `contribute` goes to the maker.
+#[derive(Accounts)]
+pub struct CancelCrowdFill<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = maker,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::CrowdFill @ ErrorCode::NotACrowdFill,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"crowd", escrow.key().as_ref()],
+    bump = crowd_fill.bump,
+    has_one = escrow,
+    constraint = !crowd_fill.settled @ ErrorCode::CrowdFillClosed,
+    constraint = crowd_fill.contributors == 0 @ ErrorCode::HasContributions,
+    close = maker,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = crowd_fill,
+  )]
+  pub pool: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelCrowdFill<'info> {
+  pub fn withdraw_and_close_vault(&self) -> Result<()> {
+    refund::withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+  //Every contribution has been reclaimed, but anyone can transfer tokens to the
+  //pool directly and it can't be closed while it holds any
+  pub fn close_pool(&self) -> Result<()> {
+
+    let escrow_key = self.escrow.key();
+    let seeds = &[b"crowd", escrow_key.as_ref(), &[self.crowd_fill.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    if self.pool.amount > 0 {
+      let cpi_accounts = TransferChecked {
+        from: self.pool.to_account_info(),
+        mint: self.token_mint_b.to_account_info(),
+        to: self.maker_token_account_b.to_account_info(),
+        authority: self.crowd_fill.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+      transfer_checked(cpi_ctx, self.pool.amount, self.token_mint_b.decimals)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+      account: self.pool.to_account_info(),
+      destination: self.maker.to_account_info(),
+      authority: self.crowd_fill.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)
+  }
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund;
+use crate::state::{Escrow, EscrowKind, SealedAuction};
+
+
+//The maker can take a sealed auction down before anyone commits, or once the
+//reveal window is over without a valid bid. Outstanding commitments can then be claimed.
+#[derive(Accounts)]
+pub struct CancelSealedAuction<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: InterfaceAccount<'info, Mint>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::SealedAuction @ ErrorCode::NotASealedAuction,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", escrow.key().as_ref()],
+    bump = sealed_auction.bump,
+    has_one = escrow,
+    constraint = !sealed_auction.settled @ ErrorCode::AuctionSettled,
+    constraint = !sealed_auction.has_bids() @ ErrorCode::AuctionHasBids,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: InterfaceAccount<'info, TokenAccount>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelSealedAuction<'info> {
+  pub fn withdraw_and_close_vault(&self) -> Result<()> {
+    refund::withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+  pub fn close_auction(&mut self) -> Result<()> {
+
+    if self.sealed_auction.commitments == 0 {
+      return self.sealed_auction.close(self.maker.to_account_info());
+    }
+
+    //Committed bidders must have had their chance to reveal
+    require!(Clock::get()?.unix_timestamp >= self.sealed_auction.reveal_end, ErrorCode::AuctionNotEnded);
+    self.sealed_auction.settled = true;
+
+    Ok(())
+  }
+}
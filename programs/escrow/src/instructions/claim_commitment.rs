@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::{Commitment, SealedAuction};
+
+
+//Returns a commitment's deposit once the auction is settled, cancelled or expired. A bidder
+//who never revealed forfeits `forfeit_bps` of the collateral to the maker.
+//The auction account is closed to the maker with the last commitment.
+#[derive(Accounts)]
+pub struct ClaimCommitment<'info> {
+
+  #[account(mut)]
+  pub bidder: Signer<'info>,
+
+  //can be a PDA of another program, so it isn't required to be a system account
+  /// CHECK: pinned to `sealed_auction.maker`, only receives rent and owns its ATA of mint B
+  #[account(
+    mut,
+    address = sealed_auction.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = sealed_auction.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bidder,
+  )]
+  pub bidder_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  //only receives forfeits, created if needed since a cancelled auction may never have paid the maker
+  #[account(
+    init_if_needed,
+    payer = bidder,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", sealed_auction.escrow.as_ref()],
+    bump = sealed_auction.bump,
+    constraint = sealed_auction.settled @ ErrorCode::AuctionNotSettled,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    mut,
+    seeds = [b"commitment", sealed_auction.key().as_ref(), bidder.key().as_ref()],
+    bump = commitment.bump,
+    has_one = bidder,
+    close = bidder,
+  )]
+  pub commitment: Box<Account<'info, Commitment>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = commitment,
+  )]
+  pub commitment_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimCommitment<'info> {
+  pub fn claim(&mut self) -> Result<()> {
+
+    let forfeit = if self.commitment.revealed {
+      0
+    } else {
+      self.sealed_auction.forfeit().min(self.commitment_vault.amount)
+    };
+    let refund = self.commitment_vault.amount - forfeit;
+
+    let auction_key = self.sealed_auction.key();
+    let seeds = &[b"commitment", auction_key.as_ref(), self.commitment.bidder.as_ref(), &[self.commitment.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    for (to, amount) in [(self.maker_token_account_b.to_account_info(), forfeit), (self.bidder_token_account_b.to_account_info(), refund)] {
+      if amount == 0 {
+        continue;
+      }
+      let cpi_accounts = TransferChecked {
+        from: self.commitment_vault.to_account_info(),
+        mint: self.token_mint_b.to_account_info(),
+        to,
+        authority: self.commitment.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+      transfer_checked(cpi_ctx, amount, self.token_mint_b.decimals)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+      account: self.commitment_vault.to_account_info(),
+      destination: self.bidder.to_account_info(),
+      authority: self.commitment.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)?;
+
+    self.sealed_auction.commitments -= 1;
+    if self.sealed_auction.commitments == 0 {
+      self.sealed_auction.close(self.maker.to_account_info())?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::{close_vault, transfer_from_vault};
+use crate::state::{Contribution, CrowdFill, Escrow, EscrowKind};
+
+
+//Pays a contributor their share of a settled crowd fill's vault. The last claim
+//takes the rounding dust and closes the vault, escrow and fill to the maker.
+#[derive(Accounts)]
+pub struct ClaimCrowdFill<'info> {
+
+  #[account(mut)]
+  pub contributor: Signer<'info>,
+
+  //can be a PDA of another program, so it isn't required to be a system account
+  /// CHECK: pinned to `escrow.maker`, only receives rent
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = contributor,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = contributor,
+  )]
+  pub contributor_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::CrowdFill @ ErrorCode::NotACrowdFill,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"crowd", escrow.key().as_ref()],
+    bump = crowd_fill.bump,
+    has_one = escrow,
+    constraint = crowd_fill.settled @ ErrorCode::CrowdFillNotFilled,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  #[account(
+    mut,
+    seeds = [b"contribution", crowd_fill.key().as_ref(), contributor.key().as_ref()],
+    bump = contribution.bump,
+    has_one = contributor,
+    close = contributor,
+  )]
+  pub contribution: Box<Account<'info, Contribution>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimCrowdFill<'info> {
+  pub fn claim(&mut self) -> Result<()> {
+
+    let last = self.crowd_fill.contributors == 1;
+    let share = if last {
+      self.vault.amount
+    } else {
+      self.crowd_fill.share(self.contribution.amount)
+    };
+
+    transfer_from_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.contributor_token_account_a.to_account_info(),
+      share,
+      &self.token_program,
+    )?;
+
+    self.crowd_fill.contributors -= 1;
+    if !last {
+      return Ok(());
+    }
+
+    close_vault(&self.escrow, &self.vault, self.maker.to_account_info(), &self.token_program)?;
+    self.crowd_fill.close(self.maker.to_account_info())?;
+    self.escrow.close(self.maker.to_account_info())
+  }
+}
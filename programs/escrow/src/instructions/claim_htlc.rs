@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::withdraw_and_close_vault;
+use crate::state::{Escrow, EscrowKind, Htlc, HtlcClaimed};
+
+
+//The designated taker claims the vault with the preimage before the timelock. The
+//escrow's rent goes back to the maker, who paid for it.
+#[derive(Accounts)]
+pub struct ClaimHtlc<'info> {
+
+  #[account(mut)]
+  pub taker: Signer<'info>,
+
+  //can be a PDA of another program, such as a multisig vault, so it isn't required to be a system account
+  /// CHECK: pinned to `escrow.maker`, only receives rent
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = taker,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = taker,
+  )]
+  pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::Htlc @ ErrorCode::NotAnHtlc,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"htlc", escrow.key().as_ref()],
+    bump = htlc.bump,
+    has_one = escrow,
+    has_one = taker,
+    close = maker,
+  )]
+  pub htlc: Box<Account<'info, Htlc>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimHtlc<'info> {
+  pub fn claim(&mut self, preimage: [u8; 32]) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp < self.htlc.timelock, ErrorCode::TimelockExpired);
+    require!(self.htlc.unlocks(&preimage), ErrorCode::WrongPreimage);
+
+    withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.taker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )?;
+
+    emit!(HtlcClaimed {
+      escrow: self.escrow.key(),
+      taker: self.taker.key(),
+      preimage,
+    });
+
+    Ok(())
+  }
+}
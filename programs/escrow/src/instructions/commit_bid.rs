@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Commitment, SealedAuction};
+
+
+//Commits to a hidden bid, see `Commitment::hash`. Every bidder locks the same
+//collateral so the deposit doesn't give the bid away.
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+
+  #[account(mut)]
+  pub bidder: Signer<'info>,
+
+  #[account(
+    address = sealed_auction.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bidder,
+  )]
+  pub bidder_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", sealed_auction.escrow.as_ref()],
+    bump = sealed_auction.bump,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    init,
+    payer = bidder,
+    space = ANCHOR_DESCRIMINATOR + Commitment::INIT_SPACE,
+    seeds = [b"commitment", sealed_auction.key().as_ref(), bidder.key().as_ref()],
+    bump,
+  )]
+  pub commitment: Box<Account<'info, Commitment>>,
+
+  #[account(
+    init,
+    payer = bidder,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = commitment,
+  )]
+  pub commitment_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> CommitBid<'info> {
+  pub fn commit_bid(&mut self, hash: [u8; 32], bumps: CommitBidBumps) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp < self.sealed_auction.commit_end, ErrorCode::CommitPhaseOver);
+
+    let cpi_accounts = TransferChecked {
+      from: self.bidder_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.commitment_vault.to_account_info(),
+      authority: self.bidder.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, self.sealed_auction.collateral, self.token_mint_b.decimals)?;
+
+    self.commitment.set_inner(Commitment {
+      auction: self.sealed_auction.key(),
+      bidder: self.bidder.key(),
+      hash,
+      revealed: false,
+      bid: 0,
+      bump: bumps.commitment,
+    });
+
+    self.sealed_auction.commitments = self.sealed_auction.commitments
+      .checked_add(1)
+      .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::program::Escrow;
+use crate::state::MatchConfig;
+
+
+//Sets how the spread of matched offers is split, only the upgrade authority can change it
+#[derive(Accounts)]
+pub struct ConfigureMatching<'info> {
+
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program: Program<'info, Escrow>,
+
+  #[account(
+    constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = ANCHOR_DESCRIMINATOR + MatchConfig::INIT_SPACE,
+    seeds = [b"match_config"],
+    bump,
+  )]
+  pub match_config: Account<'info, MatchConfig>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ConfigureMatching<'info> {
+  pub fn configure(&mut self, keeper_bps: u16, bumps: ConfigureMatchingBumps) -> Result<()> {
+
+    require!(keeper_bps <= 10_000, ErrorCode::InvalidMatch);
+
+    self.match_config.set_inner(MatchConfig {
+      keeper_bps,
+      bump: bumps.match_config,
+    });
+
+    Ok(())
+  }
+}
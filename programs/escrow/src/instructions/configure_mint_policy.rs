@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::program::Escrow;
+use crate::state::MintPolicy;
+
+
+//Sets which mint risks `make` refuses, only the upgrade authority can change it
+#[derive(Accounts)]
+pub struct ConfigureMintPolicy<'info> {
+
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program: Program<'info, Escrow>,
+
+  #[account(
+    constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(
+    init_if_needed,
+    payer = authority,
+    space = ANCHOR_DESCRIMINATOR + MintPolicy::INIT_SPACE,
+    seeds = [b"mint_policy"],
+    bump,
+  )]
+  pub mint_policy: Account<'info, MintPolicy>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ConfigureMintPolicy<'info> {
+  pub fn configure(&mut self, reject: u8, bumps: ConfigureMintPolicyBumps) -> Result<()> {
+
+    require!(reject & !MintPolicy::ALL == 0, ErrorCode::InvalidMintPolicy);
+
+    self.mint_policy.set_inner(MintPolicy {
+      reject,
+      bump: bumps.mint_policy,
+    });
+
+    Ok(())
+  }
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Contribution, CrowdFill};
+
+
+//Adds mint B to a crowd fill, capped at what is left to raise. Contributing again
+//adds to the same `Contribution`.
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+
+  #[account(mut)]
+  pub contributor: Signer<'info>,
+
+  #[account(
+    address = crowd_fill.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = contributor,
+  )]
+  pub contributor_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"crowd", crowd_fill.escrow.as_ref()],
+    bump = crowd_fill.bump,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  #[account(
+    init_if_needed,
+    payer = contributor,
+    space = ANCHOR_DESCRIMINATOR + Contribution::INIT_SPACE,
+    seeds = [b"contribution", crowd_fill.key().as_ref(), contributor.key().as_ref()],
+    bump,
+  )]
+  pub contribution: Box<Account<'info, Contribution>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = crowd_fill,
+  )]
+  pub pool: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> Contribute<'info> {
+  pub fn contribute(&mut self, amount: u64, bumps: ContributeBumps) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp < self.crowd_fill.deadline, ErrorCode::CrowdFillClosed);
+
+    let amount = amount.min(self.crowd_fill.target - self.crowd_fill.raised);
+    require!(amount > 0, ErrorCode::CrowdFillClosed);
+
+    let cpi_accounts = TransferChecked {
+      from: self.contributor_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.pool.to_account_info(),
+      authority: self.contributor.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, amount, self.token_mint_b.decimals)?;
+
+    if self.contribution.amount == 0 {
+      self.contribution.set_inner(Contribution {
+        crowd_fill: self.crowd_fill.key(),
+        contributor: self.contributor.key(),
+        amount: 0,
+        bump: bumps.contribution,
+      });
+      self.crowd_fill.contributors = self.crowd_fill.contributors
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    self.contribution.amount += amount;
+    self.crowd_fill.raised += amount;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::error::ErrorCode;
+use crate::program::Escrow;
+use crate::state::{Book, Market, Side};
+
+
+//Opens the book of a mint pair, only the upgrade authority can create it since
+//its sizes can't be changed afterwards
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+
+  #[account(mut)]
+  pub authority: Signer<'info>,
+
+  #[account(
+    constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program: Program<'info, Escrow>,
+
+  #[account(
+    constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized,
+  )]
+  pub program_data: Account<'info, ProgramData>,
+
+  pub base_mint: InterfaceAccount<'info, Mint>,
+  pub quote_mint: InterfaceAccount<'info, Mint>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = Market::LEN,
+    seeds = [b"market", base_mint.key().as_ref(), quote_mint.key().as_ref()],
+    bump,
+  )]
+  pub market: Box<Account<'info, Market>>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = Book::LEN,
+    seeds = [Book::seed(Side::Ask), market.key().as_ref()],
+    bump,
+  )]
+  pub asks: Box<Account<'info, Book>>,
+
+  #[account(
+    init,
+    payer = authority,
+    space = Book::LEN,
+    seeds = [Book::seed(Side::Bid), market.key().as_ref()],
+    bump,
+  )]
+  pub bids: Box<Account<'info, Book>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateMarket<'info> {
+  pub fn create_market(&mut self, tick_size: u64, lot_size: u64, min_order_size: u64, bumps: CreateMarketBumps) -> Result<()> {
+
+    self.market.set_inner(Market {
+      base_mint: self.base_mint.key(),
+      quote_mint: self.quote_mint.key(),
+      tick_size,
+      lot_size,
+      min_order_size,
+      bump: bumps.market,
+    });
+    require!(self.market.is_valid(), ErrorCode::InvalidMarket);
+
+    self.asks.set_inner(Book {
+      market: self.market.key(),
+      side: Side::Ask,
+      orders: Vec::new(),
+      bump: bumps.asks,
+    });
+    self.bids.set_inner(Book {
+      market: self.market.key(),
+      side: Side::Bid,
+      orders: Vec::new(),
+      bump: bumps.bids,
+    });
+
+    Ok(())
+  }
+}
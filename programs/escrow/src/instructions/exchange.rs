@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create};
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
-use crate::state::{Escrow};
+use crate::constants::{ANCHOR_DESCRIMINATOR, MAX_PROOF_LEN};
+use crate::error::ErrorCode;
+use crate::state::{Allowlist, Book, Escrow, EscrowKind, GateKind, Metadata, PaymentOptions, PriceCurve, PriceFeed, Receipt, Recipients, TokenGate, TOKEN_METADATA_PROGRAM};
 
 
 
@@ -11,27 +13,29 @@ pub struct TakeOffer<'info> {
 #[account(mut)]
 pub taker: Signer<'info>,
 
+//can be a PDA of another program, such as a multisig vault, so it isn't required to be a system account
+/// CHECK: pinned to `escrow.maker`, only used as the authority of its ATA of mint B
 #[account(
   address = escrow.maker,
 )]
-pub maker: SystemAccount<'info>,
+pub maker: UncheckedAccount<'info>,
 
 #[account(
   address = escrow.token_mint_a
 )]
 pub token_mint_a: InterfaceAccount<'info, Mint>,
+//`escrow.token_mint_b` or one of the mints listed in `payment_options`
 #[account(
-  address = escrow.token_mint_b
+  constraint = token_mint_b.key() == escrow.token_mint_b || (
+    escrow.accepts_other_mints && payment_options.as_ref().is_some_and(|options| options.amount_for(&token_mint_b.key()).is_some())
+  ) @ ErrorCode::PaymentMintNotAccepted,
 )]
 pub token_mint_b: InterfaceAccount<'info, Mint>,
 
-#[account(
-  init_if_needed,
-  payer = taker,
-    associated_token::mint = token_mint_a,
-    associated_token::authority = taker,
-)]
-pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+//the taker's ATA of mint A, created when missing, or any other token account of mint A they hold
+/// CHECK: checked in `check_taker_token_account_a`
+#[account(mut)]
+pub taker_token_account_a: UncheckedAccount<'info>,
 
 #[account(
     mut,
@@ -40,20 +44,26 @@ pub taker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
 )]
 pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
 
+//the maker's `destination` or their ATA, which is created when missing
+/// CHECK: pinned to `escrow.proceeds_account`, the mint is checked by the transfer
 #[account(
-  init_if_needed,
-  payer = taker,
-  associated_token::mint = token_mint_b,
-  associated_token::authority = maker,  
+  mut,
+  address = escrow.proceeds_account(&token_mint_b.key(), &token_program.key()) @ ErrorCode::InvalidDestination,
 )]
-pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+pub maker_token_account_b: UncheckedAccount<'info>,
 
 #[account(
 mut, 
 has_one= token_mint_a,
-has_one= token_mint_b,
 seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],  
 bump = escrow.bump, 
+constraint = escrow.kind != EscrowKind::Conditional @ ErrorCode::NotTriggered,
+constraint = escrow.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+constraint = escrow.market == Pubkey::default() || book.is_some() @ ErrorCode::ListedOnMarket,
+constraint = !escrow.allowlisted || (allowlist.is_some() && receipt.is_some()) @ ErrorCode::NotAllowlisted,
+constraint = !escrow.gated || token_gate.is_some() @ ErrorCode::GateNotMet,
+constraint = !escrow.split || recipients.is_some() @ ErrorCode::RecipientsMissing,
+constraint = !escrow.accepts_other_mints || payment_options.is_some() @ ErrorCode::PaymentOptionsMissing,
 close = taker)]
 pub escrow: Box<Account<'info, Escrow>>,
 
@@ -67,11 +77,177 @@ pub escrow: Box<Account<'info, Escrow>>,
 pub token_program: Interface<'info, TokenInterface>,
 pub associated_token_program: Program<'info, AssociatedToken>,
 pub system_program: Program<'info, System>,
+
+//only read for oracle-priced offers and can be left out for the others
+/// CHECK: pinned to `escrow.oracle` and parsed by `PriceFeed::load`
+#[account(
+  address = escrow.oracle @ ErrorCode::InvalidPriceFeed,
+)]
+pub price_feed: Option<UncheckedAccount<'info>>,
+
+//needed, and closed, when the escrow accepts other mints, whichever mint the taker pays in
+#[account(
+  mut,
+  seeds = [b"payments", escrow.key().as_ref()],
+  bump = payment_options.bump,
+  has_one = escrow,
+  close = taker,
+)]
+pub payment_options: Option<Box<Account<'info, PaymentOptions>>>,
+
+//needed when the escrow is listed on a market, to take it off the book
+#[account(
+  mut,
+  constraint = book.market == escrow.market && book.side == escrow.side @ ErrorCode::ListedOnMarket,
+)]
+pub book: Option<Box<Account<'info, Book>>>,
+
+//both needed, and the allowlist closed, when the escrow is allowlisted
+#[account(
+  mut,
+  seeds = [b"allowlist", escrow.key().as_ref()],
+  bump = allowlist.bump,
+  has_one = escrow,
+  close = taker,
+)]
+pub allowlist: Option<Box<Account<'info, Allowlist>>>,
+
+#[account(
+  init_if_needed,
+  payer = taker,
+  space = ANCHOR_DESCRIMINATOR + Receipt::INIT_SPACE,
+  seeds = [b"receipt", escrow.maker.as_ref(), allowlist.as_ref().map(|allowlist| allowlist.root).unwrap_or_default().as_ref(), taker.key().as_ref()],
+  bump,
+)]
+pub receipt: Option<Box<Account<'info, Receipt>>>,
+
+//needed, and closed, when the escrow is token-gated
+#[account(
+  mut,
+  seeds = [b"gate", escrow.key().as_ref()],
+  bump = token_gate.bump,
+  has_one = escrow,
+  close = taker,
+)]
+pub token_gate: Option<Box<Account<'info, TokenGate>>>,
+
+//needed when mint A is an NFT and the escrow enforces royalties, an NFT without
+//metadata passes the empty account at its address
+/// CHECK: pinned to the metadata address of mint A and parsed by `Metadata::load`
+#[account(
+  address = Metadata::address(&token_mint_a.key()) @ ErrorCode::InvalidMetadata,
+)]
+pub metadata: Option<UncheckedAccount<'info>>,
+
+//needed, and closed, when the escrow splits its proceeds
+#[account(
+  mut,
+  seeds = [b"recipients", escrow.key().as_ref()],
+  bump = recipients.bump,
+  has_one = escrow,
+  close = taker,
+)]
+pub recipients: Option<Box<Account<'info, Recipients>>>,
 }
 
 
 impl<'info> TakeOffer<'info> {
-pub fn send_wanted_tokens_to_maker(&mut self) -> Result<()> {
+pub fn check_not_allowlisted(&self) -> Result<()> {
+  require!(!self.escrow.allowlisted, ErrorCode::Allowlisted);
+  Ok(())
+}
+
+pub fn check_allowlist(&mut self, proof: &[[u8; 32]], cap: u64, bumps: TakeOfferBumps) -> Result<()> {
+  if !self.escrow.allowlisted {
+    return Ok(());
+  }
+  let (Some(allowlist), Some(receipt)) = (self.allowlist.as_ref(), self.receipt.as_mut()) else {
+    return err!(ErrorCode::NotAllowlisted);
+  };
+
+  require!(proof.len() <= MAX_PROOF_LEN, ErrorCode::NotAllowlisted);
+  require!(allowlist.verify(proof, Allowlist::leaf(&self.taker.key(), cap)), ErrorCode::NotAllowlisted);
+
+  if receipt.taker == Pubkey::default() {
+    receipt.taker = self.taker.key();
+    receipt.bump = bumps.receipt.ok_or(ErrorCode::NotAllowlisted)?;
+  }
+  receipt.filled = receipt.filled
+    .checked_add(self.vault.amount)
+    .ok_or(ErrorCode::MathOverflow)?;
+  require!(cap == 0 || receipt.filled <= cap, ErrorCode::CapExceeded);
+
+  Ok(())
+}
+
+//The taker's token account of the gating mint comes first in the remaining accounts,
+//followed for collection gates by the metadata of the NFT it holds
+pub fn check_token_gate(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+  if !self.escrow.gated {
+    return Ok(());
+  }
+  let token_gate = self.token_gate.as_ref().ok_or(ErrorCode::GateNotMet)?;
+
+  let holding = remaining_accounts.first().ok_or(ErrorCode::GateNotMet)?;
+  let holding = InterfaceAccount::<TokenAccount>::try_from(holding)?;
+  require_keys_eq!(holding.owner, self.taker.key(), ErrorCode::GateNotMet);
+
+  match token_gate.kind {
+    GateKind::Balance => {
+      require_keys_eq!(holding.mint, token_gate.mint, ErrorCode::GateNotMet);
+      require!(holding.amount >= token_gate.min_amount, ErrorCode::GateNotMet);
+    }
+    GateKind::Collection => {
+      require!(holding.amount > 0, ErrorCode::GateNotMet);
+      let metadata = remaining_accounts.get(1).ok_or(ErrorCode::GateNotMet)?;
+      let metadata = Metadata::load(metadata, &holding.mint)?;
+      require!(metadata.in_collection(&token_gate.mint), ErrorCode::GateNotMet);
+    }
+  }
+
+  Ok(())
+}
+
+pub fn take_from_book(&mut self) -> Result<()> {
+  let Some(book) = self.book.as_mut() else {
+    return Ok(());
+  };
+
+  //any order can be taken, so one the taker can't fill doesn't block the rest of its side,
+  //and orders evicted by better ones are already out of the book
+  book.remove(&self.escrow.key());
+
+  Ok(())
+}
+
+//Royalties go to the [creator, token account of mint B] pairs ending the remaining accounts.
+//The taker pays at most `max_pay` of mint B, royalties included, so the price can't be
+//raised between quoting and landing the transaction.
+pub fn send_wanted_tokens_to_maker(&mut self, max_pay: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+
+  let mut receive_amount = self.receive_amount()?;
+  require!(receive_amount <= max_pay, ErrorCode::MaxPayExceeded);
+
+  if self.escrow.owes_royalties(&self.token_mint_a) {
+    let metadata = self.metadata.as_ref().ok_or(ErrorCode::RoyaltiesOwed)?;
+    let payment = RoyaltyPayment {
+      taker: self.taker.to_account_info(),
+      taker_token_account_b: self.taker_token_account_b.to_account_info(),
+      token_mint_b: &self.token_mint_b,
+      token_program: self.token_program.to_account_info(),
+      associated_token_program: self.associated_token_program.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+    };
+    receive_amount = payment.pay(metadata, &self.token_mint_a.key(), receive_amount, remaining_accounts)?;
+  }
+
+  if self.escrow.split {
+    return self.send_to_recipients(receive_amount, remaining_accounts);
+  }
+
+  if self.maker_token_account_b.key() != self.escrow.destination {
+    self.create_ata(self.maker_token_account_b.to_account_info(), self.maker.to_account_info(), &self.token_mint_b)?;
+  }
 
   // Transfer the wanted tokens from the taker to the maker
     let cpi_accounts = TransferChecked {
@@ -82,11 +258,79 @@ pub fn send_wanted_tokens_to_maker(&mut self) -> Result<()> {
     };
     let cpi_program = self.token_program.to_account_info();
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    transfer_checked(cpi_ctx, self.escrow.receive_amount , self.token_mint_b.decimals)?;
+    transfer_checked(cpi_ctx, receive_amount, self.token_mint_b.decimals)?;
     Ok(())
 
 }
 
+//Each recipient's wallet and ATA of mint B have to be among the remaining accounts, in any
+//position. The taker creates the ATAs that are missing.
+fn send_to_recipients(&self, receive_amount: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+  let recipients = self.recipients.as_ref().ok_or(ErrorCode::RecipientsMissing)?;
+  let find = |key: Pubkey| remaining_accounts.iter().find(|account| account.key() == key).ok_or(ErrorCode::RecipientsMissing);
+
+  for (wallet, amount) in recipients.split(receive_amount) {
+    if amount == 0 {
+      continue;
+    }
+    let address = get_associated_token_address_with_program_id(&wallet, &self.token_mint_b.key(), &self.token_program.key());
+    let account = find(address)?;
+    self.create_ata(account.clone(), find(wallet)?.clone(), &self.token_mint_b)?;
+
+    let cpi_accounts = TransferChecked {
+      from: self.taker_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: account.clone(),
+      authority: self.taker.to_account_info(),
+    };
+    transfer_checked(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount, self.token_mint_b.decimals)?;
+  }
+
+  Ok(())
+}
+
+fn receive_amount(&self) -> Result<u64> {
+  if self.token_mint_b.key() != self.escrow.token_mint_b {
+    let payment_options = self.payment_options.as_ref().ok_or(ErrorCode::PaymentMintNotAccepted)?;
+    return payment_options.amount_for(&self.token_mint_b.key()).ok_or(error!(ErrorCode::PaymentMintNotAccepted));
+  }
+
+  let now = Clock::get()?.unix_timestamp;
+  if self.escrow.price_curve != PriceCurve::Oracle {
+    return Ok(self.escrow.receive_amount_at(now));
+  }
+
+  let price_feed = self.price_feed.as_ref().ok_or(ErrorCode::InvalidPriceFeed)?;
+  let feed = PriceFeed::load(price_feed)?;
+  self.escrow.oracle_receive_amount(&feed, self.vault.amount, self.token_mint_a.decimals, self.token_mint_b.decimals, now)
+}
+
+//The taker may be paid into any token account of mint A they hold, their ATA is created when missing
+pub fn check_taker_token_account_a(&self) -> Result<()> {
+  let ata = get_associated_token_address_with_program_id(&self.taker.key(), &self.token_mint_a.key(), &self.token_program.key());
+  if self.taker_token_account_a.key() == ata {
+    return self.create_ata(self.taker_token_account_a.to_account_info(), self.taker.to_account_info(), &self.token_mint_a);
+  }
+
+  require_keys_eq!(*self.taker_token_account_a.owner, self.token_program.key(), ErrorCode::InvalidDestination);
+  let account = TokenAccount::try_deserialize(&mut &self.taker_token_account_a.try_borrow_data()?[..])?;
+  require!(account.mint == self.token_mint_a.key() && account.owner == self.taker.key(), ErrorCode::InvalidDestination);
+
+  Ok(())
+}
+
+fn create_ata(&self, account: AccountInfo<'info>, authority: AccountInfo<'info>, mint: &InterfaceAccount<'info, Mint>) -> Result<()> {
+  let cpi_accounts = Create {
+    payer: self.taker.to_account_info(),
+    associated_token: account,
+    authority,
+    mint: mint.to_account_info(),
+    system_program: self.system_program.to_account_info(),
+    token_program: self.token_program.to_account_info(),
+  };
+  create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))
+}
+
 pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
 
   let escrow = self.escrow.to_account_info();
@@ -131,4 +375,64 @@ close_account(cpi_context)?;
 }
 
 
-//Make Refund to cancel escrow
\ No newline at end of file
+//What paying an NFT's creators in mint B takes, shared with `exchange_pnft`
+pub(crate) struct RoyaltyPayment<'a, 'info> {
+  pub taker: AccountInfo<'info>,
+  pub taker_token_account_b: AccountInfo<'info>,
+  pub token_mint_b: &'a InterfaceAccount<'info, Mint>,
+  pub token_program: AccountInfo<'info>,
+  pub associated_token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
+
+impl<'a, 'info> RoyaltyPayment<'a, 'info> {
+  //Pays every creator with a share their cut of `price` into their ATA of mint B, which
+  //is created when missing, and returns what is left for the maker. A [creator, ATA]
+  //pair for each of them ends `remaining_accounts`, in the order of the metadata.
+  pub fn pay(&self, metadata: &AccountInfo<'info>, token_mint_a: &Pubkey, price: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<u64> {
+
+    //an NFT minted without metadata has no creators to pay
+    if metadata.owner != &TOKEN_METADATA_PROGRAM && metadata.data_is_empty() {
+      return Ok(price);
+    }
+    let royalties = Metadata::load(metadata, token_mint_a)?.royalties(price);
+
+    let first = remaining_accounts.len().checked_sub(2 * royalties.len()).ok_or(ErrorCode::RoyaltiesOwed)?;
+    let mut left = price;
+
+    for ((creator, amount), accounts) in royalties.into_iter().zip(remaining_accounts[first..].chunks_exact(2)) {
+      let (wallet, account) = (&accounts[0], &accounts[1]);
+      require_keys_eq!(wallet.key(), creator, ErrorCode::RoyaltiesOwed);
+      require_keys_eq!(
+        account.key(),
+        get_associated_token_address_with_program_id(&creator, &self.token_mint_b.key(), &self.token_program.key()),
+        ErrorCode::RoyaltiesOwed
+      );
+      if amount == 0 {
+        continue;
+      }
+
+      let cpi_accounts = Create {
+        payer: self.taker.clone(),
+        associated_token: account.clone(),
+        authority: wallet.clone(),
+        mint: self.token_mint_b.to_account_info(),
+        system_program: self.system_program.clone(),
+        token_program: self.token_program.clone(),
+      };
+      create_idempotent(CpiContext::new(self.associated_token_program.clone(), cpi_accounts))?;
+
+      let cpi_accounts = TransferChecked {
+        from: self.taker_token_account_b.clone(),
+        mint: self.token_mint_b.to_account_info(),
+        to: account.clone(),
+        authority: self.taker.clone(),
+      };
+      transfer_checked(CpiContext::new(self.token_program.clone(), cpi_accounts), amount, self.token_mint_b.decimals)?;
+
+      left -= amount;
+    }
+
+    Ok(left)
+  }
+}
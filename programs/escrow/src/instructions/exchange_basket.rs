@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::Basket;
+
+
+//Takes a whole basket: every wanted leg is paid and every vault emptied, or nothing is.
+//remaining_accounts: [token_mint, vault, taker_token_account] for every offered mint,
+//then [token_mint, taker_token_account, maker_token_account] for every wanted leg,
+//both in the basket's order
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+
+  #[account(mut)]
+  pub taker: Signer<'info>,
+
+  //can be a PDA of another program, such as a multisig vault, so it isn't required to be a system account
+  /// CHECK: pinned to `basket.maker`, only owns the wanted token accounts
+  #[account(
+    address = basket.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"basket", basket.maker.as_ref(), basket.seed.to_le_bytes().as_ref()],
+    bump = basket.bump,
+    close = taker,
+  )]
+  pub basket: Box<Account<'info, Basket>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBasket<'info> {
+  pub fn take_basket(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+
+    let offered = self.basket.offered_mints.len();
+    let groups = remaining_accounts.chunks_exact(3);
+    require!(
+      groups.len() == offered + self.basket.wanted.len() && groups.remainder().is_empty(),
+      ErrorCode::InvalidRemainingAccounts
+    );
+
+    for (accounts, leg) in groups.clone().skip(offered).zip(self.basket.wanted.iter()) {
+      let token_mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+      require_keys_eq!(token_mint.key(), leg.mint, ErrorCode::EscrowMismatch);
+
+      self.create_token_account(&token_mint, &accounts[2], &self.maker.to_account_info())?;
+
+      let cpi_accounts = TransferChecked {
+        from: accounts[1].clone(),
+        mint: token_mint.to_account_info(),
+        to: accounts[2].clone(),
+        authority: self.taker.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+      transfer_checked(cpi_ctx, leg.amount, token_mint.decimals)?;
+    }
+
+    for (accounts, mint) in groups.take(offered).zip(self.basket.offered_mints.iter()) {
+      let token_mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+      require_keys_eq!(token_mint.key(), *mint, ErrorCode::EscrowMismatch);
+
+      let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+      require_keys_eq!(
+        vault.key(),
+        get_associated_token_address_with_program_id(&self.basket.key(), mint, &self.token_program.key()),
+        ErrorCode::EscrowMismatch
+      );
+
+      self.create_token_account(&token_mint, &accounts[2], &self.taker.to_account_info())?;
+      self.withdraw_and_close_vault(&token_mint, &vault, &accounts[2])?;
+    }
+
+    Ok(())
+  }
+
+  //The associated token account program checks the address itself
+  fn create_token_account(&self, token_mint: &InterfaceAccount<'info, Mint>, token_account: &AccountInfo<'info>, authority: &AccountInfo<'info>) -> Result<()> {
+
+    let cpi_accounts = Create {
+      payer: self.taker.to_account_info(),
+      associated_token: token_account.clone(),
+      authority: authority.clone(),
+      mint: token_mint.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+      token_program: self.token_program.to_account_info(),
+    };
+    create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))
+  }
+
+  fn withdraw_and_close_vault(&self, token_mint: &InterfaceAccount<'info, Mint>, vault: &InterfaceAccount<'info, TokenAccount>, taker_token_account: &AccountInfo<'info>) -> Result<()> {
+
+    let seed_bytes = self.basket.seed.to_le_bytes();
+    let seeds = &[b"basket", self.basket.maker.as_ref(), seed_bytes.as_ref(), &[self.basket.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: vault.to_account_info(),
+      mint: token_mint.to_account_info(),
+      to: taker_token_account.clone(),
+      authority: self.basket.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, vault.amount, token_mint.decimals)?;
+
+    let cpi_accounts = CloseAccount {
+      account: vault.to_account_info(),
+      destination: self.taker.to_account_info(),
+      authority: self.basket.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)
+  }
+}
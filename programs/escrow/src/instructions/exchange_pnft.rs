@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenAccount, TransferChecked, transfer_checked};
+use crate::error::ErrorCode;
+use crate::instructions::exchange::RoyaltyPayment;
+use crate::instructions::make_pnft::withdraw_pnft;
+use crate::state::{edition_address, token_record_address, Escrow, EscrowKind, Metadata, TransferPnft, TOKEN_METADATA_PROGRAM};
+
+
+//Pays `receive_amount` of mint B for the pNFT held by a `ProgrammableNft` escrow
+#[derive(Accounts)]
+pub struct TakePnft<'info> {
+
+  #[account(mut)]
+  pub taker: Signer<'info>,
+
+  //can be a PDA of another program, such as a multisig vault, so it isn't required to be a system account
+  /// CHECK: pinned to `escrow.maker`, only receives rent and owns `maker_token_account_b`
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  /// CHECK: the taker's ATA of the pNFT, Token Metadata creates it in the transfer
+  #[account(
+    mut,
+    address = get_associated_token_address(&taker.key(), &token_mint_a.key()),
+  )]
+  pub taker_token_account_a: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = taker,
+  )]
+  pub taker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = taker,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::ProgrammableNft @ ErrorCode::NotAProgrammableNft,
+    close = taker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  /// CHECK: the escrow's ATA of the pNFT
+  #[account(
+    mut,
+    address = get_associated_token_address(&escrow.key(), &token_mint_a.key()),
+  )]
+  pub vault: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the metadata address of mint A, parsed by `Metadata::load` for royalties
+  #[account(
+    mut,
+    address = Metadata::address(&token_mint_a.key()) @ ErrorCode::InvalidMetadata,
+  )]
+  pub metadata: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the pNFT's master edition
+  #[account(
+    address = edition_address(&token_mint_a.key()),
+  )]
+  pub edition: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the vault
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &vault.key()),
+  )]
+  pub vault_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the taker's token account
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &taker_token_account_a.key()),
+  )]
+  pub taker_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: the instructions sysvar, read by Token Metadata
+  #[account(
+    address = sysvar::instructions::ID,
+  )]
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  /// CHECK: the Token Metadata program
+  #[account(
+    address = TOKEN_METADATA_PROGRAM,
+  )]
+  pub token_metadata_program: UncheckedAccount<'info>,
+
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  //only needed when the pNFT has a rule set
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> TakePnft<'info> {
+  //Royalties go to the [creator, token account of mint B] pairs in the remaining accounts
+  pub fn send_wanted_tokens_to_maker(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+    let mut receive_amount = self.escrow.receive_amount;
+
+    if self.escrow.owes_royalties(&self.token_mint_a) {
+      let payment = RoyaltyPayment {
+        taker: self.taker.to_account_info(),
+        taker_token_account_b: self.taker_token_account_b.to_account_info(),
+        token_mint_b: &self.token_mint_b,
+        token_program: self.token_program.to_account_info(),
+        associated_token_program: self.associated_token_program.to_account_info(),
+        system_program: self.system_program.to_account_info(),
+      };
+      receive_amount = payment.pay(&self.metadata, &self.token_mint_a.key(), receive_amount, remaining_accounts)?;
+    }
+
+    let cpi_accounts = TransferChecked {
+      from: self.taker_token_account_b.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.maker_token_account_b.to_account_info(),
+      authority: self.taker.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, receive_amount, self.token_mint_b.decimals)
+  }
+
+  pub fn withdraw(&self) -> Result<()> {
+    let transfer = TransferPnft {
+      token: self.vault.to_account_info(),
+      token_owner: self.escrow.to_account_info(),
+      destination_token: self.taker_token_account_a.to_account_info(),
+      destination_owner: self.taker.to_account_info(),
+      mint: self.token_mint_a.to_account_info(),
+      metadata: self.metadata.to_account_info(),
+      edition: self.edition.to_account_info(),
+      token_record: self.vault_token_record.to_account_info(),
+      destination_token_record: self.taker_token_record.to_account_info(),
+      authority: self.escrow.to_account_info(),
+      payer: self.taker.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+      sysvar_instructions: self.sysvar_instructions.to_account_info(),
+      token_program: self.token_program.to_account_info(),
+      associated_token_program: self.associated_token_program.to_account_info(),
+      authorization_rules_program: self.authorization_rules_program.as_ref().map(|program| program.to_account_info()),
+      authorization_rules: self.authorization_rules.as_ref().map(|rules| rules.to_account_info()),
+      token_metadata_program: self.token_metadata_program.to_account_info(),
+    };
+
+    withdraw_pnft(&self.escrow, transfer, self.taker.to_account_info())
+  }
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund;
+use crate::state::{Escrow, EscrowKind, SealedAuction};
+
+
+//Anyone can close a sealed auction once the reveal window is over without a valid bid,
+//so the bidders' commitments can be claimed even if the maker never cancels it.
+//The vault goes back to the maker.
+#[derive(Accounts)]
+pub struct ExpireSealedAuction<'info> {
+
+  #[account(mut)]
+  pub closer: Signer<'info>,
+
+  //can be a PDA of another program, so it isn't required to be a system account
+  /// CHECK: pinned to `escrow.maker`, only receives rent and owns its ATA of mint A
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = closer,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::SealedAuction @ ErrorCode::NotASealedAuction,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", escrow.key().as_ref()],
+    bump = sealed_auction.bump,
+    has_one = escrow,
+    constraint = !sealed_auction.settled @ ErrorCode::AuctionSettled,
+    constraint = !sealed_auction.has_bids() @ ErrorCode::AuctionHasBids,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ExpireSealedAuction<'info> {
+  pub fn withdraw_and_close_vault(&self) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp >= self.sealed_auction.reveal_end, ErrorCode::AuctionNotEnded);
+
+    refund::withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+  pub fn close_auction(&mut self) -> Result<()> {
+
+    if self.sealed_auction.commitments == 0 {
+      return self.sealed_auction.close(self.maker.to_account_info());
+    }
+    self.sealed_auction.settled = true;
+
+    Ok(())
+  }
+}
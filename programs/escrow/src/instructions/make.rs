@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
-use crate::state::Escrow;
+use crate::constants::MAX_TRIGGER_EXPO;
+use crate::error::ErrorCode;
+use crate::state::{Book, BookOrder, Escrow, EscrowKind, Market, MintPolicy, PriceCurve, RoyaltyPolicy, Side, TriggerDirection, TriggerParams};
 
 
 #[derive(Accounts)]
@@ -24,7 +26,7 @@ pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
 #[account(
   init, 
   payer = maker, 
-  space = 8 + Escrow::INIT_SPACE,
+  space = Escrow::LEN,
   seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],  
   bump
 )]
@@ -41,12 +43,36 @@ pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
 pub token_program: Interface<'info, TokenInterface>,
 pub associated_token_program: Program<'info, AssociatedToken>,
 pub system_program: Program<'info, System>,
+
+/// CHECK: pinned by its seeds and deserialized by `MintPolicy::enforce`, which falls
+/// back to `MintPolicy::DEFAULT_REJECT` until the policy is configured
+#[account(
+  seeds = [b"mint_policy"],
+  bump,
+)]
+pub mint_policy: UncheckedAccount<'info>,
+
+//both passed to list a plain offer or bid on the market of its pair
+pub market: Option<Box<Account<'info, Market>>>,
+
+#[account(mut)]
+pub book: Option<Box<Account<'info, Book>>>,
+
+//pays the maker into this token account of mint B, such as a multisig vault, instead
+//of their ATA. It can't be changed once the offer is made.
+#[account(
+  constraint = destination.mint == token_mint_b.key() @ ErrorCode::InvalidDestination,
+  constraint = destination.to_account_info().owner == &token_program.key() @ ErrorCode::InvalidDestination,
+)]
+pub destination: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 }
 
 
 
 impl<'info> MakeOffer<'info> {
   pub fn init_escrow(&mut self, seed: u64, receive: u64, bumps: MakeOfferBumps) -> Result<()> {
+    let mint_risks = self.check_mints()?;
+
     self.escrow.set_inner(Escrow {
         seed,
         maker: self.maker.key(),
@@ -54,19 +80,136 @@ impl<'info> MakeOffer<'info> {
         token_mint_b: self.token_mint_b.key(),
         receive_amount: receive,
         bump: bumps.escrow,
+        version: Escrow::VERSION,
+        price_curve: PriceCurve::Fixed,
+        floor_amount: 0,
+        start_time: 0,
+        end_time: 0,
+        kind: EscrowKind::Offer,
+        oracle: Pubkey::default(),
+        spread_bps: 0,
+        max_age: 0,
+        max_confidence_bps: 0,
+        trigger_price: 0,
+        trigger_expo: 0,
+        trigger_direction: TriggerDirection::Below,
+        trigger_tip: 0,
+        accepts_other_mints: false,
+        side: Side::Ask,
+        market: Pubkey::default(),
+        allowlisted: false,
+        gated: false,
+        mint_risks,
+        royalty_policy: RoyaltyPolicy::Enforced,
+        split: false,
+        destination: self.destination.as_ref().map_or(Pubkey::default(), |destination| destination.key()),
+        reserved: [0; 94],
     });
 
-    msg!("Escrow account created");
-    msg!("Escrow account: {:?}", self.escrow.key());
-    msg!("token_mint_a: {:?}", self.token_mint_a.key());
-    msg!("token_mint_b: {:?}", self.token_mint_b.key());
-    msg!("maker_token_account_a: {:?}", self.maker_token_account_a.key());
-    msg!("vault: {:?}", self.vault.key());
-    msg!("-----------------------------------------------------------------");
-
     Ok(())
 }
 
+  //Refuses the mints whose risks the deployment's policy rejects and returns the rest
+  fn check_mints(&self) -> Result<u8> {
+    let risks = MintPolicy::risks(&self.token_mint_a.to_account_info().try_borrow_data()?)?
+      | MintPolicy::risks(&self.token_mint_b.to_account_info().try_borrow_data()?)?;
+
+    MintPolicy::enforce(&self.mint_policy, risks)?;
+
+    Ok(risks)
+  }
+
+  //Turns the offer into a dutch auction whose price falls from `receive_amount` to `floor`
+  pub fn set_price_curve(&mut self, curve: PriceCurve, floor: u64, start_time: i64, end_time: i64) -> Result<()> {
+    require!(
+      matches!(curve, PriceCurve::Linear | PriceCurve::Exponential) && floor <= self.escrow.receive_amount && start_time < end_time,
+      ErrorCode::InvalidPriceCurve
+    );
+
+    self.escrow.price_curve = curve;
+    self.escrow.floor_amount = floor;
+    self.escrow.start_time = start_time;
+    self.escrow.end_time = end_time;
+
+    Ok(())
+  }
+
+  //Makes the escrow a buy order: `token_mint_a` is the quote mint being locked and
+  //`receive_amount` is how much of the base, `token_mint_b`, the maker wants.
+  //Only `side` changes, so a seller fills the whole bid with `exchange` or part of it
+  //with `batch_exchange`, and the buyer refunds it with `refund`.
+  pub fn set_bid(&mut self) -> Result<()> {
+    self.escrow.side = Side::Bid;
+    Ok(())
+  }
+
+  //Prices the offer off `oracle` when it is taken instead of a fixed `receive_amount`
+  pub fn set_oracle(&mut self, oracle: Pubkey, spread_bps: u16, max_age: i64, max_confidence_bps: u16) -> Result<()> {
+    require!(max_age >= 0 && max_confidence_bps <= 10_000, ErrorCode::InvalidPriceFeed);
+
+    self.escrow.price_curve = PriceCurve::Oracle;
+    self.escrow.oracle = oracle;
+    self.escrow.spread_bps = spread_bps;
+    self.escrow.max_age = max_age;
+    self.escrow.max_confidence_bps = max_confidence_bps;
+
+    Ok(())
+  }
+
+  //Holds the offer back until `trigger` sees the oracle cross the trigger price
+  pub fn set_trigger(&mut self, trigger: TriggerParams, deposit: u64) -> Result<()> {
+    require!(
+      trigger.price > 0
+        && trigger.expo.abs() <= MAX_TRIGGER_EXPO
+        && trigger.tip < deposit
+        && trigger.max_age >= 0
+        && trigger.max_confidence_bps <= 10_000,
+      ErrorCode::InvalidTrigger
+    );
+
+    self.escrow.kind = EscrowKind::Conditional;
+    self.escrow.oracle = trigger.oracle;
+    self.escrow.max_age = trigger.max_age;
+    self.escrow.max_confidence_bps = trigger.max_confidence_bps;
+    self.escrow.trigger_price = trigger.price;
+    self.escrow.trigger_expo = trigger.expo;
+    self.escrow.trigger_direction = trigger.direction;
+    self.escrow.trigger_tip = trigger.tip;
+
+    Ok(())
+  }
+
+  //Inserts the escrow into its side of the market's book, a no-op when no market was passed
+  pub fn list(&mut self, deposit: u64) -> Result<()> {
+    let (market, book) = match (&self.market, &mut self.book) {
+      (Some(market), Some(book)) => (market, book),
+      (None, None) => return Ok(()),
+      _ => return err!(ErrorCode::InvalidMarket),
+    };
+
+    require_keys_eq!(book.market, market.key(), ErrorCode::InvalidMarket);
+    require!(book.side == self.escrow.side, ErrorCode::InvalidMarket);
+    require_keys_eq!(self.escrow.base_mint(), market.base_mint, ErrorCode::InvalidMarket);
+    require_keys_eq!(self.escrow.quote_mint(), market.quote_mint, ErrorCode::InvalidMarket);
+
+    let (base, quote) = match self.escrow.side {
+      Side::Ask => (deposit, self.escrow.receive_amount),
+      Side::Bid => (self.escrow.receive_amount, deposit),
+    };
+    let price = market.price(base, quote).ok_or(ErrorCode::OffMarketGrid)?;
+
+    let order = BookOrder {
+      escrow: self.escrow.key(),
+      price,
+      size: base,
+    };
+    require!(book.insert(order), ErrorCode::BookFull);
+
+    self.escrow.market = market.key();
+
+    Ok(())
+  }
+
 pub fn deposit(&mut self, deposit: u64) -> Result<()> {
 
 
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create};
+use anchor_spl::token_interface::{Mint, TokenInterface, TransferChecked, transfer_checked};
+use crate::error::ErrorCode;
+use crate::state::{Basket, Leg, MintPolicy};
+
+
+//Offers several mints at once for several others.
+//remaining_accounts: [token_mint, maker_token_account, vault] for every offered mint,
+//then the mint of every wanted leg so the mint policy can check it
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBasket<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = Basket::LEN,
+    seeds = [b"basket", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+    bump
+  )]
+  pub basket: Box<Account<'info, Basket>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: pinned by its seeds and deserialized by `MintPolicy::enforce`, which falls
+  /// back to `MintPolicy::DEFAULT_REJECT` until the policy is configured
+  #[account(
+    seeds = [b"mint_policy"],
+    bump,
+  )]
+  pub mint_policy: UncheckedAccount<'info>,
+}
+
+impl<'info> MakeBasket<'info> {
+  pub fn make_basket(&mut self, remaining_accounts: &'info [AccountInfo<'info>], seed: u64, deposits: Vec<u64>, wanted: Vec<Leg>, bumps: MakeBasketBumps) -> Result<()> {
+
+    require!(
+      remaining_accounts.len() == deposits.len() * 3 + wanted.len(),
+      ErrorCode::InvalidRemainingAccounts
+    );
+    let (offered_accounts, wanted_mints) = remaining_accounts.split_at(deposits.len() * 3);
+    let groups = offered_accounts.chunks_exact(3);
+
+    let offered_mints: Vec<Pubkey> = groups.clone().map(|accounts| accounts[0].key()).collect();
+    require!(Basket::is_valid(&offered_mints, &wanted), ErrorCode::InvalidBasket);
+    self.check_mints(offered_accounts, wanted_mints, &wanted)?;
+
+    for (accounts, deposit) in groups.zip(deposits) {
+      let token_mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+      self.deposit(&token_mint, &accounts[1], &accounts[2], deposit)?;
+    }
+
+    self.basket.set_inner(Basket {
+      seed,
+      maker: self.maker.key(),
+      offered_mints,
+      wanted,
+      bump: bumps.basket,
+    });
+
+    Ok(())
+  }
+
+  //Refuses the basket when any of its mints, offered or wanted, has a risk the policy rejects
+  fn check_mints(&self, offered_accounts: &'info [AccountInfo<'info>], wanted_mints: &'info [AccountInfo<'info>], wanted: &[Leg]) -> Result<()> {
+
+    let mut risks = 0;
+    for accounts in offered_accounts.chunks_exact(3) {
+      risks |= MintPolicy::risks(&accounts[0].try_borrow_data()?)?;
+    }
+    for (token_mint, leg) in wanted_mints.iter().zip(wanted) {
+      require_keys_eq!(token_mint.key(), leg.mint, ErrorCode::EscrowMismatch);
+      InterfaceAccount::<Mint>::try_from(token_mint)?;
+      risks |= MintPolicy::risks(&token_mint.try_borrow_data()?)?;
+    }
+
+    MintPolicy::enforce(&self.mint_policy, risks)
+  }
+
+  fn deposit(&self, token_mint: &InterfaceAccount<'info, Mint>, maker_token_account: &AccountInfo<'info>, vault: &AccountInfo<'info>, amount: u64) -> Result<()> {
+
+    require_keys_eq!(
+      vault.key(),
+      get_associated_token_address_with_program_id(&self.basket.key(), &token_mint.key(), &self.token_program.key()),
+      ErrorCode::EscrowMismatch
+    );
+
+    let cpi_accounts = Create {
+      payer: self.maker.to_account_info(),
+      associated_token: vault.clone(),
+      authority: self.basket.to_account_info(),
+      mint: token_mint.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+      token_program: self.token_program.to_account_info(),
+    };
+    create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))?;
+
+    let cpi_accounts = TransferChecked {
+      from: maker_token_account.clone(),
+      mint: token_mint.to_account_info(),
+      to: vault.clone(),
+      authority: self.maker.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+    transfer_checked(cpi_ctx, amount, token_mint.decimals)
+  }
+}
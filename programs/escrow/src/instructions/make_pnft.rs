@@ -0,0 +1,203 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenAccount, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::{edition_address, token_record_address, Escrow, EscrowKind, Metadata, MintPolicy, PriceCurve, RoyaltyPolicy, Side, TransferPnft, TriggerDirection, TOKEN_METADATA_PROGRAM};
+
+
+//Offers a programmable NFT for `receive` of mint B. The pNFT is moved into the vault
+//through Token Metadata, so its rule set has to allow the escrow program.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakePnft<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  //paid through `token_program` by `exchange_pnft`
+  #[account(
+    mint::token_program = token_program,
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = Escrow::LEN,
+    seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+    bump
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  /// CHECK: the escrow's ATA of the pNFT, Token Metadata creates it in the transfer
+  #[account(
+    mut,
+    address = get_associated_token_address(&escrow.key(), &token_mint_a.key()),
+  )]
+  pub vault: UncheckedAccount<'info>,
+
+  /// CHECK: parsed by `Metadata::load`
+  #[account(mut)]
+  pub metadata: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the pNFT's master edition
+  #[account(
+    address = edition_address(&token_mint_a.key()),
+  )]
+  pub edition: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the maker's token account
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &maker_token_account_a.key()),
+  )]
+  pub maker_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the vault
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &vault.key()),
+  )]
+  pub vault_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: the instructions sysvar, read by Token Metadata
+  #[account(
+    address = sysvar::instructions::ID,
+  )]
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  /// CHECK: the Token Metadata program
+  #[account(
+    address = TOKEN_METADATA_PROGRAM,
+  )]
+  pub token_metadata_program: UncheckedAccount<'info>,
+
+  //pNFTs are SPL Token mints, so mint B has to be one too
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: pinned by its seeds and deserialized by `MintPolicy::enforce`, which falls
+  /// back to `MintPolicy::DEFAULT_REJECT` until the policy is configured
+  #[account(
+    seeds = [b"mint_policy"],
+    bump,
+  )]
+  pub mint_policy: UncheckedAccount<'info>,
+
+  //only needed when the pNFT has a rule set, Token Metadata checks them against it
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> MakePnft<'info> {
+  pub fn init_escrow(&mut self, seed: u64, receive: u64, bumps: MakePnftBumps) -> Result<()> {
+
+    let metadata = Metadata::load(&self.metadata, &self.token_mint_a.key())?;
+    require!(metadata.is_programmable(), ErrorCode::NotAProgrammableNft);
+
+    //mint A's freeze authority is its master edition, which is how pNFTs work
+    let mint_risks = MintPolicy::risks(&self.token_mint_b.to_account_info().try_borrow_data()?)?;
+    MintPolicy::enforce(&self.mint_policy, mint_risks)?;
+
+    self.escrow.set_inner(Escrow {
+        seed,
+        maker: self.maker.key(),
+        token_mint_a: self.token_mint_a.key(),
+        token_mint_b: self.token_mint_b.key(),
+        receive_amount: receive,
+        bump: bumps.escrow,
+        version: Escrow::VERSION,
+        price_curve: PriceCurve::Fixed,
+        floor_amount: 0,
+        start_time: 0,
+        end_time: 0,
+        kind: EscrowKind::ProgrammableNft,
+        oracle: Pubkey::default(),
+        spread_bps: 0,
+        max_age: 0,
+        max_confidence_bps: 0,
+        trigger_price: 0,
+        trigger_expo: 0,
+        trigger_direction: TriggerDirection::Below,
+        trigger_tip: 0,
+        accepts_other_mints: false,
+        side: Side::Ask,
+        market: Pubkey::default(),
+        allowlisted: false,
+        gated: false,
+        mint_risks,
+        royalty_policy: RoyaltyPolicy::Enforced,
+        split: false,
+        destination: Pubkey::default(),
+        reserved: [0; 94],
+    });
+
+    Ok(())
+  }
+
+  pub fn deposit(&self) -> Result<()> {
+    TransferPnft {
+      token: self.maker_token_account_a.to_account_info(),
+      token_owner: self.maker.to_account_info(),
+      destination_token: self.vault.to_account_info(),
+      destination_owner: self.escrow.to_account_info(),
+      mint: self.token_mint_a.to_account_info(),
+      metadata: self.metadata.to_account_info(),
+      edition: self.edition.to_account_info(),
+      token_record: self.maker_token_record.to_account_info(),
+      destination_token_record: self.vault_token_record.to_account_info(),
+      authority: self.maker.to_account_info(),
+      payer: self.maker.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+      sysvar_instructions: self.sysvar_instructions.to_account_info(),
+      token_program: self.token_program.to_account_info(),
+      associated_token_program: self.associated_token_program.to_account_info(),
+      authorization_rules_program: self.authorization_rules_program.as_ref().map(|program| program.to_account_info()),
+      authorization_rules: self.authorization_rules.as_ref().map(|rules| rules.to_account_info()),
+      token_metadata_program: self.token_metadata_program.to_account_info(),
+    }
+    .invoke_signed(1, &[])
+  }
+}
+
+//Moves the pNFT out of the vault, signed by the escrow, then closes the vault to
+//`rent_to` unless Token Metadata already closed it in the transfer
+pub(crate) fn withdraw_pnft<'info>(
+  escrow: &Account<'info, Escrow>,
+  transfer: TransferPnft<'info>,
+  rent_to: AccountInfo<'info>,
+) -> Result<()> {
+
+  let seed = escrow.seed.to_le_bytes();
+  let bump = escrow.bump;
+
+  let seeds = &[b"escrow", escrow.maker.as_ref(), seed.as_ref(), &[bump]];
+  let signer_seeds = &[&seeds[..]];
+
+  transfer.invoke_signed(1, signer_seeds)?;
+
+  if transfer.token.lamports() == 0 {
+    return Ok(());
+  }
+
+  let accounts = CloseAccount {
+    account: transfer.token.clone(),
+    destination: rent_to,
+    authority: escrow.to_account_info(),
+  };
+  close_account(CpiContext::new_with_signer(transfer.token_program.clone(), accounts, signer_seeds))
+}
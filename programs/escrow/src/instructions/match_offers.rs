@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::{close_vault, transfer_from_vault};
+use crate::state::{Escrow, EscrowKind, MatchConfig, MatchFill, PriceCurve};
+
+
+//Settles two crossing offers vault to vault: `escrow_a` sells mint A for B and
+//`escrow_b` sells B for A. The keeper needs no capital and earns the spread, or
+//its share of it under `MatchConfig`. A fully filled offer is closed, with its
+//rent going to the keeper like a taker's, the other keeps its remaining price.
+#[derive(Accounts)]
+pub struct MatchOffers<'info> {
+
+  #[account(mut)]
+  pub keeper: Signer<'info>,
+
+  //either maker can be a PDA of another program, so they aren't required to be system accounts
+  /// CHECK: pinned to `escrow_a.maker`, only used as the authority of its ATA of mint B
+  #[account(
+    address = escrow_a.maker,
+  )]
+  pub maker_a: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to `escrow_b.maker`, only used as the authority of its ATA of mint A
+  #[account(
+    address = escrow_b.maker,
+  )]
+  pub maker_b: UncheckedAccount<'info>,
+
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    constraint = token_mint_b.key() != token_mint_a.key() @ ErrorCode::InvalidMatch,
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow_a.maker.as_ref(), escrow_a.seed.to_le_bytes().as_ref()],
+    bump = escrow_a.bump,
+    has_one = token_mint_a,
+    has_one = token_mint_b,
+    constraint = escrow_a.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow_a.price_curve == PriceCurve::Fixed && !escrow_a.accepts_other_mints @ ErrorCode::InvalidMatch,
+    constraint = escrow_a.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+    constraint = !escrow_a.allowlisted @ ErrorCode::Allowlisted,
+    constraint = !escrow_a.gated @ ErrorCode::TokenGated,
+    constraint = !escrow_a.split @ ErrorCode::SplitsProceeds,
+    constraint = escrow_a.destination == Pubkey::default() @ ErrorCode::CustomDestination,
+    constraint = !escrow_a.owes_royalties(&token_mint_a) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow_a: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow_a,
+  )]
+  pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow_b.maker.as_ref(), escrow_b.seed.to_le_bytes().as_ref()],
+    bump = escrow_b.bump,
+    constraint = escrow_b.token_mint_a == token_mint_b.key() && escrow_b.token_mint_b == token_mint_a.key() @ ErrorCode::InvalidMatch,
+    constraint = escrow_b.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow_b.price_curve == PriceCurve::Fixed && !escrow_b.accepts_other_mints @ ErrorCode::InvalidMatch,
+    constraint = escrow_b.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+    constraint = !escrow_b.allowlisted @ ErrorCode::Allowlisted,
+    constraint = !escrow_b.gated @ ErrorCode::TokenGated,
+    constraint = !escrow_b.split @ ErrorCode::SplitsProceeds,
+    constraint = escrow_b.destination == Pubkey::default() @ ErrorCode::CustomDestination,
+    constraint = !escrow_b.owes_royalties(&token_mint_b) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow_b: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = escrow_b,
+  )]
+  pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = keeper,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker_a,
+  )]
+  pub maker_a_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = keeper,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker_b,
+  )]
+  pub maker_b_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = keeper,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = keeper,
+  )]
+  pub keeper_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  //always passed so the split can't be skipped, read only once it has been configured
+  /// CHECK: pinned by its seeds and deserialized in `keeper_bps`
+  #[account(
+    seeds = [b"match_config"],
+    bump,
+  )]
+  pub match_config: UncheckedAccount<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> MatchOffers<'info> {
+  pub fn match_offers(&mut self) -> Result<()> {
+
+    let fill = MatchFill::compute(&self.escrow_a, self.vault_a.amount, &self.escrow_b, self.vault_b.amount)
+      .ok_or(ErrorCode::OffersDontCross)?;
+    let keeper_share = fill.keeper_share(self.keeper_bps()?);
+
+    // A to the second maker
+    transfer_from_vault(
+      &self.escrow_a,
+      &self.vault_a,
+      &self.token_mint_a,
+      self.maker_b_token_account_a.to_account_info(),
+      fill.amount_a,
+      &self.token_program,
+    )?;
+
+    // B to the first maker and the keeper
+    for (to, amount) in [
+      (self.maker_a_token_account_b.to_account_info(), fill.released_b - keeper_share),
+      (self.keeper_token_account_b.to_account_info(), keeper_share),
+    ] {
+      if amount == 0 {
+        continue;
+      }
+      transfer_from_vault(&self.escrow_b, &self.vault_b, &self.token_mint_b, to, amount, &self.token_program)?;
+    }
+
+    self.escrow_a.receive_amount -= fill.price_b;
+    self.escrow_b.receive_amount -= fill.amount_a;
+
+    if fill.amount_a == self.vault_a.amount {
+      Self::close(&self.escrow_a, &self.vault_a, &self.keeper, &self.token_program)?;
+    }
+    if self.escrow_b.receive_amount == 0 {
+      Self::close(&self.escrow_b, &self.vault_b, &self.keeper, &self.token_program)?;
+    }
+
+    Ok(())
+  }
+
+  fn keeper_bps(&self) -> Result<u16> {
+    if self.match_config.owner != &crate::ID {
+      return Ok(10_000);
+    }
+    let config = MatchConfig::try_deserialize(&mut &self.match_config.try_borrow_data()?[..])?;
+    Ok(config.keeper_bps)
+  }
+
+  //Only called on empty vaults: the first is closed once all of it has been sold, and the
+  //second once its maker is fully paid, when `MatchFill` releases all of it
+  fn close(escrow: &Account<'info, Escrow>, vault: &InterfaceAccount<'info, TokenAccount>, keeper: &Signer<'info>, token_program: &Interface<'info, TokenInterface>) -> Result<()> {
+    close_vault(escrow, vault, keeper.to_account_info(), token_program)?;
+    escrow.close(keeper.to_account_info())
+  }
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+use crate::error::ErrorCode;
+use crate::state::Escrow;
+
+
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+
+  //anyone can pay to upgrade an escrow, the offer itself does not change
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  /// CHECK: older layouts can't be deserialized as `Escrow`, the discriminator and size are checked in `migrate`
+  #[account(
+    mut,
+    owner = crate::ID,
+  )]
+  pub escrow: UncheckedAccount<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateEscrow<'info> {
+  pub fn migrate(&mut self) -> Result<()> {
+
+    let escrow = self.escrow.to_account_info();
+
+    let version = {
+      let data = escrow.try_borrow_data()?;
+      require!(
+        data.len() >= Escrow::LEGACY_LEN && data[..8] == *Escrow::DISCRIMINATOR,
+        ErrorCode::InvalidEscrowAccount
+      );
+      if data.len() == Escrow::LEGACY_LEN { 0 } else { data[Escrow::LEGACY_LEN] }
+    };
+    require!(version < Escrow::VERSION, ErrorCode::EscrowUpToDate);
+
+    // Top up the rent for the new size before growing the account
+    if escrow.data_len() < Escrow::LEN {
+      let rent = Rent::get()?.minimum_balance(Escrow::LEN);
+      let missing = rent.saturating_sub(escrow.lamports());
+      if missing > 0 {
+        let cpi_accounts = Transfer {
+          from: self.payer.to_account_info(),
+          to: escrow.clone(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, missing)?;
+      }
+      escrow.realloc(Escrow::LEN, true)?;
+    }
+
+    // Fields added since `version` read back as zeroes, which are their defaults
+    let mut data = escrow.try_borrow_mut_data()?;
+    let mut state = Escrow::try_deserialize(&mut &data[..])?;
+    state.version = Escrow::VERSION;
+    state.try_serialize(&mut &mut data[..])?;
+
+    Ok(())
+  }
+}
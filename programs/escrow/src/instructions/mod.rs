@@ -1,7 +1,85 @@
 pub mod make;
 pub mod exchange;
 pub mod refund;
+pub mod migrate;
+pub mod batch_exchange;
+pub mod bulk_refund;
+pub mod start_auction;
+pub mod bid;
+pub mod settle_auction;
+pub mod cancel_auction;
+pub mod start_sealed_auction;
+pub mod commit_bid;
+pub mod reveal_bid;
+pub mod settle_sealed_auction;
+pub mod claim_commitment;
+pub mod cancel_sealed_auction;
+pub mod expire_sealed_auction;
+pub mod trigger;
+pub mod make_basket;
+pub mod exchange_basket;
+pub mod refund_basket;
+pub mod set_payment_mints;
+pub mod configure_matching;
+pub mod match_offers;
+pub mod create_market;
+pub mod start_crowd_fill;
+pub mod contribute;
+pub mod settle_crowd_fill;
+pub mod claim_crowd_fill;
+pub mod reclaim_contribution;
+pub mod cancel_crowd_fill;
+pub mod start_htlc;
+pub mod claim_htlc;
+pub mod refund_htlc;
+pub mod set_allowlist;
+pub mod set_token_gate;
+pub mod configure_mint_policy;
+pub mod make_pnft;
+pub mod exchange_pnft;
+pub mod refund_pnft;
+pub mod set_royalty_policy;
+pub mod set_recipients;
 
 pub use make::*;
 pub use exchange::*;
-pub use refund::*;
\ No newline at end of file
+pub use refund::*;
+pub use migrate::*;
+pub use batch_exchange::*;
+pub use bulk_refund::*;
+pub use start_auction::*;
+pub use bid::*;
+pub use settle_auction::*;
+pub use cancel_auction::*;
+pub use start_sealed_auction::*;
+pub use commit_bid::*;
+pub use reveal_bid::*;
+pub use settle_sealed_auction::*;
+pub use claim_commitment::*;
+pub use cancel_sealed_auction::*;
+pub use expire_sealed_auction::*;
+pub use trigger::*;
+pub use make_basket::*;
+pub use exchange_basket::*;
+pub use refund_basket::*;
+pub use set_payment_mints::*;
+pub use configure_matching::*;
+pub use match_offers::*;
+pub use create_market::*;
+pub use start_crowd_fill::*;
+pub use contribute::*;
+pub use settle_crowd_fill::*;
+pub use claim_crowd_fill::*;
+pub use reclaim_contribution::*;
+pub use cancel_crowd_fill::*;
+pub use start_htlc::*;
+pub use claim_htlc::*;
+pub use refund_htlc::*;
+pub use set_allowlist::*;
+pub use set_token_gate::*;
+pub use configure_mint_policy::*;
+pub use make_pnft::*;
+pub use exchange_pnft::*;
+pub use refund_pnft::*;
+pub use set_royalty_policy::*;
+pub use set_recipients::*;
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+use crate::error::ErrorCode;
+use crate::state::{Contribution, CrowdFill};
+
+
+//Returns a contribution from the pool once a crowd fill's deadline has passed
+//without reaching its target
+#[derive(Accounts)]
+pub struct ReclaimContribution<'info> {
+
+  #[account(mut)]
+  pub contributor: Signer<'info>,
+
+  #[account(
+    address = crowd_fill.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = contributor,
+  )]
+  pub contributor_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"crowd", crowd_fill.escrow.as_ref()],
+    bump = crowd_fill.bump,
+    constraint = !crowd_fill.is_filled() @ ErrorCode::CrowdFillNotExpired,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  #[account(
+    mut,
+    seeds = [b"contribution", crowd_fill.key().as_ref(), contributor.key().as_ref()],
+    bump = contribution.bump,
+    has_one = contributor,
+    close = contributor,
+  )]
+  pub contribution: Box<Account<'info, Contribution>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = crowd_fill,
+  )]
+  pub pool: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReclaimContribution<'info> {
+  pub fn reclaim(&mut self) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp >= self.crowd_fill.deadline, ErrorCode::CrowdFillNotExpired);
+
+    let escrow_key = self.crowd_fill.escrow;
+    let seeds = &[b"crowd", escrow_key.as_ref(), &[self.crowd_fill.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: self.pool.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.contributor_token_account_b.to_account_info(),
+      authority: self.crowd_fill.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, self.contribution.amount, self.token_mint_b.decimals)?;
+
+    self.crowd_fill.contributors -= 1;
+
+    Ok(())
+  }
+}
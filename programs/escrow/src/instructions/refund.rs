@@ -1,7 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
-use crate::state::{Escrow};
+use crate::error::ErrorCode;
+use crate::state::{Allowlist, Book, Escrow, EscrowKind, PaymentOptions, Recipients, TokenGate};
 
 
 #[derive(Accounts)]
@@ -28,6 +29,9 @@ pub struct RefundOffer<'info> {
     bump = escrow.bump,
     close = maker,
     has_one = maker,
+    constraint = matches!(escrow.kind, EscrowKind::Offer | EscrowKind::Conditional) @ ErrorCode::NotAnOffer,
+    constraint = escrow.market == Pubkey::default() || book.is_some() @ ErrorCode::ListedOnMarket,
+    constraint = !escrow.accepts_other_mints || payment_options.is_some() @ ErrorCode::PaymentOptionsMissing,
   )]
   pub escrow: Account<'info, Escrow>,
 
@@ -41,37 +45,131 @@ pub struct RefundOffer<'info> {
   pub token_program: Interface<'info, TokenInterface>,
   pub associated_token_program: Program<'info, AssociatedToken>,
   pub system_program: Program<'info, System>,
-}
 
-impl <'info> RefundOffer<'info> {
-  pub fn withdraw_and_close_vault(&self) -> Result<()> {
-
-    let seed = self.escrow.seed.to_le_bytes();
-    let bump = self.escrow.bump;
+  //needed, and closed with the escrow, when it accepts other mints
+  #[account(
+    mut,
+    seeds = [b"payments", escrow.key().as_ref()],
+    bump = payment_options.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub payment_options: Option<Box<Account<'info, PaymentOptions>>>,
 
-    let seeds = &[b"escrow", self.maker.to_account_info().key.as_ref(), seed.as_ref(), &[bump]];
-    let signer_seeds = &[&seeds[..]];
+  //the escrow is taken out of it when listed on a market
+  #[account(
+    mut,
+    constraint = book.market == escrow.market && book.side == escrow.side @ ErrorCode::ListedOnMarket,
+  )]
+  pub book: Option<Box<Account<'info, Book>>>,
 
+  //closed with the escrow when it is allowlisted
+  #[account(
+    mut,
+    seeds = [b"allowlist", escrow.key().as_ref()],
+    bump = allowlist.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub allowlist: Option<Box<Account<'info, Allowlist>>>,
 
-   let cpi_accounts = TransferChecked {
-      from: self.vault.to_account_info(),
-      mint: self.token_mint_a.to_account_info(),
-      to: self.maker_token_account_a.to_account_info(),
-      authority: self.escrow.to_account_info(),
-    };
-    let cpi_program = self.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-    transfer_checked(cpi_ctx, self.vault.amount, self.token_mint_a.decimals)?;
+  //closed with the escrow when it is token-gated
+  #[account(
+    mut,
+    seeds = [b"gate", escrow.key().as_ref()],
+    bump = token_gate.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub token_gate: Option<Box<Account<'info, TokenGate>>>,
 
-    let new_cpi_accounts = CloseAccount {
-      account: self.vault.to_account_info(),
-      destination: self.maker.to_account_info(),
-      authority: self.escrow.to_account_info(),
-    };
-    let new_cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), new_cpi_accounts, signer_seeds);
+  //closed with the escrow when it splits its proceeds
+  #[account(
+    mut,
+    seeds = [b"recipients", escrow.key().as_ref()],
+    bump = recipients.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub recipients: Option<Box<Account<'info, Recipients>>>,
+}
 
-    close_account(new_cpi_ctx)?;
+impl <'info> RefundOffer<'info> {
+  pub fn delist(&mut self) -> Result<()> {
+    //an order evicted by better ones is no longer in the book
+    if let Some(book) = self.book.as_mut() {
+      book.remove(&self.escrow.key());
+    }
     Ok(())
   }
 
-}
\ No newline at end of file
+  pub fn withdraw_and_close_vault(&self) -> Result<()> {
+    withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+}
+
+//Empties an escrow's vault into `to` and closes it to `rent_to`, signed by the escrow PDA
+pub(crate) fn withdraw_and_close_vault<'info>(
+  escrow: &Account<'info, Escrow>,
+  vault: &InterfaceAccount<'info, TokenAccount>,
+  token_mint_a: &InterfaceAccount<'info, Mint>,
+  to: AccountInfo<'info>,
+  rent_to: AccountInfo<'info>,
+  token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+  transfer_from_vault(escrow, vault, token_mint_a, to, vault.amount, token_program)?;
+  close_vault(escrow, vault, rent_to, token_program)
+}
+
+//Sends `amount` of an escrow's vault to `to`, signed by the escrow PDA
+pub(crate) fn transfer_from_vault<'info>(
+  escrow: &Account<'info, Escrow>,
+  vault: &InterfaceAccount<'info, TokenAccount>,
+  mint: &InterfaceAccount<'info, Mint>,
+  to: AccountInfo<'info>,
+  amount: u64,
+  token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+
+  let seed = escrow.seed.to_le_bytes();
+  let seeds = &[b"escrow", escrow.maker.as_ref(), seed.as_ref(), &[escrow.bump]];
+  let signer_seeds = &[&seeds[..]];
+
+  let cpi_accounts = TransferChecked {
+    from: vault.to_account_info(),
+    mint: mint.to_account_info(),
+    to,
+    authority: escrow.to_account_info(),
+  };
+  let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+  transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+//Closes an escrow's empty vault to `rent_to`, signed by the escrow PDA
+pub(crate) fn close_vault<'info>(
+  escrow: &Account<'info, Escrow>,
+  vault: &InterfaceAccount<'info, TokenAccount>,
+  rent_to: AccountInfo<'info>,
+  token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+
+  let seed = escrow.seed.to_le_bytes();
+  let seeds = &[b"escrow", escrow.maker.as_ref(), seed.as_ref(), &[escrow.bump]];
+  let signer_seeds = &[&seeds[..]];
+
+  let cpi_accounts = CloseAccount {
+    account: vault.to_account_info(),
+    destination: rent_to,
+    authority: escrow.to_account_info(),
+  };
+  let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+  close_account(cpi_ctx)
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::Basket;
+
+
+//Returns every vault of a basket to the maker and closes it.
+//remaining_accounts: [token_mint, vault, maker_token_account] for every offered mint, in the basket's order
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+    bump = basket.bump,
+    has_one = maker,
+    close = maker,
+  )]
+  pub basket: Box<Account<'info, Basket>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundBasket<'info> {
+  pub fn refund_basket(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+
+    let groups = remaining_accounts.chunks_exact(3);
+    require!(
+      groups.len() == self.basket.offered_mints.len() && groups.remainder().is_empty(),
+      ErrorCode::InvalidRemainingAccounts
+    );
+
+    let token_program = self.token_program.key();
+    let seed_bytes = self.basket.seed.to_le_bytes();
+    let seeds = &[b"basket", self.maker.to_account_info().key.as_ref(), seed_bytes.as_ref(), &[self.basket.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    for (accounts, mint) in groups.zip(self.basket.offered_mints.iter()) {
+      let token_mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+      let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+      let maker_token_account = &accounts[2];
+
+      require_keys_eq!(token_mint.key(), *mint, ErrorCode::EscrowMismatch);
+      require_keys_eq!(
+        vault.key(),
+        get_associated_token_address_with_program_id(&self.basket.key(), mint, &token_program),
+        ErrorCode::EscrowMismatch
+      );
+      require_keys_eq!(
+        maker_token_account.key(),
+        get_associated_token_address_with_program_id(&self.maker.key(), mint, &token_program),
+        ErrorCode::EscrowMismatch
+      );
+
+      let cpi_accounts = TransferChecked {
+        from: vault.to_account_info(),
+        mint: token_mint.to_account_info(),
+        to: maker_token_account.clone(),
+        authority: self.basket.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+      transfer_checked(cpi_ctx, vault.amount, token_mint.decimals)?;
+
+      let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: self.maker.to_account_info(),
+        authority: self.basket.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+      close_account(cpi_ctx)?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::withdraw_and_close_vault;
+use crate::state::{Escrow, EscrowKind, Htlc};
+
+
+//Returns the vault to the maker once the timelock has expired unclaimed
+#[derive(Accounts)]
+pub struct RefundHtlc<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::Htlc @ ErrorCode::NotAnHtlc,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"htlc", escrow.key().as_ref()],
+    bump = htlc.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub htlc: Box<Account<'info, Htlc>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundHtlc<'info> {
+  pub fn refund(&mut self) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp >= self.htlc.timelock, ErrorCode::TimelockNotExpired);
+
+    withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.maker_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+}
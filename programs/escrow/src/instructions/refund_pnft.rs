@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::Mint;
+use crate::error::ErrorCode;
+use crate::instructions::make_pnft::withdraw_pnft;
+use crate::state::{edition_address, token_record_address, Escrow, EscrowKind, TransferPnft, TOKEN_METADATA_PROGRAM};
+
+
+//Returns the pNFT of a `ProgrammableNft` escrow to its maker
+#[derive(Accounts)]
+pub struct RefundPnft<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  /// CHECK: the maker's ATA of the pNFT, Token Metadata recreates it if it was closed
+  #[account(
+    mut,
+    address = get_associated_token_address(&maker.key(), &token_mint_a.key()),
+  )]
+  pub maker_token_account_a: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::ProgrammableNft @ ErrorCode::NotAProgrammableNft,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  /// CHECK: the escrow's ATA of the pNFT
+  #[account(
+    mut,
+    address = get_associated_token_address(&escrow.key(), &token_mint_a.key()),
+  )]
+  pub vault: UncheckedAccount<'info>,
+
+  /// CHECK: checked by Token Metadata
+  #[account(mut)]
+  pub metadata: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the pNFT's master edition
+  #[account(
+    address = edition_address(&token_mint_a.key()),
+  )]
+  pub edition: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the vault
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &vault.key()),
+  )]
+  pub vault_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to the token record of the maker's token account
+  #[account(
+    mut,
+    address = token_record_address(&token_mint_a.key(), &maker_token_account_a.key()),
+  )]
+  pub maker_token_record: UncheckedAccount<'info>,
+
+  /// CHECK: the instructions sysvar, read by Token Metadata
+  #[account(
+    address = sysvar::instructions::ID,
+  )]
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  /// CHECK: the Token Metadata program
+  #[account(
+    address = TOKEN_METADATA_PROGRAM,
+  )]
+  pub token_metadata_program: UncheckedAccount<'info>,
+
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  //only needed when the pNFT has a rule set
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+  /// CHECK: checked by Token Metadata
+  pub authorization_rules: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> RefundPnft<'info> {
+  pub fn refund(&self) -> Result<()> {
+    let transfer = TransferPnft {
+      token: self.vault.to_account_info(),
+      token_owner: self.escrow.to_account_info(),
+      destination_token: self.maker_token_account_a.to_account_info(),
+      destination_owner: self.maker.to_account_info(),
+      mint: self.token_mint_a.to_account_info(),
+      metadata: self.metadata.to_account_info(),
+      edition: self.edition.to_account_info(),
+      token_record: self.vault_token_record.to_account_info(),
+      destination_token_record: self.maker_token_record.to_account_info(),
+      authority: self.escrow.to_account_info(),
+      payer: self.maker.to_account_info(),
+      system_program: self.system_program.to_account_info(),
+      sysvar_instructions: self.sysvar_instructions.to_account_info(),
+      token_program: self.token_program.to_account_info(),
+      associated_token_program: self.associated_token_program.to_account_info(),
+      authorization_rules_program: self.authorization_rules_program.as_ref().map(|program| program.to_account_info()),
+      authorization_rules: self.authorization_rules.as_ref().map(|rules| rules.to_account_info()),
+      token_metadata_program: self.token_metadata_program.to_account_info(),
+    };
+
+    withdraw_pnft(&self.escrow, transfer, self.maker.to_account_info())
+  }
+}
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+use crate::error::ErrorCode;
+use crate::state::{Commitment, SealedAuction};
+
+
+//Opens a commitment during the reveal window. A bid at or above the reserve is
+//topped up from the bidder so the commitment vault holds all of it.
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+
+  pub bidder: Signer<'info>,
+
+  #[account(
+    address = sealed_auction.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bidder,
+  )]
+  pub bidder_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", sealed_auction.escrow.as_ref()],
+    bump = sealed_auction.bump,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    mut,
+    seeds = [b"commitment", sealed_auction.key().as_ref(), bidder.key().as_ref()],
+    bump = commitment.bump,
+    has_one = bidder,
+  )]
+  pub commitment: Box<Account<'info, Commitment>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = commitment,
+  )]
+  pub commitment_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RevealBid<'info> {
+  pub fn reveal_bid(&mut self, bid: u64, salt: [u8; 32]) -> Result<()> {
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+      now >= self.sealed_auction.commit_end && now < self.sealed_auction.reveal_end,
+      ErrorCode::NotInRevealPhase
+    );
+    require!(!self.commitment.revealed, ErrorCode::AlreadyRevealed);
+    require!(
+      Commitment::hash(bid, &salt, &self.bidder.key()) == self.commitment.hash,
+      ErrorCode::CommitmentMismatch
+    );
+
+    self.commitment.revealed = true;
+    self.commitment.bid = bid;
+
+    //A bid under the reserve is revealed, so it keeps its collateral, but can't win
+    if bid < self.sealed_auction.reserve {
+      return Ok(());
+    }
+
+    let top_up = bid.saturating_sub(self.commitment_vault.amount);
+    if top_up > 0 {
+      let cpi_accounts = TransferChecked {
+        from: self.bidder_token_account_b.to_account_info(),
+        mint: self.token_mint_b.to_account_info(),
+        to: self.commitment_vault.to_account_info(),
+        authority: self.bidder.to_account_info(),
+      };
+      let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+      transfer_checked(cpi_ctx, top_up, self.token_mint_b.decimals)?;
+    }
+
+    self.sealed_auction.record_bid(self.bidder.key(), bid);
+
+    Ok(())
+  }
+}
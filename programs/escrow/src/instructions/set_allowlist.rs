@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{Allowlist, Escrow, EscrowKind};
+
+
+//Restricts the escrow to the takers under `root`, who take it with `exchange_allowlisted`,
+//see `Allowlist::leaf`.
+//Calling it again replaces the root.
+#[derive(Accounts)]
+pub struct SetAllowlist<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init_if_needed,
+    payer = maker,
+    space = Allowlist::LEN,
+    seeds = [b"allowlist", escrow.key().as_ref()],
+    bump,
+  )]
+  pub allowlist: Box<Account<'info, Allowlist>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetAllowlist<'info> {
+  pub fn set_allowlist(&mut self, root: [u8; 32], bumps: SetAllowlistBumps) -> Result<()> {
+
+    self.allowlist.set_inner(Allowlist {
+      escrow: self.escrow.key(),
+      root,
+      bump: bumps.allowlist,
+    });
+
+    self.escrow.allowlisted = true;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use crate::constants::MAX_PAYMENT_MINTS;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, Leg, PaymentOptions};
+
+
+//Lets the taker pay with any of `accepted` instead of `token_mint_b`, each at its own amount.
+//Calling it again replaces the list, takers bound what they pay with `exchange_with_max_pay`.
+#[derive(Accounts)]
+pub struct SetPaymentMints<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init_if_needed,
+    payer = maker,
+    space = PaymentOptions::LEN,
+    seeds = [b"payments", escrow.key().as_ref()],
+    bump,
+  )]
+  pub payment_options: Box<Account<'info, PaymentOptions>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPaymentMints<'info> {
+  pub fn set_payment_mints(&mut self, accepted: Vec<Leg>, bumps: SetPaymentMintsBumps) -> Result<()> {
+
+    require!(
+      (1..=MAX_PAYMENT_MINTS).contains(&accepted.len())
+        && accepted.iter().enumerate().all(|(i, leg)| {
+          leg.mint != self.escrow.token_mint_b && accepted[..i].iter().all(|other| other.mint != leg.mint)
+        }),
+      ErrorCode::InvalidPaymentMints
+    );
+
+    self.payment_options.set_inner(PaymentOptions {
+      escrow: self.escrow.key(),
+      accepted,
+      bump: bumps.payment_options,
+    });
+
+    self.escrow.accepts_other_mints = true;
+
+    Ok(())
+  }
+}
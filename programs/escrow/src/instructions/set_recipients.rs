@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use crate::constants::MAX_RECIPIENTS;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, Recipient, Recipients};
+
+
+//Pays what the taker sends to `recipients` by their shares instead of to the maker,
+//so the escrow can't also have a `destination`. Send it in the same transaction as
+//`make`, calling it again replaces the list.
+#[derive(Accounts)]
+pub struct SetRecipients<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+    constraint = escrow.destination == Pubkey::default() @ ErrorCode::SplitWithDestination,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init_if_needed,
+    payer = maker,
+    space = Recipients::LEN,
+    seeds = [b"recipients", escrow.key().as_ref()],
+    bump,
+  )]
+  pub recipients: Box<Account<'info, Recipients>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetRecipients<'info> {
+  pub fn set_recipients(&mut self, recipients: Vec<Recipient>, bumps: SetRecipientsBumps) -> Result<()> {
+
+    require!(
+      (1..=MAX_RECIPIENTS).contains(&recipients.len())
+        && recipients.iter().enumerate().all(|(i, recipient)| {
+          recipient.share_bps > 0 && recipients[..i].iter().all(|other| other.wallet != recipient.wallet)
+        })
+        && recipients.iter().map(|recipient| recipient.share_bps as u32).sum::<u32>() == Recipients::TOTAL_BPS as u32,
+      ErrorCode::InvalidRecipients
+    );
+
+    self.recipients.set_inner(Recipients {
+      escrow: self.escrow.key(),
+      recipients,
+      bump: bumps.recipients,
+    });
+
+    self.escrow.split = true;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, Metadata, RoyaltyPolicy};
+
+
+//Chooses whether the taker pays the NFT's creators. Only the sole creator selling
+//their own NFT can waive royalties, anyone can enforce them again.
+#[derive(Accounts)]
+pub struct SetRoyaltyPolicy<'info> {
+
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = matches!(escrow.kind, EscrowKind::Offer | EscrowKind::ProgrammableNft) @ ErrorCode::NotAnOffer,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  //needed to waive royalties
+  /// CHECK: parsed by `Metadata::load`
+  pub metadata: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> SetRoyaltyPolicy<'info> {
+  pub fn set_royalty_policy(&mut self, policy: RoyaltyPolicy) -> Result<()> {
+
+    if policy == RoyaltyPolicy::Waived {
+      let metadata = self.metadata.as_ref().ok_or(ErrorCode::NotACreator)?;
+      let metadata = Metadata::load(metadata, &self.escrow.token_mint_a)?;
+      require!(metadata.is_sole_creator(&self.maker.key()), ErrorCode::NotACreator);
+    }
+
+    self.escrow.royalty_policy = policy;
+
+    Ok(())
+  }
+}
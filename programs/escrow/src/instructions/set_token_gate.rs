@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, GateKind, TokenGate};
+
+
+//Only lets takers holding `min_amount` of `mint`, or an NFT of the `mint`
+//collection, fill the escrow. Calling it again replaces the gate.
+#[derive(Accounts)]
+pub struct SetTokenGate<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.kind == EscrowKind::Offer @ ErrorCode::NotAnOffer,
+    constraint = escrow.market == Pubkey::default() @ ErrorCode::ListedOnMarket,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init_if_needed,
+    payer = maker,
+    space = TokenGate::LEN,
+    seeds = [b"gate", escrow.key().as_ref()],
+    bump,
+  )]
+  pub token_gate: Box<Account<'info, TokenGate>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetTokenGate<'info> {
+  pub fn set_token_gate(&mut self, kind: GateKind, mint: Pubkey, min_amount: u64, bumps: SetTokenGateBumps) -> Result<()> {
+
+    require!(kind == GateKind::Collection || min_amount > 0, ErrorCode::InvalidTokenGate);
+
+    self.token_gate.set_inner(TokenGate {
+      escrow: self.escrow.key(),
+      kind,
+      mint,
+      min_amount,
+      bump: bumps.token_gate,
+    });
+
+    self.escrow.gated = true;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::instructions::refund::withdraw_and_close_vault;
+use crate::state::{Auction, Bid, Escrow, EscrowKind};
+
+
+//Anyone can settle an auction once it has ended: the vault goes to the winner
+//and the winning bid to the maker.
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+
+  #[account(mut)]
+  pub settler: Signer<'info>,
+
+  //either party can be a PDA of another program, so they aren't required to be system accounts
+  /// CHECK: pinned to `escrow.maker`, only receives rent and owns its ATA of mint B
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to `auction.top_bidder`, only receives rent and owns its ATA of mint A
+  #[account(
+    mut,
+    address = auction.top_bidder,
+  )]
+  pub winner: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = settler,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = winner,
+  )]
+  pub winner_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = settler,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::EnglishAuction @ ErrorCode::NotAnAuction,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"auction", escrow.key().as_ref()],
+    bump = auction.bump,
+    has_one = escrow,
+    close = maker,
+  )]
+  pub auction: Box<Account<'info, Auction>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"bid", escrow.key().as_ref(), winner.key().as_ref()],
+    bump = bid.bump,
+    close = winner,
+  )]
+  pub bid: Box<Account<'info, Bid>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = bid,
+  )]
+  pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleAuction<'info> {
+  pub fn send_vault_to_winner(&mut self) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp >= self.auction.end_time, ErrorCode::AuctionNotEnded);
+    require!(self.auction.has_bids(), ErrorCode::AuctionHasNoBids);
+
+    withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.winner_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+  pub fn send_bid_to_maker(&mut self) -> Result<()> {
+
+    let escrow_key = self.escrow.key();
+    let seeds = &[b"bid", escrow_key.as_ref(), self.bid.bidder.as_ref(), &[self.bid.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: self.bid_vault.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.maker_token_account_b.to_account_info(),
+      authority: self.bid.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, self.bid_vault.amount, self.token_mint_b.decimals)?;
+
+    let cpi_accounts = CloseAccount {
+      account: self.bid_vault.to_account_info(),
+      destination: self.winner.to_account_info(),
+      authority: self.bid.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)
+  }
+}
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account};
+use crate::error::ErrorCode;
+use crate::state::{CrowdFill, Escrow, EscrowKind};
+
+
+//Anyone can settle a crowd fill once its target is raised: the pool goes to the
+//maker and the vault is left for the contributors to claim their shares of.
+#[derive(Accounts)]
+pub struct SettleCrowdFill<'info> {
+
+  #[account(mut)]
+  pub settler: Signer<'info>,
+
+  //can be a PDA of another program, so it isn't required to be a system account
+  /// CHECK: pinned to `escrow.maker`, only receives rent and owns its ATA of mint B
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = settler,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::CrowdFill @ ErrorCode::NotACrowdFill,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"crowd", escrow.key().as_ref()],
+    bump = crowd_fill.bump,
+    has_one = escrow,
+    constraint = !crowd_fill.settled @ ErrorCode::CrowdFillClosed,
+    constraint = crowd_fill.is_filled() @ ErrorCode::CrowdFillNotFilled,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  #[account(
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = crowd_fill,
+  )]
+  pub pool: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleCrowdFill<'info> {
+  pub fn settle(&mut self) -> Result<()> {
+
+    let escrow_key = self.escrow.key();
+    let seeds = &[b"crowd", escrow_key.as_ref(), &[self.crowd_fill.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: self.pool.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.maker_token_account_b.to_account_info(),
+      authority: self.crowd_fill.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, self.pool.amount, self.token_mint_b.decimals)?;
+
+    let cpi_accounts = CloseAccount {
+      account: self.pool.to_account_info(),
+      destination: self.maker.to_account_info(),
+      authority: self.crowd_fill.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)?;
+
+    self.crowd_fill.offered = self.vault.amount;
+    self.crowd_fill.settled = true;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+use crate::error::ErrorCode;
+use crate::instructions::refund::withdraw_and_close_vault;
+use crate::state::{Commitment, Escrow, EscrowKind, SealedAuction};
+
+
+//Anyone can settle a sealed auction once the reveal window is over: the vault goes
+//to the highest revealed bid and the clearing price to the maker. Whatever is left
+//in the winner's commitment is returned by `claim_commitment`.
+#[derive(Accounts)]
+pub struct SettleSealedAuction<'info> {
+
+  #[account(mut)]
+  pub settler: Signer<'info>,
+
+  //either party can be a PDA of another program, so they aren't required to be system accounts
+  /// CHECK: pinned to `escrow.maker`, only receives rent and owns its ATA of mint B
+  #[account(
+    mut,
+    address = escrow.maker,
+  )]
+  pub maker: UncheckedAccount<'info>,
+
+  /// CHECK: pinned to `sealed_auction.top_bidder`, only owns its ATA of mint A
+  #[account(
+    address = sealed_auction.top_bidder,
+  )]
+  pub winner: UncheckedAccount<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = settler,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = winner,
+  )]
+  pub winner_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    init_if_needed,
+    payer = settler,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = maker,
+  )]
+  pub maker_token_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::SealedAuction @ ErrorCode::NotASealedAuction,
+    close = maker,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    seeds = [b"sealed", escrow.key().as_ref()],
+    bump = sealed_auction.bump,
+    has_one = escrow,
+    constraint = !sealed_auction.settled @ ErrorCode::AuctionSettled,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    seeds = [b"commitment", sealed_auction.key().as_ref(), winner.key().as_ref()],
+    bump = commitment.bump,
+  )]
+  pub commitment: Box<Account<'info, Commitment>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = commitment,
+  )]
+  pub commitment_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleSealedAuction<'info> {
+  pub fn send_vault_to_winner(&mut self) -> Result<()> {
+
+    require!(Clock::get()?.unix_timestamp >= self.sealed_auction.reveal_end, ErrorCode::AuctionNotEnded);
+    require!(self.sealed_auction.has_bids(), ErrorCode::AuctionHasNoBids);
+
+    withdraw_and_close_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.winner_token_account_a.to_account_info(),
+      self.maker.to_account_info(),
+      &self.token_program,
+    )
+  }
+
+  pub fn send_price_to_maker(&mut self) -> Result<()> {
+
+    let auction_key = self.sealed_auction.key();
+    let seeds = &[b"commitment", auction_key.as_ref(), self.commitment.bidder.as_ref(), &[self.commitment.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+      from: self.commitment_vault.to_account_info(),
+      mint: self.token_mint_b.to_account_info(),
+      to: self.maker_token_account_b.to_account_info(),
+      authority: self.commitment.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, self.sealed_auction.clearing_price(), self.token_mint_b.decimals)?;
+
+    self.sealed_auction.settled = true;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Auction, Escrow, EscrowKind};
+
+
+//Turns an offer into an english auction with `receive_amount` as the reserve price.
+//Send it in the same transaction as `make` so the offer can't be taken in between.
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  //royalties can't be collected once the offer is no longer taken through `exchange`
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.is_plain_offer() @ ErrorCode::NotAnOffer,
+    constraint = !escrow.owes_royalties(&token_mint_a) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = ANCHOR_DESCRIMINATOR + Auction::INIT_SPACE,
+    seeds = [b"auction", escrow.key().as_ref()],
+    bump,
+  )]
+  pub auction: Box<Account<'info, Auction>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartAuction<'info> {
+  pub fn start_auction(&mut self, end_time: i64, min_increment: u64, extension: i64, bumps: StartAuctionBumps) -> Result<()> {
+
+    require!(
+      end_time > Clock::get()?.unix_timestamp && extension >= 0,
+      ErrorCode::InvalidAuction
+    );
+
+    self.auction.set_inner(Auction {
+      escrow: self.escrow.key(),
+      min_increment,
+      end_time,
+      extension,
+      top_bid: 0,
+      top_bidder: Pubkey::default(),
+      bump: bumps.auction,
+    });
+
+    self.escrow.kind = EscrowKind::EnglishAuction;
+
+    Ok(())
+  }
+}
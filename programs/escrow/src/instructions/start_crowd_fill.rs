@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{CrowdFill, Escrow, EscrowKind};
+
+
+//Turns an offer into a crowd fill raising `receive_amount` until `deadline`.
+//Send it in the same transaction as `make` so the offer can't be taken in between.
+#[derive(Accounts)]
+pub struct StartCrowdFill<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  //royalties can't be collected once the offer is no longer taken through `exchange`
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    address = escrow.token_mint_b
+  )]
+  pub token_mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.is_plain_offer() @ ErrorCode::NotAnOffer,
+    constraint = !escrow.owes_royalties(&token_mint_a) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = ANCHOR_DESCRIMINATOR + CrowdFill::INIT_SPACE,
+    seeds = [b"crowd", escrow.key().as_ref()],
+    bump,
+  )]
+  pub crowd_fill: Box<Account<'info, CrowdFill>>,
+
+  //holds the contributions until the fill is settled or they are reclaimed
+  #[account(
+    init,
+    payer = maker,
+    associated_token::mint = token_mint_b,
+    associated_token::authority = crowd_fill,
+  )]
+  pub pool: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartCrowdFill<'info> {
+  pub fn start_crowd_fill(&mut self, deadline: i64, bumps: StartCrowdFillBumps) -> Result<()> {
+
+    require!(
+      deadline > Clock::get()?.unix_timestamp && self.escrow.receive_amount > 0,
+      ErrorCode::InvalidCrowdFill
+    );
+
+    self.crowd_fill.set_inner(CrowdFill {
+      escrow: self.escrow.key(),
+      maker: self.maker.key(),
+      token_mint_b: self.escrow.token_mint_b,
+      target: self.escrow.receive_amount,
+      raised: 0,
+      offered: 0,
+      deadline,
+      contributors: 0,
+      settled: false,
+      bump: bumps.crowd_fill,
+    });
+
+    self.escrow.kind = EscrowKind::CrowdFill;
+
+    Ok(())
+  }
+}
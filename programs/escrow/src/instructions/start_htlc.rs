@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, Htlc};
+
+
+//Locks an offer behind a hash and a time lock for `taker` only.
+//Send it in the same transaction as `make` so the offer can't be taken in between.
+#[derive(Accounts)]
+pub struct StartHtlc<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  //royalties can't be collected once the offer is no longer taken through `exchange`
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.is_plain_offer() @ ErrorCode::NotAnOffer,
+    constraint = !escrow.owes_royalties(&token_mint_a) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = ANCHOR_DESCRIMINATOR + Htlc::INIT_SPACE,
+    seeds = [b"htlc", escrow.key().as_ref()],
+    bump,
+  )]
+  pub htlc: Box<Account<'info, Htlc>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartHtlc<'info> {
+  pub fn start_htlc(&mut self, taker: Pubkey, hashlock: [u8; 32], timelock: i64, bumps: StartHtlcBumps) -> Result<()> {
+
+    require!(timelock > Clock::get()?.unix_timestamp, ErrorCode::InvalidHtlc);
+
+    self.htlc.set_inner(Htlc {
+      escrow: self.escrow.key(),
+      taker,
+      hashlock,
+      timelock,
+      bump: bumps.htlc,
+    });
+
+    self.escrow.kind = EscrowKind::Htlc;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::constants::ANCHOR_DESCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::state::{Escrow, EscrowKind, SealedAuction, SealedPricing};
+
+
+//Turns an offer into a sealed-bid auction with `receive_amount` as the reserve price.
+//Send it in the same transaction as `make` so the offer can't be taken in between.
+#[derive(Accounts)]
+pub struct StartSealedAuction<'info> {
+
+  #[account(mut)]
+  pub maker: Signer<'info>,
+
+  //royalties can't be collected once the offer is no longer taken through `exchange`
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    has_one = maker,
+    constraint = escrow.is_plain_offer() @ ErrorCode::NotAnOffer,
+    constraint = !escrow.owes_royalties(&token_mint_a) @ ErrorCode::RoyaltiesOwed,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    init,
+    payer = maker,
+    space = ANCHOR_DESCRIMINATOR + SealedAuction::INIT_SPACE,
+    seeds = [b"sealed", escrow.key().as_ref()],
+    bump,
+  )]
+  pub sealed_auction: Box<Account<'info, SealedAuction>>,
+
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartSealedAuction<'info> {
+  pub fn start_sealed_auction(&mut self, commit_end: i64, reveal_end: i64, collateral: u64, pricing: SealedPricing, forfeit_bps: u16, bumps: StartSealedAuctionBumps) -> Result<()> {
+
+    require!(
+      commit_end > Clock::get()?.unix_timestamp && reveal_end > commit_end && forfeit_bps <= 10_000,
+      ErrorCode::InvalidSealedAuction
+    );
+
+    self.sealed_auction.set_inner(SealedAuction {
+      escrow: self.escrow.key(),
+      maker: self.maker.key(),
+      token_mint_b: self.escrow.token_mint_b,
+      reserve: self.escrow.receive_amount,
+      collateral,
+      commit_end,
+      reveal_end,
+      pricing,
+      forfeit_bps,
+      top_bid: 0,
+      top_bidder: Pubkey::default(),
+      second_bid: 0,
+      commitments: 0,
+      settled: false,
+      bump: bumps.sealed_auction,
+    });
+
+    self.escrow.kind = EscrowKind::SealedAuction;
+
+    Ok(())
+  }
+}
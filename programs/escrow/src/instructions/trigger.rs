@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::ErrorCode;
+use crate::instructions::refund::transfer_from_vault;
+use crate::state::{Escrow, EscrowKind, PriceFeed};
+
+
+//Anyone can trigger a conditional order once its oracle crosses the trigger price.
+//The order becomes a plain offer and the cranker is tipped from the vault.
+#[derive(Accounts)]
+pub struct TriggerOrder<'info> {
+
+  #[account(mut)]
+  pub cranker: Signer<'info>,
+
+  #[account(
+    address = escrow.token_mint_a
+  )]
+  pub token_mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+  #[account(
+    init_if_needed,
+    payer = cranker,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = cranker,
+  )]
+  pub cranker_token_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  #[account(
+    mut,
+    seeds = [b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+    bump = escrow.bump,
+    constraint = escrow.kind == EscrowKind::Conditional @ ErrorCode::NotConditional,
+  )]
+  pub escrow: Box<Account<'info, Escrow>>,
+
+  #[account(
+    mut,
+    associated_token::mint = token_mint_a,
+    associated_token::authority = escrow,
+  )]
+  pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+  /// CHECK: pinned to `escrow.oracle` and parsed by `PriceFeed::load`
+  #[account(
+    address = escrow.oracle @ ErrorCode::InvalidPriceFeed,
+  )]
+  pub price_feed: UncheckedAccount<'info>,
+
+  pub token_program: Interface<'info, TokenInterface>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+}
+
+impl<'info> TriggerOrder<'info> {
+  pub fn trigger(&mut self) -> Result<()> {
+
+    let feed = PriceFeed::load(&self.price_feed)?;
+    require!(self.escrow.is_triggered(&feed, Clock::get()?.unix_timestamp)?, ErrorCode::TriggerNotReached);
+
+    self.escrow.kind = EscrowKind::Offer;
+
+    let tip = self.escrow.trigger_tip.min(self.vault.amount);
+    if tip == 0 {
+      return Ok(());
+    }
+
+    transfer_from_vault(
+      &self.escrow,
+      &self.vault,
+      &self.token_mint_a,
+      self.cranker_token_account_a.to_account_info(),
+      tip,
+      &self.token_program,
+    )
+  }
+}
@@ -5,6 +5,7 @@ pub mod state;
 
 use anchor_lang::prelude::*;
 pub use instructions::*;
+use state::{GateKind, Leg, PriceCurve, Recipient, RoyaltyPolicy, SealedPricing, TriggerParams};
 
 declare_id!("53E3gL8jErkT5PahCinHP6nw3P8ZtxBidvvLvsxpqs91");
 
@@ -15,18 +16,357 @@ pub mod escrow {
     pub fn make(ctx: Context<MakeOffer>, seed: u64, receive: u64, deposit: u64) -> Result<()> {
         ctx.accounts.init_escrow(seed, receive, ctx.bumps)?;
         ctx.accounts.deposit(deposit)?;
+        ctx.accounts.list(deposit)?;
 
         Ok(())
     }
 
-    pub fn exchange(ctx: Context<TakeOffer>) -> Result<()> {
-        ctx.accounts.send_wanted_tokens_to_maker()?;
+    //`token_mint_a` is the mint paid with and `receive` the amount of `token_mint_b` wanted.
+    //Bids are filled like asks: whole by `exchange`, partially by `batch_exchange`.
+    pub fn make_bid(ctx: Context<MakeOffer>, seed: u64, receive: u64, deposit: u64) -> Result<()> {
+        ctx.accounts.init_escrow(seed, receive, ctx.bumps)?;
+        ctx.accounts.set_bid()?;
+        ctx.accounts.deposit(deposit)?;
+        ctx.accounts.list(deposit)?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn make_dutch(
+        ctx: Context<MakeOffer>,
+        seed: u64,
+        receive: u64,
+        floor: u64,
+        start_time: i64,
+        end_time: i64,
+        curve: PriceCurve,
+        deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.init_escrow(seed, receive, ctx.bumps)?;
+        ctx.accounts.set_price_curve(curve, floor, start_time, end_time)?;
+        ctx.accounts.deposit(deposit)?;
+
+        Ok(())
+    }
+
+    pub fn make_oracle(
+        ctx: Context<MakeOffer>,
+        seed: u64,
+        oracle: Pubkey,
+        spread_bps: u16,
+        max_age: i64,
+        max_confidence_bps: u16,
+        deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.init_escrow(seed, 0, ctx.bumps)?;
+        ctx.accounts
+            .set_oracle(oracle, spread_bps, max_age, max_confidence_bps)?;
+        ctx.accounts.deposit(deposit)?;
+
+        Ok(())
+    }
+
+    pub fn make_conditional(
+        ctx: Context<MakeOffer>,
+        seed: u64,
+        receive: u64,
+        trigger: TriggerParams,
+        deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.init_escrow(seed, receive, ctx.bumps)?;
+        ctx.accounts.set_trigger(trigger, deposit)?;
+        ctx.accounts.deposit(deposit)?;
+
+        Ok(())
+    }
+
+    pub fn trigger(ctx: Context<TriggerOrder>) -> Result<()> {
+        ctx.accounts.trigger()?;
+        Ok(())
+    }
+
+    pub fn set_payment_mints(ctx: Context<SetPaymentMints>, accepted: Vec<Leg>) -> Result<()> {
+        ctx.accounts.set_payment_mints(accepted, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn set_allowlist(ctx: Context<SetAllowlist>, root: [u8; 32]) -> Result<()> {
+        ctx.accounts.set_allowlist(root, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn set_royalty_policy(ctx: Context<SetRoyaltyPolicy>, policy: RoyaltyPolicy) -> Result<()> {
+        ctx.accounts.set_royalty_policy(policy)?;
+        Ok(())
+    }
+
+    pub fn set_recipients(ctx: Context<SetRecipients>, recipients: Vec<Recipient>) -> Result<()> {
+        ctx.accounts.set_recipients(recipients, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn set_token_gate(ctx: Context<SetTokenGate>, kind: GateKind, mint: Pubkey, min_amount: u64) -> Result<()> {
+        ctx.accounts.set_token_gate(kind, mint, min_amount, ctx.bumps)?;
+        Ok(())
+    }
+
+    //The remaining accounts are only read when the escrow is token-gated, owes royalties
+    //or splits its proceeds
+    pub fn exchange<'info>(ctx: Context<'_, '_, 'info, 'info, TakeOffer<'info>>) -> Result<()> {
+        ctx.accounts.check_not_allowlisted()?;
+        ctx.accounts.check_token_gate(ctx.remaining_accounts)?;
+        ctx.accounts.take_from_book()?;
+        ctx.accounts.check_taker_token_account_a()?;
+        ctx.accounts.send_wanted_tokens_to_maker(u64::MAX, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        Ok(())
+    }
+
+    //`exchange` paying at most `max_pay` of mint B, royalties included, for escrows
+    //whose price can move before the transaction lands
+    pub fn exchange_with_max_pay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+        max_pay: u64,
+    ) -> Result<()> {
+        ctx.accounts.check_not_allowlisted()?;
+        ctx.accounts.check_token_gate(ctx.remaining_accounts)?;
+        ctx.accounts.take_from_book()?;
+        ctx.accounts.check_taker_token_account_a()?;
+        ctx.accounts.send_wanted_tokens_to_maker(max_pay, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        Ok(())
+    }
+
+    //`exchange` for allowlisted escrows, the taker proves they are on the list with `cap`
+    pub fn exchange_allowlisted<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeOffer<'info>>,
+        proof: Vec<[u8; 32]>,
+        cap: u64,
+        max_pay: u64,
+    ) -> Result<()> {
+        ctx.accounts.check_allowlist(&proof, cap, ctx.bumps)?;
+        ctx.accounts.check_token_gate(ctx.remaining_accounts)?;
+        ctx.accounts.take_from_book()?;
+        ctx.accounts.check_taker_token_account_a()?;
+        ctx.accounts.send_wanted_tokens_to_maker(max_pay, ctx.remaining_accounts)?;
         ctx.accounts.withdraw_and_close_vault()?;
         Ok(())
     }
 
+    pub fn create_market(ctx: Context<CreateMarket>, tick_size: u64, lot_size: u64, min_order_size: u64) -> Result<()> {
+        ctx.accounts.create_market(tick_size, lot_size, min_order_size, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn match_offers(ctx: Context<MatchOffers>) -> Result<()> {
+        ctx.accounts.match_offers()?;
+        Ok(())
+    }
+
+    pub fn configure_matching(ctx: Context<ConfigureMatching>, keeper_bps: u16) -> Result<()> {
+        ctx.accounts.configure(keeper_bps, ctx.bumps)?;
+        Ok(())
+    }
+
+    //`reject` is a bitmask of the `MintPolicy` risk flags
+    pub fn configure_mint_policy(ctx: Context<ConfigureMintPolicy>, reject: u8) -> Result<()> {
+        ctx.accounts.configure(reject, ctx.bumps)?;
+        Ok(())
+    }
+
     pub fn refund(ctx: Context<RefundOffer>) -> Result<()> {
+        ctx.accounts.delist()?;
         ctx.accounts.withdraw_and_close_vault()?;
         Ok(())
     }
+
+    pub fn bulk_refund<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkRefundOffer<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_all(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn batch_exchange<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTakeOffer<'info>>,
+        total: u64,
+        min_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.fill(ctx.remaining_accounts, total, min_receive)?;
+        Ok(())
+    }
+
+    pub fn make_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBasket<'info>>,
+        seed: u64,
+        deposits: Vec<u64>,
+        wanted: Vec<Leg>,
+    ) -> Result<()> {
+        ctx.accounts
+            .make_basket(ctx.remaining_accounts, seed, deposits, wanted, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn exchange_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeBasket<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.take_basket(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn refund_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBasket<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_basket(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn start_auction(
+        ctx: Context<StartAuction>,
+        end_time: i64,
+        min_increment: u64,
+        extension: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .start_auction(end_time, min_increment, extension, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        ctx.accounts.place_bid(amount, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        ctx.accounts.send_vault_to_winner()?;
+        ctx.accounts.send_bid_to_maker()?;
+        Ok(())
+    }
+
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        ctx.accounts.withdraw_and_close_vault()?;
+        Ok(())
+    }
+
+    pub fn start_sealed_auction(
+        ctx: Context<StartSealedAuction>,
+        commit_end: i64,
+        reveal_end: i64,
+        collateral: u64,
+        pricing: SealedPricing,
+        forfeit_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.start_sealed_auction(
+            commit_end,
+            reveal_end,
+            collateral,
+            pricing,
+            forfeit_bps,
+            ctx.bumps,
+        )?;
+        Ok(())
+    }
+
+    pub fn commit_bid(ctx: Context<CommitBid>, hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.commit_bid(hash, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn reveal_bid(ctx: Context<RevealBid>, bid: u64, salt: [u8; 32]) -> Result<()> {
+        ctx.accounts.reveal_bid(bid, salt)?;
+        Ok(())
+    }
+
+    pub fn settle_sealed_auction(ctx: Context<SettleSealedAuction>) -> Result<()> {
+        ctx.accounts.send_vault_to_winner()?;
+        ctx.accounts.send_price_to_maker()?;
+        Ok(())
+    }
+
+    pub fn claim_commitment(ctx: Context<ClaimCommitment>) -> Result<()> {
+        ctx.accounts.claim()?;
+        Ok(())
+    }
+
+    pub fn cancel_sealed_auction(ctx: Context<CancelSealedAuction>) -> Result<()> {
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.close_auction()?;
+        Ok(())
+    }
+
+    pub fn expire_sealed_auction(ctx: Context<ExpireSealedAuction>) -> Result<()> {
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.close_auction()?;
+        Ok(())
+    }
+
+    pub fn start_crowd_fill(ctx: Context<StartCrowdFill>, deadline: i64) -> Result<()> {
+        ctx.accounts.start_crowd_fill(deadline, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+        ctx.accounts.contribute(amount, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn settle_crowd_fill(ctx: Context<SettleCrowdFill>) -> Result<()> {
+        ctx.accounts.settle()?;
+        Ok(())
+    }
+
+    pub fn claim_crowd_fill(ctx: Context<ClaimCrowdFill>) -> Result<()> {
+        ctx.accounts.claim()?;
+        Ok(())
+    }
+
+    pub fn reclaim_contribution(ctx: Context<ReclaimContribution>) -> Result<()> {
+        ctx.accounts.reclaim()?;
+        Ok(())
+    }
+
+    pub fn cancel_crowd_fill(ctx: Context<CancelCrowdFill>) -> Result<()> {
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.close_pool()?;
+        Ok(())
+    }
+
+    pub fn start_htlc(ctx: Context<StartHtlc>, taker: Pubkey, hashlock: [u8; 32], timelock: i64) -> Result<()> {
+        ctx.accounts.start_htlc(taker, hashlock, timelock, ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn claim_htlc(ctx: Context<ClaimHtlc>, preimage: [u8; 32]) -> Result<()> {
+        ctx.accounts.claim(preimage)?;
+        Ok(())
+    }
+
+    pub fn refund_htlc(ctx: Context<RefundHtlc>) -> Result<()> {
+        ctx.accounts.refund()?;
+        Ok(())
+    }
+
+    pub fn make_pnft(ctx: Context<MakePnft>, seed: u64, receive: u64) -> Result<()> {
+        ctx.accounts.init_escrow(seed, receive, ctx.bumps)?;
+        ctx.accounts.deposit()?;
+        Ok(())
+    }
+
+    //the remaining accounts pay the creators when royalties are enforced
+    pub fn exchange_pnft<'info>(ctx: Context<'_, '_, 'info, 'info, TakePnft<'info>>) -> Result<()> {
+        ctx.accounts.send_wanted_tokens_to_maker(ctx.remaining_accounts)?;
+        ctx.accounts.withdraw()?;
+        Ok(())
+    }
+
+    pub fn refund_pnft(ctx: Context<RefundPnft>) -> Result<()> {
+        ctx.accounts.refund()?;
+        Ok(())
+    }
+
+    pub fn migrate(ctx: Context<MigrateEscrow>) -> Result<()> {
+        ctx.accounts.migrate()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::constants::ANCHOR_DESCRIMINATOR;
+
+//Merkle root of the takers allowed to fill an escrow. Leaves commit to a taker
+//and their cap, see `Allowlist::leaf`.
+#[account]
+#[derive(InitSpace)]
+pub struct Allowlist {
+    pub escrow: Pubkey,
+    pub root: [u8; 32],
+    pub bump: u8,
+}
+
+impl Allowlist {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Allowlist::INIT_SPACE;
+
+    //`cap` is the most of mint A the taker may buy under this root, 0 for no cap.
+    //Leaves and nodes are prefixed differently so a node can't pass as a leaf.
+    pub fn leaf(taker: &Pubkey, cap: u64) -> [u8; 32] {
+        hashv(&[&[0], taker.as_ref(), &cap.to_le_bytes()]).to_bytes()
+    }
+
+    //Pairs are hashed in sorted order, so the proof needs no left/right flags
+    pub fn verify(&self, proof: &[[u8; 32]], leaf: [u8; 32]) -> bool {
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+            hashv(&[&[1], &left, &right]).to_bytes()
+        });
+        computed == self.root
+    }
+}
+
+//How much of mint A a taker has bought under an allowlist root. Keyed by the
+//maker and the root, so a cap holds across every escrow of the same sale.
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    pub taker: Pubkey,
+    pub filled: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+//English auction over the vault of an `Escrow` of kind `EnglishAuction`
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    pub escrow: Pubkey,
+    pub min_increment: u64,
+    pub end_time: i64,
+    //a bid this close to the end pushes the end back to `now + extension`
+    pub extension: i64,
+    pub top_bid: u64,
+    //Pubkey::default() until the first bid
+    pub top_bidder: Pubkey,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub fn has_bids(&self) -> bool {
+        self.top_bidder != Pubkey::default()
+    }
+}
+
+//A bidder's deposit of mint B, held in the bid's own vault until they are outbid or win
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR, MAX_BASKET_LEGS};
+
+//An offer of several mints for several others, taken whole by `exchange_basket`.
+//Every offered mint has its own vault, the associated token account of the basket.
+#[account]
+#[derive(InitSpace)]
+pub struct Basket {
+    pub seed: u64,
+    pub maker: Pubkey,
+    //the amounts are whatever the vaults hold
+    #[max_len(MAX_BASKET_LEGS)]
+    pub offered_mints: Vec<Pubkey>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub wanted: Vec<Leg>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct Leg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl Basket {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Basket::INIT_SPACE;
+
+    //1 to MAX_BASKET_LEGS mints a side, none of them twice
+    pub fn is_valid(offered_mints: &[Pubkey], wanted: &[Leg]) -> bool {
+        let distinct = |mints: &[Pubkey]| mints.iter().enumerate().all(|(i, mint)| !mints[..i].contains(mint));
+        let wanted_mints: Vec<Pubkey> = wanted.iter().map(|leg| leg.mint).collect();
+
+        (1..=MAX_BASKET_LEGS).contains(&offered_mints.len())
+            && (1..=MAX_BASKET_LEGS).contains(&wanted.len())
+            && distinct(offered_mints)
+            && distinct(&wanted_mints)
+    }
+}
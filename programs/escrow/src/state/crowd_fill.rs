@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+//Pools many takers' mint B towards an `Escrow` of kind `CrowdFill`. Once `target`
+//is raised the maker is paid and every contributor claims a pro-rata share of the
+//vault, if the deadline passes first contributors take their deposits back.
+#[account]
+#[derive(InitSpace)]
+pub struct CrowdFill {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub token_mint_b: Pubkey,
+    //the escrow's `receive_amount`
+    pub target: u64,
+    pub raised: u64,
+    //what the vault held when the fill was settled, shares are taken from this
+    pub offered: u64,
+    pub deadline: i64,
+    //contributions that haven't been claimed yet
+    pub contributors: u32,
+    pub settled: bool,
+    pub bump: u8,
+}
+
+impl CrowdFill {
+    pub fn is_filled(&self) -> bool {
+        self.raised == self.target
+    }
+
+    //Rounded down, the last claim takes whatever the vault has left
+    pub fn share(&self, contributed: u64) -> u64 {
+        (self.offered as u128 * contributed as u128 / self.target as u128) as u64
+    }
+}
+
+//What one taker has put into a crowd fill, held in the fill's pool
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub crowd_fill: Pubkey,
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
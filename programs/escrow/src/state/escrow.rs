@@ -1,5 +1,11 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{ANCHOR_DESCRIMINATOR, EXPONENTIAL_HALVINGS};
+use crate::error::ErrorCode;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_interface::Mint;
+use crate::state::PriceFeed;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Escrow {
@@ -7,12 +13,266 @@ pub struct Escrow {
     pub maker: Pubkey,
     pub token_mint_a: Pubkey,
     pub token_mint_b: Pubkey,
+    //for dutch auctions this is the starting price
     pub receive_amount: u64,
     pub bump: u8,
+    pub version: u8,
+    pub price_curve: PriceCurve,
+    pub floor_amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub kind: EscrowKind,
+    //the price feed of `PriceCurve::Oracle` offers
+    pub oracle: Pubkey,
+    //premium over the oracle price the maker asks for
+    pub spread_bps: u16,
+    //how old, in seconds, the feed's price may be
+    pub max_age: i64,
+    //widest confidence interval accepted, relative to the price
+    pub max_confidence_bps: u16,
+    //a `Conditional` order goes live once `oracle` crosses trigger_price * 10^trigger_expo
+    pub trigger_price: i64,
+    pub trigger_expo: i32,
+    pub trigger_direction: TriggerDirection,
+    //paid out of the vault, in mint A, to whoever triggers the order
+    pub trigger_tip: u64,
+    //whether `PaymentOptions` lists other mints the taker may pay with
+    pub accepts_other_mints: bool,
+    //an Ask sells `token_mint_a`, a Bid buys `token_mint_b`, both lock `token_mint_a`
+    pub side: Side,
+    //the `Market` whose book lists the escrow, default when it isn't listed
+    pub market: Pubkey,
+    //whether only the takers in its `Allowlist` can fill it
+    pub allowlisted: bool,
+    //whether the taker must meet its `TokenGate`
+    pub gated: bool,
+    //`MintPolicy` flags raised by either mint, so UIs can warn takers
+    pub mint_risks: u8,
+    //whether `exchange` pays the creators of an NFT out of the taker's payment
+    pub royalty_policy: RoyaltyPolicy,
+    //whether `exchange` splits the taker's payment between its `Recipients`
+    pub split: bool,
+    //token account of `token_mint_b` the maker is paid into, default for their ATA
+    pub destination: Pubkey,
+    //new fields are carved out of here so the account size stays the same
+    pub reserved: [u8; 94],
 }
 
 //we don't need the amount offered because we can get it from the vault: ctx.accounts.vault.amount
 
+//Only plain offers can be taken with `exchange`, the other kinds have their own instructions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum EscrowKind {
+    Offer,
+    //see `Auction`, `receive_amount` is the reserve price
+    EnglishAuction,
+    //see `SealedAuction`, `receive_amount` is the reserve price
+    SealedAuction,
+    //waits for its trigger price, `trigger` turns it into an `Offer`
+    Conditional,
+    //see `CrowdFill`, `receive_amount` is what has to be raised
+    CrowdFill,
+    //see `Htlc`, `receive_amount` is unused as the taker pays on another chain
+    Htlc,
+    //holds a pNFT, which only Token Metadata can move, see `TransferPnft`
+    ProgrammableNft,
+}
+
+//Bids and asks are stored and filled the same way, `side` only tells which mint
+//the maker thinks of as the base of the pair
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum Side {
+    Ask,
+    Bid,
+}
+
+//Escrows of older versions read back as `Enforced`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum RoyaltyPolicy {
+    //the creators in the NFT's metadata get `seller_fee_basis_points` of the price
+    Enforced,
+    //the maker keeps the whole price, only the NFT's sole creator can waive them
+    Waived,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum PriceCurve {
+    Fixed,
+    //decays in a straight line from `receive_amount` to `floor_amount`
+    Linear,
+    //what's left above `floor_amount` halves EXPONENTIAL_HALVINGS times over the window
+    Exponential,
+    //priced off `oracle` when taken, `receive_amount` is unused
+    Oracle,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum TriggerDirection {
+    //stop: the oracle price falls to the trigger or below
+    Below,
+    //take-profit: the oracle price rises to the trigger or above
+    Above,
+}
+
+//What `make_conditional` is given to set up the trigger
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct TriggerParams {
+    pub oracle: Pubkey,
+    pub price: i64,
+    pub expo: i32,
+    pub direction: TriggerDirection,
+    pub max_age: i64,
+    pub max_confidence_bps: u16,
+    pub tip: u64,
+}
+
 impl Escrow {
-    pub const LEN: usize = core::mem::size_of::<Escrow>();
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Escrow::INIT_SPACE;
+    //size of the escrows created before the version byte existed (version 0)
+    pub const LEGACY_LEN: usize = ANCHOR_DESCRIMINATOR + 8 + 32 + 32 + 32 + 8 + 1;
+
+    //A fixed price offer with none of the options tied to `exchange`: payment options,
+    //a market listing, an allowlist, a token gate, a split or a custom destination.
+    //Only these can be turned into an auction, a crowd fill or an HTLC.
+    pub fn is_plain_offer(&self) -> bool {
+        self.kind == EscrowKind::Offer
+            && self.price_curve == PriceCurve::Fixed
+            && !self.accepts_other_mints
+            && self.market == Pubkey::default()
+            && !self.allowlisted
+            && !self.gated
+            && !self.split
+            && self.destination == Pubkey::default()
+    }
+
+    //Whether the taker owes royalties, which is the case when mint A is an NFT
+    pub fn owes_royalties(&self, token_mint_a: &Mint) -> bool {
+        self.royalty_policy == RoyaltyPolicy::Enforced && token_mint_a.decimals == 0 && token_mint_a.supply == 1
+    }
+
+    //Where the maker is paid in `mint`: their `destination` for `token_mint_b` when
+    //they set one, their ATA otherwise
+    pub fn proceeds_account(&self, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        if *mint == self.token_mint_b && self.destination != Pubkey::default() {
+            return self.destination;
+        }
+        get_associated_token_address_with_program_id(&self.maker, mint, token_program)
+    }
+
+    //The mint being bought or sold
+    pub fn base_mint(&self) -> Pubkey {
+        match self.side {
+            Side::Ask => self.token_mint_a,
+            Side::Bid => self.token_mint_b,
+        }
+    }
+
+    //The mint the base is priced in
+    pub fn quote_mint(&self) -> Pubkey {
+        match self.side {
+            Side::Ask => self.token_mint_b,
+            Side::Bid => self.token_mint_a,
+        }
+    }
+
+    //What the taker has to pay for the whole vault at `now`
+    pub fn receive_amount_at(&self, now: i64) -> u64 {
+        if matches!(self.price_curve, PriceCurve::Fixed | PriceCurve::Oracle) || now <= self.start_time {
+            return self.receive_amount;
+        }
+        if now >= self.end_time {
+            return self.floor_amount;
+        }
+
+        let excess = (self.receive_amount - self.floor_amount) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        let elapsed = (now - self.start_time) as u128;
+
+        let excess = match self.price_curve {
+            PriceCurve::Fixed | PriceCurve::Oracle => unreachable!(),
+            PriceCurve::Linear => excess * (duration - elapsed) / duration,
+            PriceCurve::Exponential => {
+                let half_life = (duration / EXPONENTIAL_HALVINGS as u128).max(1);
+                let halvings = (elapsed / half_life).min(127) as u32;
+                let excess = excess >> halvings;
+                //straight line between two halvings
+                excess - excess * (elapsed % half_life) / (2 * half_life)
+            }
+        };
+
+        self.floor_amount + excess as u64
+    }
+
+    //How much of the vault a taker gets for paying `pay` of the `receive_amount`,
+    //rounded down so partial fills never beat the maker's price
+    pub fn fill_amount(&self, vault_amount: u64, pay: u64) -> Option<u64> {
+        if pay >= self.receive_amount {
+            return Some(vault_amount);
+        }
+        let amount = (vault_amount as u128)
+            .checked_mul(pay as u128)?
+            .checked_div(self.receive_amount as u128)?;
+        u64::try_from(amount).ok()
+    }
+
+    //What the taker has to pay for `vault_amount` at the feed's price plus the spread,
+    //rounded up so the maker never gets less than asked
+    pub fn oracle_receive_amount(&self, feed: &PriceFeed, vault_amount: u64, decimals_a: u8, decimals_b: u8, now: i64) -> Result<u64> {
+        self.check_feed(feed, now)?;
+
+        //base units of B = base units of A * 10^-decimals_a * price * 10^expo * 10^decimals_b
+        let scale = decimals_b as i32 - decimals_a as i32 + feed.expo;
+        let power = 10u128
+            .checked_pow(scale.unsigned_abs())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let mut numerator = (vault_amount as u128)
+            .checked_mul(feed.price as u128)
+            .and_then(|n| n.checked_mul(10_000 + self.spread_bps as u128))
+            .ok_or(ErrorCode::MathOverflow)?;
+        let mut denominator = 10_000u128;
+        if scale >= 0 {
+            numerator = numerator.checked_mul(power).ok_or(ErrorCode::MathOverflow)?;
+        } else {
+            denominator = denominator.checked_mul(power).ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        u64::try_from(numerator.div_ceil(denominator)).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    //Whether the feed has crossed the trigger of a conditional order
+    pub fn is_triggered(&self, feed: &PriceFeed, now: i64) -> Result<bool> {
+        self.check_feed(feed, now)?;
+
+        //compare both prices at the smaller of the two exponents
+        let expo = feed.expo.min(self.trigger_expo);
+        let scale = |price: i64, from: i32| -> Result<i128> {
+            let power = from
+                .checked_sub(expo)
+                .and_then(|power| u32::try_from(power).ok())
+                .ok_or(error!(ErrorCode::InvalidTrigger))?;
+            10i128
+                .checked_pow(power)
+                .and_then(|power| (price as i128).checked_mul(power))
+                .ok_or(error!(ErrorCode::MathOverflow))
+        };
+        let price = scale(feed.price, feed.expo)?;
+        let trigger = scale(self.trigger_price, self.trigger_expo)?;
+
+        Ok(match self.trigger_direction {
+            TriggerDirection::Below => price <= trigger,
+            TriggerDirection::Above => price >= trigger,
+        })
+    }
+
+    fn check_feed(&self, feed: &PriceFeed, now: i64) -> Result<()> {
+        require!(feed.price > 0, ErrorCode::InvalidPriceFeed);
+        require!(now.saturating_sub(feed.publish_time) <= self.max_age, ErrorCode::StalePrice);
+        require!(
+            feed.conf as u128 * 10_000 <= feed.price as u128 * self.max_confidence_bps as u128,
+            ErrorCode::PriceTooUncertain
+        );
+        Ok(())
+    }
 }
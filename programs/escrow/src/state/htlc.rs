@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+//Hash time lock on an `Escrow` of kind `Htlc`: `taker` can claim the vault with
+//the preimage of `hashlock` until `timelock`, after which the maker can refund it.
+#[account]
+#[derive(InitSpace)]
+pub struct Htlc {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    //sha256 of the secret, the same hash the counterparty chain locks with
+    pub hashlock: [u8; 32],
+    pub timelock: i64,
+    pub bump: u8,
+}
+
+impl Htlc {
+    pub fn unlocks(&self, preimage: &[u8; 32]) -> bool {
+        hash(preimage).to_bytes() == self.hashlock
+    }
+}
+
+//Logged when the vault is claimed so the other side of the swap can read the preimage
+#[event]
+pub struct HtlcClaimed {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub preimage: [u8; 32],
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR, MAX_BOOK_ORDERS};
+use crate::state::Side;
+
+//The order book of a mint pair. Asks are escrows selling `base_mint` for
+//`quote_mint`, bids the other way around, each side kept in its own `Book`.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    //prices, in quote per lot, are multiples of this
+    pub tick_size: u64,
+    //base amounts are multiples of this
+    pub lot_size: u64,
+    //smallest base amount an order can be for
+    pub min_order_size: u64,
+    pub bump: u8,
+}
+
+impl Market {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Market::INIT_SPACE;
+
+    pub fn is_valid(&self) -> bool {
+        self.base_mint != self.quote_mint && self.tick_size > 0 && self.lot_size > 0 && self.min_order_size > 0
+    }
+
+    //Quote per lot of an order for `base` against `quote`, None when it is below
+    //the minimum size or off the lot and tick grid
+    pub fn price(&self, base: u64, quote: u64) -> Option<u64> {
+        if base < self.min_order_size || !divides(self.lot_size, base) {
+            return None;
+        }
+        let lots = base / self.lot_size;
+        if !divides(lots, quote) {
+            return None;
+        }
+        let price = quote / lots;
+        (price > 0 && divides(self.tick_size, price)).then_some(price)
+    }
+}
+
+//`u64::is_multiple_of` is newer than the Solana toolchain's rustc
+fn divides(step: u64, value: u64) -> bool {
+    value / step * step == value
+}
+
+//One side of a market, best price first and oldest first at the same price
+#[account]
+#[derive(InitSpace)]
+pub struct Book {
+    pub market: Pubkey,
+    pub side: Side,
+    #[max_len(MAX_BOOK_ORDERS)]
+    pub orders: Vec<BookOrder>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct BookOrder {
+    pub escrow: Pubkey,
+    //quote per lot
+    pub price: u64,
+    //in base
+    pub size: u64,
+}
+
+impl Book {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Book::INIT_SPACE;
+
+    pub fn seed(side: Side) -> &'static [u8] {
+        match side {
+            Side::Ask => b"asks",
+            Side::Bid => b"bids",
+        }
+    }
+
+    pub fn best(&self) -> Option<&BookOrder> {
+        self.orders.first()
+    }
+
+    //When the book is full the worst order is evicted to make room for a better one,
+    //so nobody can lock a market by filling it with far off prices. False when the
+    //book is full and `order` isn't better than any resting one.
+    pub fn insert(&mut self, order: BookOrder) -> bool {
+        let index = self
+            .orders
+            .iter()
+            .position(|resting| match self.side {
+                Side::Ask => resting.price > order.price,
+                Side::Bid => resting.price < order.price,
+            })
+            .unwrap_or(self.orders.len());
+        if self.orders.len() >= MAX_BOOK_ORDERS {
+            if index == self.orders.len() {
+                return false;
+            }
+            self.orders.pop();
+        }
+        self.orders.insert(index, order);
+        true
+    }
+
+    //false when `escrow` isn't in the book
+    pub fn remove(&mut self, escrow: &Pubkey) -> bool {
+        let Some(index) = self.orders.iter().position(|order| order.escrow == *escrow) else {
+            return false;
+        };
+        self.orders.remove(index);
+        true
+    }
+}
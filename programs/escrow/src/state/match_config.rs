@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Escrow;
+
+//How `match_offers` splits the spread between two crossing offers. Without it
+//the keeper keeps the whole spread.
+#[account]
+#[derive(InitSpace)]
+pub struct MatchConfig {
+    //share of the spread paid to the keeper, the rest goes to the maker selling mint A
+    pub keeper_bps: u16,
+    pub bump: u8,
+}
+
+//What matching two fixed-price offers moves: `escrow_a` sells A for B, `escrow_b` sells B for A
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchFill {
+    //of mint A, from the first vault to the second maker
+    pub amount_a: u64,
+    //of mint B, what the first maker asks for `amount_a`
+    pub price_b: u64,
+    //of mint B, what leaves the second vault for `amount_a`
+    pub released_b: u64,
+}
+
+impl MatchFill {
+    //Fills the smaller of the two offers, rounding both prices in their maker's favor.
+    //None when the offers don't cross.
+    pub fn compute(escrow_a: &Escrow, vault_a: u64, escrow_b: &Escrow, vault_b: u64) -> Option<Self> {
+        let amount_a = vault_a.min(escrow_b.receive_amount);
+        if amount_a == 0 {
+            return None;
+        }
+
+        let price_b = (amount_a as u128 * escrow_a.receive_amount as u128).div_ceil(vault_a as u128);
+        let released_b = amount_a as u128 * vault_b as u128 / escrow_b.receive_amount as u128;
+        if released_b < price_b {
+            return None;
+        }
+
+        Some(Self {
+            amount_a,
+            price_b: u64::try_from(price_b).ok()?,
+            released_b: u64::try_from(released_b).ok()?,
+        })
+    }
+
+    pub fn spread(&self) -> u64 {
+        self.released_b - self.price_b
+    }
+
+    pub fn keeper_share(&self, keeper_bps: u16) -> u64 {
+        (self.spread() as u128 * keeper_bps as u128 / 10_000) as u64
+    }
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::{PROGRAMMABLE_NON_FUNGIBLE, PROGRAMMABLE_NON_FUNGIBLE_EDITION};
+
+//The Metaplex token metadata program
+pub const TOKEN_METADATA_PROGRAM: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+//The leading fields of a Metaplex `Metadata` account, as far as the collection.
+//They are read straight from the account so the program doesn't pull in the
+//Metaplex crate for a handful of fields.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Metadata {
+    pub key: u8,
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<Collection>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+impl Metadata {
+    //`Key::MetadataV1`
+    pub const KEY: u8 = 4;
+
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", TOKEN_METADATA_PROGRAM.as_ref(), mint.as_ref()],
+            &TOKEN_METADATA_PROGRAM,
+        )
+        .0
+    }
+
+    //Loads the metadata of `mint`, checking it is the account Metaplex derives for it
+    pub fn load(info: &AccountInfo, mint: &Pubkey) -> Result<Self> {
+        require!(
+            info.owner == &TOKEN_METADATA_PROGRAM && info.key() == Self::address(mint),
+            ErrorCode::InvalidMetadata
+        );
+        let data = info.try_borrow_data()?;
+        let metadata = Metadata::deserialize(&mut &data[..]).map_err(|_| error!(ErrorCode::InvalidMetadata))?;
+        require!(metadata.key == Self::KEY && metadata.mint == *mint, ErrorCode::InvalidMetadata);
+        Ok(metadata)
+    }
+
+    //What each creator is owed of `price`, rounded down, leaving out those without a share
+    pub fn royalties(&self, price: u64) -> Vec<(Pubkey, u64)> {
+        let total = price as u128 * self.seller_fee_basis_points.min(10_000) as u128 / 10_000;
+        self.creators
+            .iter()
+            .flatten()
+            .filter(|creator| creator.share > 0)
+            .map(|creator| (creator.address, (total * creator.share as u128 / 100) as u64))
+            .collect()
+    }
+
+    //Whether `key` is verified as the only creator with a share, so waiving the
+    //royalties takes nothing from anyone else
+    pub fn is_sole_creator(&self, key: &Pubkey) -> bool {
+        let mut paid = self.creators.iter().flatten().filter(|creator| creator.share > 0).peekable();
+        paid.peek().is_some() && paid.all(|creator| creator.verified && creator.address == *key)
+    }
+
+    pub fn is_programmable(&self) -> bool {
+        matches!(self.token_standard, Some(PROGRAMMABLE_NON_FUNGIBLE | PROGRAMMABLE_NON_FUNGIBLE_EDITION))
+    }
+
+    pub fn in_collection(&self, collection: &Pubkey) -> bool {
+        self.collection.is_some_and(|c| c.verified && c.key == *collection)
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use anchor_spl::token_2022::spl_token_2022::extension::default_account_state::DefaultAccountState;
+use anchor_spl::token_2022::spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHook;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::{AccountState, Mint};
+
+//Which mint risks `make` refuses for this deployment, the others are only flagged
+//in `Escrow::mint_risks`. Until it is configured `DEFAULT_REJECT` is refused.
+#[account]
+#[derive(InitSpace)]
+pub struct MintPolicy {
+    //bitmask of the `MintPolicy` risk flags
+    pub reject: u8,
+    pub bump: u8,
+}
+
+impl MintPolicy {
+    //someone can transfer or burn the vault's tokens
+    pub const PERMANENT_DELEGATE: u8 = 1 << 0;
+    //new token accounts, the vault or the taker's, start out frozen
+    pub const DEFAULT_FROZEN: u8 = 1 << 1;
+    //the tokens can't leave the account they were minted to
+    pub const NON_TRANSFERABLE: u8 = 1 << 2;
+    //someone can freeze the vault
+    pub const FREEZE_AUTHORITY: u8 = 1 << 3;
+    //every transfer runs a program chosen by the mint, which may fail it
+    pub const TRANSFER_HOOK: u8 = 1 << 4;
+    pub const ALL: u8 = (1 << 5) - 1;
+    //the risks that can take or strand the vault's tokens, refused until a policy is configured
+    pub const DEFAULT_REJECT: u8 = Self::PERMANENT_DELEGATE | Self::DEFAULT_FROZEN | Self::NON_TRANSFERABLE;
+
+    //Refuses the `risks` the policy stored in `info` rejects, or `DEFAULT_REJECT`
+    //while the policy account isn't initialized
+    pub fn enforce(info: &AccountInfo, risks: u8) -> Result<()> {
+        let reject = if info.owner == &crate::ID {
+            MintPolicy::try_deserialize(&mut &info.try_borrow_data()?[..])?.reject
+        } else {
+            Self::DEFAULT_REJECT
+        };
+        require!(risks & reject == 0, ErrorCode::RiskyMint);
+        Ok(())
+    }
+
+    //The risk flags of a mint account of either token program
+    pub fn risks(data: &[u8]) -> Result<u8> {
+        let mint = StateWithExtensions::<Mint>::unpack(data)?;
+
+        let mut risks = 0;
+        if mint.base.freeze_authority.is_some() {
+            risks |= Self::FREEZE_AUTHORITY;
+        }
+        for extension in mint.get_extension_types()? {
+            risks |= match extension {
+                ExtensionType::PermanentDelegate => {
+                    let delegate = mint.get_extension::<PermanentDelegate>()?.delegate;
+                    if Option::<Pubkey>::from(delegate).is_some() { Self::PERMANENT_DELEGATE } else { 0 }
+                }
+                ExtensionType::DefaultAccountState => {
+                    let state = mint.get_extension::<DefaultAccountState>()?.state;
+                    if state == AccountState::Frozen as u8 { Self::DEFAULT_FROZEN } else { 0 }
+                }
+                ExtensionType::NonTransferable => Self::NON_TRANSFERABLE,
+                ExtensionType::TransferHook => {
+                    let program_id = mint.get_extension::<TransferHook>()?.program_id;
+                    if Option::<Pubkey>::from(program_id).is_some() { Self::TRANSFER_HOOK } else { 0 }
+                }
+                _ => 0,
+            };
+        }
+
+        Ok(risks)
+    }
+}
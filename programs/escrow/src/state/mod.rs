@@ -1,3 +1,33 @@
 pub mod escrow;
+pub mod auction;
+pub mod sealed_auction;
+pub mod price_feed;
+pub mod basket;
+pub mod payment_options;
+pub mod match_config;
+pub mod market;
+pub mod crowd_fill;
+pub mod htlc;
+pub mod allowlist;
+pub mod metadata;
+pub mod token_gate;
+pub mod mint_policy;
+pub mod pnft;
+pub mod recipients;
 
-pub use escrow::*;
\ No newline at end of file
+pub use escrow::*;
+pub use auction::*;
+pub use sealed_auction::*;
+pub use price_feed::*;
+pub use basket::*;
+pub use payment_options::*;
+pub use match_config::*;
+pub use market::*;
+pub use crowd_fill::*;
+pub use htlc::*;
+pub use allowlist::*;
+pub use metadata::*;
+pub use token_gate::*;
+pub use mint_policy::*;
+pub use pnft::*;
+pub use recipients::*;
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR, MAX_PAYMENT_MINTS};
+use crate::state::Leg;
+
+//Mints an escrow accepts on top of `token_mint_b`, each at its own fixed amount.
+//Only read while `escrow.accepts_other_mints` is set.
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentOptions {
+    pub escrow: Pubkey,
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub accepted: Vec<Leg>,
+    pub bump: u8,
+}
+
+impl PaymentOptions {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + PaymentOptions::INIT_SPACE;
+
+    pub fn amount_for(&self, mint: &Pubkey) -> Option<u64> {
+        self.accepted.iter().find(|leg| leg.mint == *mint).map(|leg| leg.amount)
+    }
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::state::TOKEN_METADATA_PROGRAM;
+
+//`TokenStandard::ProgrammableNonFungible` and its editions
+pub const PROGRAMMABLE_NON_FUNGIBLE: u8 = 4;
+pub const PROGRAMMABLE_NON_FUNGIBLE_EDITION: u8 = 5;
+
+//The master edition of an NFT, its mint and freeze authority
+pub fn edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM.as_ref(), mint.as_ref(), b"edition"],
+        &TOKEN_METADATA_PROGRAM,
+    )
+    .0
+}
+
+//Where Token Metadata keeps the lock and delegate state of a pNFT's token account
+pub fn token_record_address(mint: &Pubkey, token: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM.as_ref(), mint.as_ref(), b"token_record", token.as_ref()],
+        &TOKEN_METADATA_PROGRAM,
+    )
+    .0
+}
+
+//The accounts of Token Metadata's `Transfer` instruction. pNFTs stay frozen in
+//their token account, so they can only be moved through it, and their rule set,
+//royalty enforcement included, decides whether the transfer goes through.
+pub struct TransferPnft<'info> {
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    //created by Token Metadata when it doesn't exist yet
+    pub destination_token: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    //only needed when the pNFT has a rule set
+    pub authorization_rules_program: Option<AccountInfo<'info>>,
+    pub authorization_rules: Option<AccountInfo<'info>>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+impl<'info> TransferPnft<'info> {
+    //`MetadataInstruction::Transfer`
+    const DISCRIMINATOR: u8 = 49;
+
+    //`Transfer(TransferArgs::V1 { amount, authorization_data: None })`
+    pub fn data(amount: u64) -> Vec<u8> {
+        let mut data = vec![Self::DISCRIMINATOR, 0];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(0);
+        data
+    }
+
+    pub fn instruction(&self, amount: u64) -> Instruction {
+        //left out optional accounts are passed as the Token Metadata program
+        let optional = |account: &Option<AccountInfo<'info>>| {
+            AccountMeta::new_readonly(account.as_ref().map_or(TOKEN_METADATA_PROGRAM, |account| account.key()), false)
+        };
+
+        Instruction {
+            program_id: TOKEN_METADATA_PROGRAM,
+            accounts: vec![
+                AccountMeta::new(self.token.key(), false),
+                AccountMeta::new_readonly(self.token_owner.key(), false),
+                AccountMeta::new(self.destination_token.key(), false),
+                AccountMeta::new_readonly(self.destination_owner.key(), false),
+                AccountMeta::new_readonly(self.mint.key(), false),
+                AccountMeta::new(self.metadata.key(), false),
+                AccountMeta::new_readonly(self.edition.key(), false),
+                AccountMeta::new(self.token_record.key(), false),
+                AccountMeta::new(self.destination_token_record.key(), false),
+                AccountMeta::new_readonly(self.authority.key(), true),
+                AccountMeta::new(self.payer.key(), true),
+                AccountMeta::new_readonly(self.system_program.key(), false),
+                AccountMeta::new_readonly(self.sysvar_instructions.key(), false),
+                AccountMeta::new_readonly(self.token_program.key(), false),
+                AccountMeta::new_readonly(self.associated_token_program.key(), false),
+                optional(&self.authorization_rules_program),
+                optional(&self.authorization_rules),
+            ],
+            data: Self::data(amount),
+        }
+    }
+
+    pub fn invoke_signed(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let mut infos = vec![
+            self.token.clone(),
+            self.token_owner.clone(),
+            self.destination_token.clone(),
+            self.destination_owner.clone(),
+            self.mint.clone(),
+            self.metadata.clone(),
+            self.edition.clone(),
+            self.token_record.clone(),
+            self.destination_token_record.clone(),
+            self.authority.clone(),
+            self.payer.clone(),
+            self.system_program.clone(),
+            self.sysvar_instructions.clone(),
+            self.token_program.clone(),
+            self.associated_token_program.clone(),
+            self.token_metadata_program.clone(),
+        ];
+        infos.extend(self.authorization_rules_program.clone());
+        infos.extend(self.authorization_rules.clone());
+
+        invoke_signed(&self.instruction(amount), &infos, signer_seeds).map_err(Into::into)
+    }
+}
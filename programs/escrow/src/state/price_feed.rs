@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+
+//The Pyth receiver program, owner of the `PriceUpdateV2` accounts behind Pyth's push feeds
+pub const PYTH_RECEIVER_PROGRAM: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+//programs/mock-oracle. Anyone can set the price of its feeds, so only builds with
+//the `mock-oracle` feature, for tests and local validators, accept them.
+#[cfg(feature = "mock-oracle")]
+pub const MOCK_ORACLE_PROGRAM: Pubkey = pubkey!("7gfYkN3NfEQPaE35GvbmLJgZLmSvgdAXVV37TSRHCtJ6");
+
+//The price fields of a feed: `price * 10^expo` of mint B per mint A, give or take `conf`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+//A Pyth receiver `PriceUpdateV2` account, read straight from the account so the
+//program doesn't pull in the Pyth SDK for a handful of fields
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+    pub posted_slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+impl PriceUpdateV2 {
+    //sha256("account:PriceUpdateV2")[..8]
+    pub const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+}
+
+impl PriceFeed {
+    //sha256("account:PriceFeed")[..8], the mock oracle feed's anchor discriminator
+    pub const DISCRIMINATOR: [u8; 8] = [189, 103, 252, 23, 152, 35, 243, 156];
+
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        require!(data.len() >= 8, ErrorCode::InvalidPriceFeed);
+        let (discriminator, mut data) = data.split_at(8);
+
+        if info.owner == &PYTH_RECEIVER_PROGRAM && discriminator == PriceUpdateV2::DISCRIMINATOR {
+            let update = PriceUpdateV2::deserialize(&mut data).map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+            //partially verified updates were checked against too few guardian signatures
+            require!(update.verification_level == VerificationLevel::Full, ErrorCode::InvalidPriceFeed);
+            return Ok(PriceFeed {
+                price: update.price,
+                conf: update.conf,
+                expo: update.expo,
+                publish_time: update.publish_time,
+            });
+        }
+
+        #[cfg(feature = "mock-oracle")]
+        if info.owner == &MOCK_ORACLE_PROGRAM && discriminator == Self::DISCRIMINATOR {
+            return PriceFeed::deserialize(&mut data).map_err(|_| error!(ErrorCode::InvalidPriceFeed));
+        }
+
+        err!(ErrorCode::InvalidPriceFeed)
+    }
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ANCHOR_DESCRIMINATOR, MAX_RECIPIENTS};
+
+//Wallets that share what the taker pays for an escrow, in place of its maker.
+//Only read while `escrow.split` is set.
+#[account]
+#[derive(InitSpace)]
+pub struct Recipients {
+    pub escrow: Pubkey,
+    #[max_len(MAX_RECIPIENTS)]
+    pub recipients: Vec<Recipient>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct Recipient {
+    //paid into its ATA of the mint the taker pays with
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+impl Recipients {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + Recipients::INIT_SPACE;
+    pub const TOTAL_BPS: u16 = 10_000;
+
+    //Each recipient's cut of `amount`, rounded down, with the dust going to the first one
+    pub fn split(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        let mut shares: Vec<(Pubkey, u64)> = self.recipients.iter()
+            .map(|recipient| (recipient.wallet, (amount as u128 * recipient.share_bps as u128 / Self::TOTAL_BPS as u128) as u64))
+            .collect();
+        let paid: u64 = shares.iter().map(|(_, share)| share).sum();
+        if let Some((_, first)) = shares.first_mut() {
+            *first += amount - paid;
+        }
+        shares
+    }
+}
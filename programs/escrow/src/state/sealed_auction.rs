@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+//Sealed-bid auction over the vault of an `Escrow` of kind `SealedAuction`.
+//Bidders commit a hash of their bid until `commit_end` and reveal it until `reveal_end`.
+#[account]
+#[derive(InitSpace)]
+pub struct SealedAuction {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub reserve: u64,
+    //deposited with every commitment so that bidders have a reason to reveal
+    pub collateral: u64,
+    pub commit_end: i64,
+    pub reveal_end: i64,
+    pub pricing: SealedPricing,
+    //share of the collateral an unrevealed bidder loses to the maker
+    pub forfeit_bps: u16,
+    pub top_bid: u64,
+    //Pubkey::default() until a valid bid is revealed
+    pub top_bidder: Pubkey,
+    pub second_bid: u64,
+    //commitments that haven't been claimed yet, the auction is closed with the last one
+    pub commitments: u32,
+    pub settled: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum SealedPricing {
+    FirstPrice,
+    //the winner pays the second highest bid, or the reserve if there is none
+    SecondPrice,
+}
+
+impl SealedAuction {
+    pub fn has_bids(&self) -> bool {
+        self.top_bidder != Pubkey::default()
+    }
+
+    pub fn clearing_price(&self) -> u64 {
+        match self.pricing {
+            SealedPricing::FirstPrice => self.top_bid,
+            SealedPricing::SecondPrice => self.second_bid.max(self.reserve),
+        }
+    }
+
+    //Records a revealed bid, keeping track of the two highest ones
+    pub fn record_bid(&mut self, bidder: Pubkey, amount: u64) {
+        if amount > self.top_bid {
+            self.second_bid = self.top_bid;
+            self.top_bid = amount;
+            self.top_bidder = bidder;
+        } else if amount > self.second_bid {
+            self.second_bid = amount;
+        }
+    }
+
+    pub fn forfeit(&self) -> u64 {
+        (self.collateral as u128 * self.forfeit_bps as u128 / 10_000) as u64
+    }
+}
+
+//A bidder's sealed bid, its vault holds the collateral and, once revealed, the rest of the bid
+#[account]
+#[derive(InitSpace)]
+pub struct Commitment {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub hash: [u8; 32],
+    pub revealed: bool,
+    pub bid: u64,
+    pub bump: u8,
+}
+
+impl Commitment {
+    //sha256(bid || salt || bidder), the bidder's key stops others from copying a commitment
+    pub fn hash(bid: u64, salt: &[u8; 32], bidder: &Pubkey) -> [u8; 32] {
+        hashv(&[&bid.to_le_bytes(), salt, bidder.as_ref()]).to_bytes()
+    }
+}
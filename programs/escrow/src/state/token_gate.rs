@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::ANCHOR_DESCRIMINATOR;
+
+//What a taker must hold to fill a gated escrow, checked against the token account
+//they pass in `exchange`'s remaining accounts
+#[account]
+#[derive(InitSpace)]
+pub struct TokenGate {
+    pub escrow: Pubkey,
+    pub kind: GateKind,
+    //the gating mint, or the collection for `GateKind::Collection`
+    pub mint: Pubkey,
+    //in the gating mint's base units, unused for collections
+    pub min_amount: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum GateKind {
+    //at least `min_amount` of `mint`
+    Balance,
+    //any NFT whose metadata has `mint` as its verified collection
+    Collection,
+}
+
+impl TokenGate {
+    pub const LEN: usize = ANCHOR_DESCRIMINATOR + TokenGate::INIT_SPACE;
+}
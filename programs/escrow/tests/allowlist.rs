@@ -0,0 +1,288 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{solana_program::hash::hashv, AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Allowlist, Escrow, Receipt},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const CAP: u64 = 60_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    //TAKER and three others
+    const OTHERS: [Pubkey; 3] = [
+        Pubkey::new_from_array([0x05; 32]),
+        Pubkey::new_from_array([0x06; 32]),
+        Pubkey::new_from_array([0x07; 32]),
+    ];
+
+    fn node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1], &left, &right]).to_bytes()
+    }
+
+    //The 4 leaf tree and TAKER's proof in it
+    fn tree() -> ([u8; 32], Vec<[u8; 32]>) {
+        let leaves = [
+            Allowlist::leaf(&TAKER, CAP),
+            Allowlist::leaf(&OTHERS[0], 0),
+            Allowlist::leaf(&OTHERS[1], 5),
+            Allowlist::leaf(&OTHERS[2], 0),
+        ];
+        let right = node(leaves[2], leaves[3]);
+        (
+            node(node(leaves[0], leaves[1]), right),
+            vec![leaves[1], right],
+        )
+    }
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn allowlist_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"allowlist", escrow().as_ref()], &ID)
+    }
+
+    fn receipt_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"receipt",
+                MAKER.as_ref(),
+                tree().0.as_ref(),
+                TAKER.as_ref(),
+            ],
+            &ID,
+        )
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn allowlist() -> Allowlist {
+        Allowlist {
+            escrow: escrow(),
+            root: tree().0,
+            bump: allowlist_pda().1,
+        }
+    }
+
+    fn set_allowlist_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SetAllowlist { root: tree().0 }.data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(allowlist_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //The price feed, payment options and book are left out by passing the program id
+    fn exchange_with_proof_ix(proof: Vec<[u8; 32]>, cap: u64) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+        instruction.data = escrow::instruction::ExchangeAllowlisted {
+            proof,
+            cap,
+            max_pay: u64::MAX,
+        }
+        .data();
+        for _ in 0..3 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new(allowlist_pda().0, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new(receipt_pda().0, false));
+        instruction
+    }
+
+    //An allowlisted offer when `allowlisted`, with TAKER having already bought `filled`
+    fn accounts(mollusk: &Mollusk, allowlisted: bool, filled: u64) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            allowlisted,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let receipt = Receipt {
+            taker: TAKER,
+            filled,
+            bump: receipt_pda().1,
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                allowlist_pda().0,
+                if allowlisted {
+                    state_account(mollusk, &allowlist(), Allowlist::LEN)
+                } else {
+                    Account::default()
+                },
+            ),
+            (
+                receipt_pda().0,
+                if filled > 0 {
+                    state_account(mollusk, &receipt, 8 + Receipt::INIT_SPACE)
+                } else {
+                    Account::default()
+                },
+            ),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let (_, proof) = tree();
+        let allowlist = allowlist();
+
+        assert!(allowlist.verify(&proof, Allowlist::leaf(&TAKER, CAP)));
+        //a different cap, or another taker's leaf, doesn't verify
+        assert!(!allowlist.verify(&proof, Allowlist::leaf(&TAKER, 0)));
+        assert!(!allowlist.verify(&proof, Allowlist::leaf(&OTHERS[0], 0)));
+    }
+
+    #[test]
+    fn test_set_allowlist() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &set_allowlist_ix(),
+            &accounts(&mollusk, false, 0),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert!(escrow.allowlisted);
+
+        let allowlist = result.get_account(&allowlist_pda().0).unwrap();
+        let allowlist = Allowlist::try_deserialize(&mut allowlist.data.as_slice()).unwrap();
+        assert_eq!(allowlist.root, tree().0);
+    }
+
+    #[test]
+    fn test_exchange_with_proof_records_receipt() {
+        let mollusk = setup();
+        let allowlist = allowlist_pda().0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_with_proof_ix(tree().1, CAP),
+            &accounts(&mollusk, true, 0),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&allowlist).closed().build(),
+            ],
+        );
+
+        let taker = result
+            .get_account(&get_associated_token_address(&TAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(taker), DEPOSIT_AMOUNT);
+
+        let receipt = result.get_account(&receipt_pda().0).unwrap();
+        let receipt = Receipt::try_deserialize(&mut receipt.data.as_slice()).unwrap();
+        assert_eq!(receipt.taker, TAKER);
+        assert_eq!(receipt.filled, DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_exchange_with_wrong_cap() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_with_proof_ix(tree().1, 0),
+            &accounts(&mollusk, true, 0),
+            &[Check::err(program_error(ErrorCode::NotAllowlisted))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_over_cap() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_with_proof_ix(tree().1, CAP),
+            &accounts(&mollusk, true, 20_000),
+            &[Check::err(program_error(ErrorCode::CapExceeded))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_without_proof_accounts() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, true, 0),
+            &[Check::err(program_error(ErrorCode::NotAllowlisted))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_allowlisted_escrow() {
+        let mollusk = setup();
+
+        //the proof accounts are passed, but to the plain `exchange`
+        let mut instruction = exchange_with_proof_ix(vec![], 0);
+        instruction.data = escrow::instruction::Exchange {}.data();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, true, 0),
+            &[Check::err(program_error(ErrorCode::Allowlisted))],
+        );
+    }
+}
@@ -0,0 +1,376 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Basket, Leg, MintPolicy},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::{Account, WritableAccount},
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    //"3 tokens for 2 tokens"
+    const OFFERED: [(Pubkey, u64); 3] = [
+        (Pubkey::new_from_array([0x11; 32]), 1_000),
+        (Pubkey::new_from_array([0x12; 32]), 2_000),
+        (Pubkey::new_from_array([0x13; 32]), 3_000),
+    ];
+    const WANTED: [Leg; 2] = [
+        Leg {
+            mint: Pubkey::new_from_array([0x21; 32]),
+            amount: 4_000,
+        },
+        Leg {
+            mint: Pubkey::new_from_array([0x22; 32]),
+            amount: 5_000,
+        },
+    ];
+
+    fn basket_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"basket", MAKER.as_ref(), &SEED.to_le_bytes()], &ID)
+    }
+
+    fn vault(mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(&basket_pda().0, mint)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn basket_state() -> Basket {
+        Basket {
+            seed: SEED,
+            maker: MAKER,
+            offered_mints: OFFERED.iter().map(|(mint, _)| *mint).collect(),
+            wanted: WANTED.to_vec(),
+            bump: basket_pda().1,
+        }
+    }
+
+    fn make_basket_ix() -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(MAKER, true),
+            AccountMeta::new(basket_pda().0, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            AccountMeta::new_readonly(mint_policy_pda().0, false),
+        ];
+        for (mint, _) in OFFERED {
+            accounts.push(AccountMeta::new_readonly(mint, false));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&MAKER, &mint),
+                false,
+            ));
+            accounts.push(AccountMeta::new(vault(&mint), false));
+        }
+        for leg in WANTED {
+            accounts.push(AccountMeta::new_readonly(leg.mint, false));
+        }
+
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::MakeBasket {
+                seed: SEED,
+                deposits: OFFERED.iter().map(|(_, amount)| *amount).collect(),
+                wanted: WANTED.to_vec(),
+            }
+            .data(),
+            accounts,
+        )
+    }
+
+    fn exchange_basket_ix(wanted: &[Leg]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(TAKER, true),
+            AccountMeta::new_readonly(MAKER, false),
+            AccountMeta::new(basket_pda().0, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
+        for (mint, _) in OFFERED {
+            accounts.push(AccountMeta::new_readonly(mint, false));
+            accounts.push(AccountMeta::new(vault(&mint), false));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&TAKER, &mint),
+                false,
+            ));
+        }
+        for leg in wanted {
+            accounts.push(AccountMeta::new_readonly(leg.mint, false));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&TAKER, &leg.mint),
+                false,
+            ));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&MAKER, &leg.mint),
+                false,
+            ));
+        }
+
+        Instruction::new_with_bytes(ID, &escrow::instruction::ExchangeBasket {}.data(), accounts)
+    }
+
+    fn refund_basket_ix() -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(MAKER, true),
+            AccountMeta::new(basket_pda().0, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ];
+        for (mint, _) in OFFERED {
+            accounts.push(AccountMeta::new_readonly(mint, false));
+            accounts.push(AccountMeta::new(vault(&mint), false));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&MAKER, &mint),
+                false,
+            ));
+        }
+
+        Instruction::new_with_bytes(ID, &escrow::instruction::RefundBasket {}.data(), accounts)
+    }
+
+    //Every mint, the maker and taker with their balances, and the basket if it is open
+    fn accounts(mollusk: &Mollusk, open: bool) -> Vec<(Pubkey, Account)> {
+        let mut accounts = vec![(MAKER, user()), (TAKER, user())];
+        for (mint, deposit) in OFFERED {
+            accounts.push((mint, mint_account(mollusk, deposit, 6)));
+            accounts.push((
+                get_associated_token_address(&MAKER, &mint),
+                token_account(mollusk, mint, MAKER, if open { 0 } else { deposit }),
+            ));
+            accounts.push((
+                vault(&mint),
+                if open {
+                    token_account(mollusk, mint, basket_pda().0, deposit)
+                } else {
+                    Account::default()
+                },
+            ));
+            accounts.push((
+                get_associated_token_address(&TAKER, &mint),
+                Account::default(),
+            ));
+        }
+        for leg in WANTED {
+            accounts.push((leg.mint, mint_account(mollusk, leg.amount, 6)));
+            accounts.push((
+                get_associated_token_address(&TAKER, &leg.mint),
+                token_account(mollusk, leg.mint, TAKER, leg.amount),
+            ));
+            accounts.push((
+                get_associated_token_address(&MAKER, &leg.mint),
+                Account::default(),
+            ));
+        }
+        accounts.push((
+            basket_pda().0,
+            if open {
+                state_account(mollusk, &basket_state(), Basket::LEN)
+            } else {
+                Account::default()
+            },
+        ));
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_basket_validity() {
+        let offered: Vec<Pubkey> = OFFERED.iter().map(|(mint, _)| *mint).collect();
+        assert!(Basket::is_valid(&offered, &WANTED));
+        assert!(!Basket::is_valid(&[], &WANTED));
+        assert!(!Basket::is_valid(&offered, &[]));
+        assert!(!Basket::is_valid(&[offered[0], offered[0]], &WANTED));
+        assert!(!Basket::is_valid(&offered, &[WANTED[0], WANTED[0]]));
+        assert!(!Basket::is_valid(&[offered[0]; 5], &WANTED));
+    }
+
+    #[test]
+    fn test_make_basket() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_basket_ix(),
+            &accounts(&mollusk, false),
+            &[Check::success()],
+        );
+
+        for (mint, deposit) in OFFERED {
+            assert_eq!(amount(result.get_account(&vault(&mint)).unwrap()), deposit);
+        }
+        let basket = result.get_account(&basket_pda().0).unwrap();
+        let basket = Basket::try_deserialize(&mut basket.data.as_slice()).unwrap();
+        assert_eq!(basket.wanted, WANTED.to_vec());
+    }
+
+    #[test]
+    fn test_make_basket_rejects_risky_wanted_mint() {
+        let mollusk = setup();
+
+        //the policy refuses freeze authorities, and the second wanted mint has one
+        let mut accounts = accounts(&mollusk, false);
+        let (_, mint) = accounts
+            .iter_mut()
+            .find(|(key, _)| *key == WANTED[1].mint)
+            .unwrap();
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                mint_authority: COption::None,
+                supply: WANTED[1].amount,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::Some(MAKER),
+            },
+            mint.data_as_mut_slice(),
+        )
+        .unwrap();
+        //ahead of the unconfigured default
+        accounts.insert(
+            0,
+            (
+                mint_policy_pda().0,
+                state_account(
+                    &mollusk,
+                    &MintPolicy {
+                        reject: MintPolicy::FREEZE_AUTHORITY,
+                        bump: mint_policy_pda().1,
+                    },
+                    8 + MintPolicy::INIT_SPACE,
+                ),
+            ),
+        );
+
+        mollusk.process_and_validate_instruction(
+            &make_basket_ix(),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::RiskyMint))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_basket_settles_every_leg() {
+        let mollusk = setup();
+
+        let basket = basket_pda().0;
+        let vaults: Vec<Pubkey> = OFFERED.iter().map(|(mint, _)| vault(mint)).collect();
+        let mut checks = vec![Check::success(), Check::account(&basket).closed().build()];
+        for vault in &vaults {
+            checks.push(Check::account(vault).closed().build());
+        }
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_basket_ix(&WANTED),
+            &accounts(&mollusk, true),
+            &checks,
+        );
+
+        for (mint, deposit) in OFFERED {
+            let taker = result
+                .get_account(&get_associated_token_address(&TAKER, &mint))
+                .unwrap();
+            assert_eq!(amount(taker), deposit);
+        }
+        for leg in WANTED {
+            let maker = result
+                .get_account(&get_associated_token_address(&MAKER, &leg.mint))
+                .unwrap();
+            assert_eq!(amount(maker), leg.amount);
+        }
+    }
+
+    #[test]
+    fn test_exchange_basket_with_pda_maker() {
+        let mollusk = setup();
+
+        //the maker is owned by a program, like a multisig vault
+        let mut accounts = accounts(&mollusk, true);
+        for (key, account) in accounts.iter_mut() {
+            if *key == MAKER {
+                account.owner = Pubkey::new_from_array([0x09; 32]);
+                account.data = vec![0; 64];
+            }
+        }
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_basket_ix(&WANTED),
+            &accounts,
+            &[
+                Check::success(),
+                Check::account(&basket_pda().0).closed().build(),
+            ],
+        );
+
+        for leg in WANTED {
+            let maker = result
+                .get_account(&get_associated_token_address(&MAKER, &leg.mint))
+                .unwrap();
+            assert_eq!(amount(maker), leg.amount);
+        }
+    }
+
+    #[test]
+    fn test_exchange_basket_legs_out_of_order() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_basket_ix(&[WANTED[1], WANTED[0]]),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::EscrowMismatch))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_basket_missing_leg() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_basket_ix(&WANTED[..1]),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(
+                ErrorCode::InvalidRemainingAccounts,
+            ))],
+        );
+    }
+
+    #[test]
+    fn test_refund_basket() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &refund_basket_ix(),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&basket_pda().0).closed().build(),
+            ],
+        );
+
+        for (mint, deposit) in OFFERED {
+            let maker = result
+                .get_account(&get_associated_token_address(&MAKER, &mint))
+                .unwrap();
+            assert_eq!(amount(maker), deposit);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountDeserialize;
+    use escrow::{error::ErrorCode, state::Escrow};
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account, native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const MAKER_1: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const MAKER_2: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const MINT_Z: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    //MAKER_1 offers 5_000 X for 10_000 Y, MAKER_2 offers 4_000 X for 10_000 Y
+    fn accounts(mollusk: &Mollusk, second_wants: Pubkey) -> Vec<(Pubkey, Account)> {
+        let mut accounts = vec![
+            (TAKER, user()),
+            (MINT_X, mint_account(mollusk, 100_000_000, 6)),
+            (MINT_Y, mint_account(mollusk, 100_000_000, 6)),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, 20_000),
+            ),
+        ];
+        for (maker, deposit, wants) in [(MAKER_1, 5_000, MINT_Y), (MAKER_2, 4_000, second_wants)] {
+            let escrow = escrow_pda(&maker, 1).0;
+            accounts.push((
+                escrow,
+                escrow_account(mollusk, &escrow_state(maker, 1, MINT_X, wants, 10_000)),
+            ));
+            accounts.push((
+                get_associated_token_address(&escrow, &MINT_X),
+                token_account(mollusk, MINT_X, escrow, deposit),
+            ));
+            accounts.push((
+                get_associated_token_address(&maker, &MINT_Y),
+                token_account(mollusk, MINT_Y, maker, 0),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    #[test]
+    fn test_fill_amount_rounds_in_makers_favour() {
+        let escrow = escrow_state(MAKER_1, 1, MINT_X, MINT_Y, 3);
+
+        assert_eq!(escrow.fill_amount(10, 3), Some(10));
+        assert_eq!(escrow.fill_amount(10, 5), Some(10));
+        assert_eq!(escrow.fill_amount(10, 1), Some(3));
+        assert_eq!(escrow.fill_amount(10, 2), Some(6));
+        assert_eq!(escrow.fill_amount(u64::MAX, 2), Some(u64::MAX / 3 * 2));
+    }
+
+    #[test]
+    fn test_batch_exchange_fills_in_order() {
+        let mollusk = setup();
+        let escrow_1 = escrow_pda(&MAKER_1, 1).0;
+        let escrow_2 = escrow_pda(&MAKER_2, 1).0;
+
+        let instruction = batch_exchange_ix(
+            TAKER,
+            MINT_X,
+            MINT_Y,
+            &[(MAKER_1, 1), (MAKER_2, 1)],
+            15_000,
+            7_000,
+        );
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, MINT_Y),
+            &[
+                Check::success(),
+                Check::account(&escrow_1).closed().build(),
+                Check::account(&get_associated_token_address(&escrow_1, &MINT_X))
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        //The first escrow is filled, the second one half filled
+        let taker_x = result
+            .get_account(&get_associated_token_address(&TAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(taker_x), 5_000 + 2_000);
+
+        let vault_2 = result
+            .get_account(&get_associated_token_address(&escrow_2, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(vault_2), 2_000);
+
+        let escrow_2 = result.get_account(&escrow_2).unwrap();
+        let escrow_2 = Escrow::try_deserialize(&mut escrow_2.data.as_slice()).unwrap();
+        assert_eq!(escrow_2.receive_amount, 5_000);
+
+        let maker_2_y = result
+            .get_account(&get_associated_token_address(&MAKER_2, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_2_y), 5_000);
+    }
+
+    #[test]
+    fn test_batch_exchange_slippage() {
+        let mollusk = setup();
+
+        let instruction = batch_exchange_ix(
+            TAKER,
+            MINT_X,
+            MINT_Y,
+            &[(MAKER_1, 1), (MAKER_2, 1)],
+            15_000,
+            7_001,
+        );
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, MINT_Y),
+            &[Check::err(program_error(ErrorCode::SlippageExceeded))],
+        );
+    }
+
+    #[test]
+    fn test_batch_exchange_rejects_other_pairs() {
+        let mollusk = setup();
+
+        let instruction = batch_exchange_ix(
+            TAKER,
+            MINT_X,
+            MINT_Y,
+            &[(MAKER_1, 1), (MAKER_2, 1)],
+            15_000,
+            0,
+        );
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, MINT_Z),
+            &[Check::err(program_error(ErrorCode::EscrowMismatch))],
+        );
+    }
+}
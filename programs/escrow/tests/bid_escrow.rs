@@ -0,0 +1,196 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::state::{Escrow, Side};
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account, instruction::Instruction, native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    //the buyer locks 20_000 of the quote for 100 of the base
+    const QUOTE_DEPOSIT: u64 = 20_000;
+    const BASE_WANTED: u64 = 100;
+    const BUYER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const SELLER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const BASE: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const QUOTE: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn bid() -> Escrow {
+        Escrow {
+            side: Side::Bid,
+            ..escrow_state(BUYER, SEED, QUOTE, BASE, BASE_WANTED)
+        }
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn make_bid_ix() -> Instruction {
+        let mut instruction = make_ix(BUYER, QUOTE, BASE, SEED, BASE_WANTED, QUOTE_DEPOSIT);
+        instruction.data = escrow::instruction::MakeBid {
+            seed: SEED,
+            receive: BASE_WANTED,
+            deposit: QUOTE_DEPOSIT,
+        }
+        .data();
+        instruction
+    }
+
+    //A buyer holding the quote and a seller holding the base, with the bid open if `open`
+    fn accounts(mollusk: &Mollusk, open: bool) -> Vec<(Pubkey, Account)> {
+        let escrow = escrow_pda(&BUYER, SEED).0;
+        vec![
+            (
+                BUYER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                SELLER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (BASE, mint_account(mollusk, BASE_WANTED, 0)),
+            (QUOTE, mint_account(mollusk, QUOTE_DEPOSIT, 6)),
+            (
+                get_associated_token_address(&BUYER, &QUOTE),
+                token_account(mollusk, QUOTE, BUYER, if open { 0 } else { QUOTE_DEPOSIT }),
+            ),
+            (
+                get_associated_token_address(&BUYER, &BASE),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&SELLER, &BASE),
+                token_account(mollusk, BASE, SELLER, BASE_WANTED),
+            ),
+            (
+                get_associated_token_address(&SELLER, &QUOTE),
+                Account::default(),
+            ),
+            (
+                escrow,
+                if open {
+                    escrow_account(mollusk, &bid())
+                } else {
+                    Account::default()
+                },
+            ),
+            (
+                get_associated_token_address(&escrow, &QUOTE),
+                if open {
+                    token_account(mollusk, QUOTE, escrow, QUOTE_DEPOSIT)
+                } else {
+                    Account::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect()
+    }
+
+    #[test]
+    fn test_bid_base_and_quote() {
+        let bid = bid();
+        assert_eq!(bid.base_mint(), BASE);
+        assert_eq!(bid.quote_mint(), QUOTE);
+
+        let ask = escrow_state(SELLER, SEED, BASE, QUOTE, QUOTE_DEPOSIT);
+        assert_eq!(ask.base_mint(), BASE);
+        assert_eq!(ask.quote_mint(), QUOTE);
+    }
+
+    #[test]
+    fn test_make_bid() {
+        let mollusk = setup();
+        let escrow = escrow_pda(&BUYER, SEED).0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_bid_ix(),
+            &accounts(&mollusk, false),
+            &[Check::success()],
+        );
+
+        let state = result.get_account(&escrow).unwrap();
+        let state = Escrow::try_deserialize(&mut state.data.as_slice()).unwrap();
+        assert_eq!(state.side, Side::Bid);
+        assert_eq!(state.receive_amount, BASE_WANTED);
+
+        let vault = result
+            .get_account(&get_associated_token_address(&escrow, &QUOTE))
+            .unwrap();
+        assert_eq!(amount(vault), QUOTE_DEPOSIT);
+    }
+
+    #[test]
+    fn test_seller_fills_bid() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(SELLER, BUYER, QUOTE, BASE, SEED),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&BUYER, SEED).0).closed().build(),
+            ],
+        );
+
+        let buyer_base = result
+            .get_account(&get_associated_token_address(&BUYER, &BASE))
+            .unwrap();
+        assert_eq!(amount(buyer_base), BASE_WANTED);
+
+        let seller_quote = result
+            .get_account(&get_associated_token_address(&SELLER, &QUOTE))
+            .unwrap();
+        assert_eq!(amount(seller_quote), QUOTE_DEPOSIT);
+    }
+
+    #[test]
+    fn test_seller_fills_bid_partially() {
+        let mollusk = setup();
+
+        //selling 25 of the 100 wanted releases a quarter of the deposit
+        let instruction = batch_exchange_ix(SELLER, QUOTE, BASE, &[(BUYER, SEED)], 25, 0);
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, true),
+            &[Check::success()],
+        );
+
+        let seller_quote = result
+            .get_account(&get_associated_token_address(&SELLER, &QUOTE))
+            .unwrap();
+        assert_eq!(amount(seller_quote), QUOTE_DEPOSIT / 4);
+
+        let state = result.get_account(&escrow_pda(&BUYER, SEED).0).unwrap();
+        let state = Escrow::try_deserialize(&mut state.data.as_slice()).unwrap();
+        assert_eq!(state.receive_amount, BASE_WANTED - 25);
+        assert_eq!(state.side, Side::Bid);
+    }
+
+    #[test]
+    fn test_refund_bid() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &refund_ix(BUYER, BUYER, QUOTE, SEED),
+            &accounts(&mollusk, true),
+            &[Check::success()],
+        );
+
+        let buyer_quote = result
+            .get_account(&get_associated_token_address(&BUYER, &QUOTE))
+            .unwrap();
+        assert_eq!(amount(buyer_quote), QUOTE_DEPOSIT);
+    }
+}
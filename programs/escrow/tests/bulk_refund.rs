@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use escrow::error::ErrorCode;
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account, native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const OTHER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    //MAKER has offered 5_000 X for Y (seed 1) and 7_000 Y for X (seed 2)
+    fn accounts(mollusk: &Mollusk, signer: Pubkey) -> Vec<(Pubkey, Account)> {
+        let mut accounts = vec![
+            (
+                signer,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(mollusk, 100_000_000, 6)),
+            (MINT_Y, mint_account(mollusk, 100_000_000, 6)),
+            (
+                get_associated_token_address(&signer, &MINT_X),
+                token_account(mollusk, MINT_X, signer, 0),
+            ),
+            (
+                get_associated_token_address(&signer, &MINT_Y),
+                token_account(mollusk, MINT_Y, signer, 0),
+            ),
+        ];
+        for (seed, mint_a, mint_b, deposit) in
+            [(1, MINT_X, MINT_Y, 5_000), (2, MINT_Y, MINT_X, 7_000)]
+        {
+            let escrow = escrow_pda(&MAKER, seed).0;
+            accounts.push((
+                escrow,
+                escrow_account(mollusk, &escrow_state(MAKER, seed, mint_a, mint_b, 10_000)),
+            ));
+            accounts.push((
+                get_associated_token_address(&escrow, &mint_a),
+                token_account(mollusk, mint_a, escrow, deposit),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_bulk_refund() {
+        let mollusk = setup();
+        let escrow_1 = escrow_pda(&MAKER, 1).0;
+        let escrow_2 = escrow_pda(&MAKER, 2).0;
+
+        let instruction = bulk_refund_ix(MAKER, MAKER, &[(1, MINT_X), (2, MINT_Y)]);
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, MAKER),
+            &[
+                Check::success(),
+                Check::account(&escrow_1).closed().build(),
+                Check::account(&escrow_2).closed().build(),
+                Check::account(&get_associated_token_address(&escrow_1, &MINT_X))
+                    .closed()
+                    .build(),
+                Check::account(&get_associated_token_address(&escrow_2, &MINT_Y))
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        for (mint, refunded) in [(MINT_X, 5_000), (MINT_Y, 7_000)] {
+            let account = result
+                .get_account(&get_associated_token_address(&MAKER, &mint))
+                .unwrap();
+            let account = spl_token::state::Account::unpack(&account.data).unwrap();
+            assert_eq!(account.amount, refunded);
+        }
+    }
+
+    #[test]
+    fn test_bulk_refund_only_by_maker() {
+        let mollusk = setup();
+
+        let instruction = bulk_refund_ix(OTHER, MAKER, &[(1, MINT_X), (2, MINT_Y)]);
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, OTHER),
+            &[Check::err(program_error(ErrorCode::EscrowMismatch))],
+        );
+    }
+}
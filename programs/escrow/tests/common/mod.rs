@@ -0,0 +1,410 @@
+//Shared Mollusk setup and account builders for the integration tests.
+//Every test binary only uses part of these helpers.
+#![allow(dead_code)]
+
+use anchor_lang::{AccountSerialize, AnchorSerialize, InstructionData, Space};
+use escrow::state::{
+    Escrow, EscrowKind, PriceCurve, PriceFeed, PriceUpdateV2, RoyaltyPolicy, Side,
+    TriggerDirection, VerificationLevel, PYTH_RECEIVER_PROGRAM,
+};
+use mollusk_svm::{program, Mollusk};
+use solana_sdk::{
+    account::{Account, WritableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::AccountState;
+
+pub const ID: Pubkey = escrow::ID;
+pub const ASSOCIATED_TOKEN_PROGRAM: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub fn setup() -> Mollusk {
+    let mut mollusk = Mollusk::new(&ID, "../../target/deploy/escrow");
+
+    mollusk.add_program(
+        &spl_token::ID,
+        "tests/elf/spl_token",
+        &mollusk_svm::program::loader_keys::LOADER_V4,
+    );
+
+    mollusk.add_program(
+        &ASSOCIATED_TOKEN_PROGRAM,
+        "tests/elf/associated_token",
+        &mollusk_svm::program::loader_keys::LOADER_V4,
+    );
+
+    mollusk
+}
+
+//Program accounts every escrow instruction needs in the transaction
+pub fn program_accounts() -> Vec<(Pubkey, Account)> {
+    vec![
+        mollusk_svm::program::keyed_account_for_system_program(),
+        (
+            spl_token::ID,
+            program::create_program_account_loader_v3(&spl_token::ID),
+        ),
+        (
+            ASSOCIATED_TOKEN_PROGRAM,
+            program::create_program_account_loader_v3(&ASSOCIATED_TOKEN_PROGRAM),
+        ),
+        //no mint policy configured, tests that need one put theirs first
+        (mint_policy_pda().0, Account::default()),
+    ]
+}
+
+pub fn is_program(key: &Pubkey) -> bool {
+    program_accounts().iter().any(|(program, _)| program == key)
+}
+
+pub fn mint_policy_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint_policy"], &ID)
+}
+
+pub fn escrow_pda(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &ID)
+}
+
+pub fn vault_address(maker: &Pubkey, mint_a: &Pubkey, seed: u64) -> Pubkey {
+    get_associated_token_address(&escrow_pda(maker, seed).0, mint_a)
+}
+
+pub fn mint_account(mollusk: &Mollusk, supply: u64, decimals: u8) -> Account {
+    let mut account = Account::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(spl_token::state::Mint::LEN),
+        spl_token::state::Mint::LEN,
+        &spl_token::ID,
+    );
+    spl_token::state::Mint::pack(
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        account.data_as_mut_slice(),
+    )
+    .unwrap();
+    account
+}
+
+pub fn token_account(mollusk: &Mollusk, mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut account = Account::new(
+        mollusk
+            .sysvars
+            .rent
+            .minimum_balance(spl_token::state::Account::LEN),
+        spl_token::state::Account::LEN,
+        &spl_token::ID,
+    );
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        account.data_as_mut_slice(),
+    )
+    .unwrap();
+    account
+}
+
+pub fn escrow_account(mollusk: &Mollusk, escrow: &Escrow) -> Account {
+    state_account(mollusk, escrow, Escrow::LEN)
+}
+
+//Any of the program's accounts, serialized with its discriminator
+pub fn state_account<T: AccountSerialize>(mollusk: &Mollusk, state: &T, space: usize) -> Account {
+    let mut account = Account::new(mollusk.sysvars.rent.minimum_balance(space), space, &ID);
+    let mut data = account.data_as_mut_slice();
+    state
+        .try_serialize(&mut data)
+        .expect("Failed to serialize state account data");
+    account
+}
+
+//A fully verified Pyth receiver price update carrying `feed`
+pub fn price_feed_account(mollusk: &Mollusk, feed: &PriceFeed) -> Account {
+    let update = PriceUpdateV2 {
+        write_authority: Pubkey::new_unique(),
+        verification_level: VerificationLevel::Full,
+        feed_id: [0xfe; 32],
+        price: feed.price,
+        conf: feed.conf,
+        expo: feed.expo,
+        publish_time: feed.publish_time,
+        prev_publish_time: feed.publish_time - 1,
+        ema_price: feed.price,
+        ema_conf: feed.conf,
+        posted_slot: 0,
+    };
+    let mut data = PriceUpdateV2::DISCRIMINATOR.to_vec();
+    update
+        .serialize(&mut data)
+        .expect("Failed to serialize price update data");
+    let mut account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(data.len()),
+        data.len(),
+        &PYTH_RECEIVER_PROGRAM,
+    );
+    account.data = data;
+    account
+}
+
+//A price feed laid out the way the mock oracle writes it, only accepted by
+//builds with the `mock-oracle` feature
+pub fn mock_price_feed_account(mollusk: &Mollusk, feed: &PriceFeed, authority: Pubkey) -> Account {
+    let state = mock_oracle::PriceFeed {
+        price: feed.price,
+        conf: feed.conf,
+        expo: feed.expo,
+        publish_time: feed.publish_time,
+        authority,
+    };
+    let space = 8 + mock_oracle::PriceFeed::INIT_SPACE;
+    let mut account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(space),
+        space,
+        &mock_oracle::ID,
+    );
+    state
+        .try_serialize(&mut account.data_as_mut_slice())
+        .expect("Failed to serialize price feed data");
+    account
+}
+
+pub fn make_ix(
+    maker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    seed: u64,
+    receive: u64,
+    deposit: u64,
+) -> Instruction {
+    let escrow = escrow_pda(&maker, seed).0;
+
+    Instruction::new_with_bytes(
+        ID,
+        &escrow::instruction::Make {
+            seed,
+            receive,
+            deposit,
+        }
+        .data(),
+        vec![
+            AccountMeta::new(maker, true),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new(get_associated_token_address(&maker, &mint_a), false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(get_associated_token_address(&escrow, &mint_a), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            AccountMeta::new_readonly(mint_policy_pda().0, false),
+        ],
+    )
+}
+
+pub fn exchange_ix(
+    taker: Pubkey,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    seed: u64,
+) -> Instruction {
+    let escrow = escrow_pda(&maker, seed).0;
+
+    Instruction::new_with_bytes(
+        ID,
+        &escrow::instruction::Exchange {}.data(),
+        vec![
+            AccountMeta::new(taker, true),
+            AccountMeta::new(maker, false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new(get_associated_token_address(&taker, &mint_a), false),
+            AccountMeta::new(get_associated_token_address(&taker, &mint_b), false),
+            AccountMeta::new(get_associated_token_address(&maker, &mint_b), false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(get_associated_token_address(&escrow, &mint_a), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+pub fn refund_ix(maker: Pubkey, escrow_maker: Pubkey, mint_a: Pubkey, seed: u64) -> Instruction {
+    let escrow = escrow_pda(&escrow_maker, seed).0;
+
+    Instruction::new_with_bytes(
+        ID,
+        &escrow::instruction::Refund {}.data(),
+        vec![
+            AccountMeta::new(maker, true),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new(get_associated_token_address(&maker, &mint_a), false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(get_associated_token_address(&escrow, &mint_a), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+//Collects the accounts an instruction touches from `world`, defaulting to
+//empty system accounts for addresses that do not exist yet.
+pub fn accounts_for(
+    instruction: &Instruction,
+    world: &std::collections::BTreeMap<Pubkey, Account>,
+) -> Vec<(Pubkey, Account)> {
+    let mut accounts: Vec<(Pubkey, Account)> = Vec::new();
+    for meta in &instruction.accounts {
+        if is_program(&meta.pubkey) || accounts.iter().any(|(key, _)| *key == meta.pubkey) {
+            continue;
+        }
+        let account = world.get(&meta.pubkey).cloned().unwrap_or_default();
+        accounts.push((meta.pubkey, account));
+    }
+    accounts.extend(program_accounts());
+    accounts
+}
+
+pub fn migrate_ix(payer: Pubkey, escrow: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &escrow::instruction::Migrate {}.data(),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+pub fn program_error(code: impl Into<u32>) -> solana_sdk::program_error::ProgramError {
+    solana_sdk::program_error::ProgramError::Custom(code.into())
+}
+
+pub fn escrow_state(
+    maker: Pubkey,
+    seed: u64,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    receive: u64,
+) -> Escrow {
+    Escrow {
+        seed,
+        maker,
+        token_mint_a: mint_a,
+        token_mint_b: mint_b,
+        receive_amount: receive,
+        bump: escrow_pda(&maker, seed).1,
+        version: Escrow::VERSION,
+        price_curve: PriceCurve::Fixed,
+        floor_amount: 0,
+        start_time: 0,
+        end_time: 0,
+        kind: EscrowKind::Offer,
+        oracle: Pubkey::default(),
+        spread_bps: 0,
+        max_age: 0,
+        max_confidence_bps: 0,
+        trigger_price: 0,
+        trigger_expo: 0,
+        trigger_direction: TriggerDirection::Below,
+        trigger_tip: 0,
+        accepts_other_mints: false,
+        side: Side::Ask,
+        market: Pubkey::default(),
+        allowlisted: false,
+        gated: false,
+        mint_risks: 0,
+        royalty_policy: RoyaltyPolicy::Enforced,
+        split: false,
+        destination: Pubkey::default(),
+        reserved: [0; 94],
+    }
+}
+
+//`escrows` are (maker, seed) pairs, filled in the order given
+pub fn batch_exchange_ix(
+    taker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    escrows: &[(Pubkey, u64)],
+    total: u64,
+    min_receive: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(taker, true),
+        AccountMeta::new_readonly(mint_a, false),
+        AccountMeta::new_readonly(mint_b, false),
+        AccountMeta::new(get_associated_token_address(&taker, &mint_a), false),
+        AccountMeta::new(get_associated_token_address(&taker, &mint_b), false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+        AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+    ];
+    for (maker, seed) in escrows {
+        let escrow = escrow_pda(maker, *seed).0;
+        accounts.push(AccountMeta::new(escrow, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(&escrow, &mint_a),
+            false,
+        ));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(maker, &mint_b),
+            false,
+        ));
+    }
+
+    Instruction::new_with_bytes(
+        ID,
+        &escrow::instruction::BatchExchange { total, min_receive }.data(),
+        accounts,
+    )
+}
+
+//`escrows` are (seed, mint_a) pairs of the maker's escrows
+pub fn bulk_refund_ix(
+    maker: Pubkey,
+    escrow_maker: Pubkey,
+    escrows: &[(u64, Pubkey)],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(maker, true),
+        AccountMeta::new_readonly(spl_token::ID, false),
+    ];
+    for (seed, mint_a) in escrows {
+        let escrow = escrow_pda(&escrow_maker, *seed).0;
+        accounts.push(AccountMeta::new(escrow, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(&escrow, mint_a),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(*mint_a, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(&maker, mint_a),
+            false,
+        ));
+    }
+
+    Instruction::new_with_bytes(ID, &escrow::instruction::BulkRefund {}.data(), accounts)
+}
@@ -0,0 +1,320 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, EscrowKind, PriceFeed, TriggerDirection, TriggerParams},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 5_000;
+    const TIP: u64 = 50;
+    const NOW: i64 = 1_000;
+    //stop at 95.00
+    const TRIGGER_PRICE: i64 = 95;
+    const TRIGGER_EXPO: i32 = 0;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const CRANKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const FEED: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn trigger_params(direction: TriggerDirection, tip: u64) -> TriggerParams {
+        TriggerParams {
+            oracle: FEED,
+            price: TRIGGER_PRICE,
+            expo: TRIGGER_EXPO,
+            direction,
+            max_age: 60,
+            max_confidence_bps: 100,
+            tip,
+        }
+    }
+
+    fn order(direction: TriggerDirection) -> Escrow {
+        Escrow {
+            kind: EscrowKind::Conditional,
+            oracle: FEED,
+            max_age: 60,
+            max_confidence_bps: 100,
+            trigger_price: TRIGGER_PRICE,
+            trigger_expo: TRIGGER_EXPO,
+            trigger_direction: direction,
+            trigger_tip: TIP,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        }
+    }
+
+    //`price` is in cents
+    fn feed(price: i64) -> PriceFeed {
+        PriceFeed {
+            price,
+            conf: 1,
+            expo: -2,
+            publish_time: NOW,
+        }
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn make_conditional_ix(tip: u64) -> Instruction {
+        let mut instruction = make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT);
+        instruction.data = escrow::instruction::MakeConditional {
+            seed: SEED,
+            receive: RECEIVE,
+            trigger: trigger_params(TriggerDirection::Below, tip),
+            deposit: DEPOSIT_AMOUNT,
+        }
+        .data();
+        instruction
+    }
+
+    fn trigger_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::Trigger {}.data(),
+            vec![
+                AccountMeta::new(CRANKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(get_associated_token_address(&CRANKER, &MINT_X), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(get_associated_token_address(&escrow(), &MINT_X), false),
+                AccountMeta::new_readonly(FEED, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn accounts(mollusk: &Mollusk, order: &Escrow, feed: &PriceFeed) -> Vec<(Pubkey, Account)> {
+        vec![
+            (
+                MAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                CRANKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (
+                get_associated_token_address(&MAKER, &MINT_X),
+                token_account(mollusk, MINT_X, MAKER, 0),
+            ),
+            (
+                get_associated_token_address(&CRANKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&CRANKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, CRANKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (escrow(), escrow_account(mollusk, order)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (FEED, price_feed_account(mollusk, feed)),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect()
+    }
+
+    #[test]
+    fn test_trigger_directions() {
+        let stop = order(TriggerDirection::Below);
+        assert!(!stop.is_triggered(&feed(9_501), NOW).unwrap());
+        assert!(stop.is_triggered(&feed(9_500), NOW).unwrap());
+
+        let take_profit = order(TriggerDirection::Above);
+        assert!(!take_profit.is_triggered(&feed(9_499), NOW).unwrap());
+        assert!(take_profit.is_triggered(&feed(9_500), NOW).unwrap());
+    }
+
+    #[test]
+    fn test_trigger_checks_feed() {
+        let stale = PriceFeed {
+            publish_time: NOW - 61,
+            ..feed(9_000)
+        };
+        let result = order(TriggerDirection::Below).is_triggered(&stale, NOW);
+        assert_eq!(result.unwrap_err(), ErrorCode::StalePrice.into());
+    }
+
+    #[test]
+    fn test_trigger_rejects_extreme_exponents() {
+        let tiny = PriceFeed {
+            expo: i32::MIN,
+            ..feed(9_000)
+        };
+        let result = order(TriggerDirection::Below).is_triggered(&tiny, NOW);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidTrigger.into());
+
+        let huge = Escrow {
+            trigger_expo: i32::MAX,
+            ..order(TriggerDirection::Below)
+        };
+        let result = huge.is_triggered(&feed(9_000), NOW);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidTrigger.into());
+    }
+
+    #[test]
+    fn test_make_conditional_exponent_out_of_range() {
+        let mollusk = setup();
+        let mut accounts = accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_000));
+        accounts.retain(|(key, _)| {
+            *key != escrow() && *key != get_associated_token_address(&escrow(), &MINT_X)
+        });
+        accounts.push((escrow(), Account::default()));
+        accounts.push((
+            get_associated_token_address(&escrow(), &MINT_X),
+            Account::default(),
+        ));
+
+        let mut instruction = make_conditional_ix(TIP);
+        instruction.data = escrow::instruction::MakeConditional {
+            seed: SEED,
+            receive: RECEIVE,
+            trigger: TriggerParams {
+                expo: -19,
+                ..trigger_params(TriggerDirection::Below, TIP)
+            },
+            deposit: DEPOSIT_AMOUNT,
+        }
+        .data();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts,
+            &[Check::err(program_error(ErrorCode::InvalidTrigger))],
+        );
+    }
+
+    #[test]
+    fn test_make_conditional_tip_above_deposit() {
+        let mollusk = setup();
+        let mut accounts = accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_000));
+        accounts.retain(|(key, _)| {
+            *key != escrow() && *key != get_associated_token_address(&escrow(), &MINT_X)
+        });
+        accounts.push((escrow(), Account::default()));
+        accounts.push((
+            get_associated_token_address(&escrow(), &MINT_X),
+            Account::default(),
+        ));
+
+        mollusk.process_and_validate_instruction(
+            &make_conditional_ix(DEPOSIT_AMOUNT),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::InvalidTrigger))],
+        );
+    }
+
+    #[test]
+    fn test_trigger_pays_tip() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let result = mollusk.process_and_validate_instruction(
+            &trigger_ix(),
+            &accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_000)),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.kind, EscrowKind::Offer);
+
+        let cranker_x = result
+            .get_account(&get_associated_token_address(&CRANKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(cranker_x), TIP);
+    }
+
+    #[test]
+    fn test_trigger_before_crossing() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        mollusk.process_and_validate_instruction(
+            &trigger_ix(),
+            &accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_600)),
+            &[Check::err(program_error(ErrorCode::TriggerNotReached))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_before_trigger() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(CRANKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_000)),
+            &[Check::err(program_error(ErrorCode::NotTriggered))],
+        );
+    }
+
+    #[test]
+    fn test_refund_before_trigger() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &refund_ix(MAKER, MAKER, MINT_X, SEED),
+            &accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_600)),
+            &[Check::success(), Check::account(&escrow()).closed().build()],
+        );
+    }
+
+    #[test]
+    fn test_trigger_then_exchange() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let result = mollusk.process_instruction_chain(
+            &[
+                trigger_ix(),
+                exchange_ix(CRANKER, MAKER, MINT_X, MINT_Y, SEED),
+            ],
+            &accounts(&mollusk, &order(TriggerDirection::Below), &feed(9_000)),
+        );
+        assert!(!result.program_result.is_err());
+
+        //the cranker gets the tip and then what is left of the vault
+        let cranker_x = result
+            .resulting_accounts
+            .iter()
+            .find(|(key, _)| *key == get_associated_token_address(&CRANKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(&cranker_x.1), DEPOSIT_AMOUNT);
+    }
+}
@@ -0,0 +1,515 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Contribution, CrowdFill, Escrow, EscrowKind},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    //1_000 X offered for 3_000 Y raised by the crowd
+    const OFFERED: u64 = 1_000;
+    const TARGET: u64 = 3_000;
+    const DEADLINE: i64 = 10_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const CONTRIBUTOR_1: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const CONTRIBUTOR_2: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn crowd_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"crowd", escrow().as_ref()], &ID)
+    }
+
+    fn contribution_pda(contributor: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"contribution",
+                crowd_pda().0.as_ref(),
+                contributor.as_ref(),
+            ],
+            &ID,
+        )
+    }
+
+    fn pool() -> Pubkey {
+        get_associated_token_address(&crowd_pda().0, &MINT_Y)
+    }
+
+    fn vault() -> Pubkey {
+        get_associated_token_address(&escrow(), &MINT_X)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn crowd_state(raised: u64, contributors: u32, settled: bool) -> CrowdFill {
+        CrowdFill {
+            escrow: escrow(),
+            maker: MAKER,
+            token_mint_b: MINT_Y,
+            target: TARGET,
+            raised,
+            offered: if settled { OFFERED } else { 0 },
+            deadline: DEADLINE,
+            contributors,
+            settled,
+            bump: crowd_pda().1,
+        }
+    }
+
+    //The crowd fill with its vault holding `vault_amount` and, unless settled, the
+    //pool holding what was raised. `contributions` are (contributor, amount) pairs.
+    fn accounts(
+        mollusk: &Mollusk,
+        crowd: &CrowdFill,
+        vault_amount: u64,
+        contributions: &[(Pubkey, u64)],
+    ) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            kind: EscrowKind::CrowdFill,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, TARGET)
+        };
+
+        let mut accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(mollusk, OFFERED, 6)),
+            (MINT_Y, mint_account(mollusk, 100_000, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                vault(),
+                token_account(mollusk, MINT_X, escrow(), vault_amount),
+            ),
+            (
+                crowd_pda().0,
+                state_account(mollusk, crowd, 8 + CrowdFill::INIT_SPACE),
+            ),
+            (
+                pool(),
+                if crowd.settled {
+                    Account::default()
+                } else {
+                    token_account(mollusk, MINT_Y, crowd_pda().0, crowd.raised)
+                },
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+        ];
+        for contributor in [CONTRIBUTOR_1, CONTRIBUTOR_2] {
+            let (contribution, bump) = contribution_pda(&contributor);
+            let contributed = contributions
+                .iter()
+                .find(|(key, _)| *key == contributor)
+                .map(|(_, amount)| *amount);
+            accounts.push((contributor, user()));
+            accounts.push((
+                contribution,
+                match contributed {
+                    Some(amount) => state_account(
+                        mollusk,
+                        &Contribution {
+                            crowd_fill: crowd_pda().0,
+                            contributor,
+                            amount,
+                            bump,
+                        },
+                        8 + Contribution::INIT_SPACE,
+                    ),
+                    None => Account::default(),
+                },
+            ));
+            accounts.push((
+                get_associated_token_address(&contributor, &MINT_Y),
+                token_account(mollusk, MINT_Y, contributor, 10_000),
+            ));
+            accounts.push((
+                get_associated_token_address(&contributor, &MINT_X),
+                Account::default(),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn contribute_ix(contributor: Pubkey, amount: u64) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::Contribute { amount }.data(),
+            vec![
+                AccountMeta::new(contributor, true),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&contributor, &MINT_Y), false),
+                AccountMeta::new(crowd_pda().0, false),
+                AccountMeta::new(contribution_pda(&contributor).0, false),
+                AccountMeta::new(pool(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn settle_ix(settler: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SettleCrowdFill {}.data(),
+            vec![
+                AccountMeta::new(settler, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+                AccountMeta::new_readonly(escrow(), false),
+                AccountMeta::new(crowd_pda().0, false),
+                AccountMeta::new_readonly(vault(), false),
+                AccountMeta::new(pool(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn claim_ix(contributor: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::ClaimCrowdFill {}.data(),
+            vec![
+                AccountMeta::new(contributor, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(get_associated_token_address(&contributor, &MINT_X), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(crowd_pda().0, false),
+                AccountMeta::new(contribution_pda(&contributor).0, false),
+                AccountMeta::new(vault(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn cancel_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::CancelCrowdFill {}.data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_X), false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(crowd_pda().0, false),
+                AccountMeta::new(vault(), false),
+                AccountMeta::new(pool(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn reclaim_ix(contributor: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::ReclaimContribution {}.data(),
+            vec![
+                AccountMeta::new(contributor, true),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&contributor, &MINT_Y), false),
+                AccountMeta::new(crowd_pda().0, false),
+                AccountMeta::new(contribution_pda(&contributor).0, false),
+                AccountMeta::new(pool(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn get_crowd(account: &Account) -> CrowdFill {
+        CrowdFill::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_share() {
+        let crowd = crowd_state(TARGET, 2, true);
+        assert_eq!(crowd.share(1_000), 333);
+        assert_eq!(crowd.share(2_000), 666);
+        assert_eq!(crowd.share(TARGET), OFFERED);
+    }
+
+    #[test]
+    fn test_contribute_caps_at_target() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let result = mollusk.process_and_validate_instruction(
+            &contribute_ix(CONTRIBUTOR_2, 1_000),
+            &accounts(
+                &mollusk,
+                &crowd_state(2_500, 1, false),
+                OFFERED,
+                &[(CONTRIBUTOR_1, 2_500)],
+            ),
+            &[Check::success()],
+        );
+
+        let crowd = get_crowd(result.get_account(&crowd_pda().0).unwrap());
+        assert!(crowd.is_filled());
+        assert_eq!(crowd.contributors, 2);
+        assert_eq!(amount(result.get_account(&pool()).unwrap()), TARGET);
+
+        let contribution = result
+            .get_account(&contribution_pda(&CONTRIBUTOR_2).0)
+            .unwrap();
+        let contribution =
+            Contribution::try_deserialize(&mut contribution.data.as_slice()).unwrap();
+        assert_eq!(contribution.amount, 500);
+    }
+
+    #[test]
+    fn test_contribute_after_deadline() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = DEADLINE;
+
+        mollusk.process_and_validate_instruction(
+            &contribute_ix(CONTRIBUTOR_1, 1_000),
+            &accounts(&mollusk, &crowd_state(0, 0, false), OFFERED, &[]),
+            &[Check::err(program_error(ErrorCode::CrowdFillClosed))],
+        );
+    }
+
+    #[test]
+    fn test_settle_pays_maker() {
+        let mollusk = setup();
+        let contributions = [(CONTRIBUTOR_1, 1_000), (CONTRIBUTOR_2, 2_000)];
+
+        let result = mollusk.process_and_validate_instruction(
+            &settle_ix(CONTRIBUTOR_1),
+            &accounts(
+                &mollusk,
+                &crowd_state(TARGET, 2, false),
+                OFFERED,
+                &contributions,
+            ),
+            &[Check::success(), Check::account(&pool()).closed().build()],
+        );
+
+        let maker = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker), TARGET);
+
+        let crowd = get_crowd(result.get_account(&crowd_pda().0).unwrap());
+        assert!(crowd.settled);
+        assert_eq!(crowd.offered, OFFERED);
+    }
+
+    #[test]
+    fn test_settle_pays_pda_maker() {
+        let mollusk = setup();
+        let contributions = [(CONTRIBUTOR_1, 1_000), (CONTRIBUTOR_2, 2_000)];
+
+        //the maker is owned by a program, like a multisig vault
+        let mut accounts = accounts(
+            &mollusk,
+            &crowd_state(TARGET, 2, false),
+            OFFERED,
+            &contributions,
+        );
+        for (key, account) in accounts.iter_mut() {
+            if *key == MAKER {
+                account.owner = Pubkey::new_from_array([0x06; 32]);
+                account.data = vec![0; 64];
+            }
+        }
+
+        let result = mollusk.process_and_validate_instruction(
+            &settle_ix(CONTRIBUTOR_1),
+            &accounts,
+            &[Check::success(), Check::account(&pool()).closed().build()],
+        );
+
+        let maker = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker), TARGET);
+    }
+
+    #[test]
+    fn test_cancel_sweeps_pool() {
+        let mollusk = setup();
+
+        //tokens sent to the pool without `contribute`
+        let mut accounts = accounts(&mollusk, &crowd_state(0, 0, false), OFFERED, &[]);
+        accounts
+            .iter_mut()
+            .find(|(key, _)| *key == pool())
+            .unwrap()
+            .1 = token_account(&mollusk, MINT_Y, crowd_pda().0, 50);
+        accounts.push((
+            get_associated_token_address(&MAKER, &MINT_X),
+            token_account(&mollusk, MINT_X, MAKER, 0),
+        ));
+
+        let result = mollusk.process_and_validate_instruction(
+            &cancel_ix(),
+            &accounts,
+            &[
+                Check::success(),
+                Check::account(&pool()).closed().build(),
+                Check::account(&crowd_pda().0).closed().build(),
+                Check::account(&escrow()).closed().build(),
+            ],
+        );
+
+        let maker_y = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_y), 50);
+        let maker_x = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(maker_x), OFFERED);
+    }
+
+    #[test]
+    fn test_settle_before_filled() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &settle_ix(CONTRIBUTOR_1),
+            &accounts(
+                &mollusk,
+                &crowd_state(1_000, 1, false),
+                OFFERED,
+                &[(CONTRIBUTOR_1, 1_000)],
+            ),
+            &[Check::err(program_error(ErrorCode::CrowdFillNotFilled))],
+        );
+    }
+
+    #[test]
+    fn test_claim_pro_rata() {
+        let mollusk = setup();
+        let contributions = [(CONTRIBUTOR_1, 1_000), (CONTRIBUTOR_2, 2_000)];
+        let contribution = contribution_pda(&CONTRIBUTOR_1).0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &claim_ix(CONTRIBUTOR_1),
+            &accounts(
+                &mollusk,
+                &crowd_state(TARGET, 2, true),
+                OFFERED,
+                &contributions,
+            ),
+            &[
+                Check::success(),
+                Check::account(&contribution).closed().build(),
+            ],
+        );
+
+        let claimed = result
+            .get_account(&get_associated_token_address(&CONTRIBUTOR_1, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(claimed), 333);
+        assert_eq!(amount(result.get_account(&vault()).unwrap()), 667);
+    }
+
+    #[test]
+    fn test_last_claim_closes_crowd_fill() {
+        let mollusk = setup();
+        let crowd = crowd_pda().0;
+        let escrow = escrow();
+
+        let result = mollusk.process_and_validate_instruction(
+            &claim_ix(CONTRIBUTOR_2),
+            &accounts(
+                &mollusk,
+                &crowd_state(TARGET, 1, true),
+                667,
+                &[(CONTRIBUTOR_2, 2_000)],
+            ),
+            &[
+                Check::success(),
+                Check::account(&crowd).closed().build(),
+                Check::account(&escrow).closed().build(),
+            ],
+        );
+
+        let claimed = result
+            .get_account(&get_associated_token_address(&CONTRIBUTOR_2, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(claimed), 667);
+    }
+
+    #[test]
+    fn test_reclaim_after_deadline() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = DEADLINE;
+
+        let result = mollusk.process_and_validate_instruction(
+            &reclaim_ix(CONTRIBUTOR_1),
+            &accounts(
+                &mollusk,
+                &crowd_state(1_000, 1, false),
+                OFFERED,
+                &[(CONTRIBUTOR_1, 1_000)],
+            ),
+            &[Check::success()],
+        );
+
+        let refunded = result
+            .get_account(&get_associated_token_address(&CONTRIBUTOR_1, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(refunded), 11_000);
+        let crowd = get_crowd(result.get_account(&crowd_pda().0).unwrap());
+        assert_eq!(crowd.contributors, 0);
+    }
+
+    #[test]
+    fn test_reclaim_before_deadline() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = DEADLINE - 1;
+
+        mollusk.process_and_validate_instruction(
+            &reclaim_ix(CONTRIBUTOR_1),
+            &accounts(
+                &mollusk,
+                &crowd_state(1_000, 1, false),
+                OFFERED,
+                &[(CONTRIBUTOR_1, 1_000)],
+            ),
+            &[Check::err(program_error(ErrorCode::CrowdFillNotExpired))],
+        );
+    }
+}
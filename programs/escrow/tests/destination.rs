@@ -0,0 +1,255 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountDeserialize;
+    use escrow::{error::ErrorCode, state::Escrow};
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    //token accounts of the custody provider, which aren't ATAs
+    const CUSTODIAN: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const VAULT_Y: Pubkey = Pubkey::new_from_array([0x06; 32]);
+    const TAKER_X: Pubkey = Pubkey::new_from_array([0x07; 32]);
+    const CUSTODY_X: Pubkey = Pubkey::new_from_array([0x08; 32]);
+
+    //positions of the token accounts in `exchange_ix`
+    const TAKER_TOKEN_ACCOUNT_A: usize = 4;
+    const MAKER_TOKEN_ACCOUNT_B: usize = 6;
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    //`make` paying the maker into `destination`, the market and book left out
+    fn make_with_destination_ix(destination: Pubkey) -> Instruction {
+        let mut instruction = make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT);
+        for _ in 0..2 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(destination, false));
+        instruction
+    }
+
+    //`exchange` paying the maker into `maker_b` and the taker into `taker_a`
+    fn custom_exchange_ix(taker_a: Pubkey, maker_b: Pubkey) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+        instruction.accounts[TAKER_TOKEN_ACCOUNT_A].pubkey = taker_a;
+        instruction.accounts[MAKER_TOKEN_ACCOUNT_B].pubkey = maker_b;
+        instruction
+    }
+
+    //An offer of X for RECEIVE of Y paying into `destination`, with neither party's ATA created
+    fn accounts(mollusk: &Mollusk, destination: Pubkey) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            destination,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (VAULT_Y, token_account(mollusk, MINT_Y, CUSTODIAN, 0)),
+            (TAKER_X, token_account(mollusk, MINT_X, TAKER, 0)),
+            (CUSTODY_X, token_account(mollusk, MINT_X, CUSTODIAN, 0)),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    //The maker holding DEPOSIT_AMOUNT of X before making the offer
+    fn make_accounts(mollusk: &Mollusk) -> Vec<(Pubkey, Account)> {
+        let mut accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (
+                get_associated_token_address(&MAKER, &MINT_X),
+                token_account(mollusk, MINT_X, MAKER, DEPOSIT_AMOUNT),
+            ),
+            (escrow(), Account::default()),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                Account::default(),
+            ),
+            (VAULT_Y, token_account(mollusk, MINT_Y, CUSTODIAN, 0)),
+            (CUSTODY_X, token_account(mollusk, MINT_X, CUSTODIAN, 0)),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_make_with_destination() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_with_destination_ix(VAULT_Y),
+            &make_accounts(&mollusk),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.destination, VAULT_Y);
+        assert_eq!(escrow.proceeds_account(&MINT_Y, &spl_token::ID), VAULT_Y);
+    }
+
+    #[test]
+    fn test_make_with_destination_of_other_mint() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &make_with_destination_ix(CUSTODY_X),
+            &make_accounts(&mollusk),
+            &[Check::err(program_error(ErrorCode::InvalidDestination))],
+        );
+    }
+
+    #[test]
+    fn test_make_with_destination_of_other_token_program() {
+        let mollusk = setup();
+
+        let mut accounts = make_accounts(&mollusk);
+        let (_, vault_y) = accounts
+            .iter_mut()
+            .find(|(key, _)| *key == VAULT_Y)
+            .unwrap();
+        vault_y.owner = anchor_spl::token_2022::ID;
+
+        mollusk.process_and_validate_instruction(
+            &make_with_destination_ix(VAULT_Y),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::InvalidDestination))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_into_custom_accounts() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &custom_exchange_ix(TAKER_X, VAULT_Y),
+            &accounts(&mollusk, VAULT_Y),
+            &[Check::success(), Check::account(&escrow()).closed().build()],
+        );
+
+        assert_eq!(amount(result.get_account(&VAULT_Y).unwrap()), RECEIVE);
+        assert_eq!(
+            amount(result.get_account(&TAKER_X).unwrap()),
+            DEPOSIT_AMOUNT
+        );
+        //neither ATA had to be created
+        let maker_y = get_associated_token_address(&MAKER, &MINT_Y);
+        assert!(result.get_account(&maker_y).unwrap().data.is_empty());
+        let taker_x = get_associated_token_address(&TAKER, &MINT_X);
+        assert!(result.get_account(&taker_x).unwrap().data.is_empty());
+    }
+
+    #[test]
+    fn test_exchange_creates_missing_atas() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, Pubkey::default()),
+            &[Check::success()],
+        );
+
+        let maker_y = get_associated_token_address(&MAKER, &MINT_Y);
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), RECEIVE);
+        let taker_x = get_associated_token_address(&TAKER, &MINT_X);
+        assert_eq!(
+            amount(result.get_account(&taker_x).unwrap()),
+            DEPOSIT_AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_exchange_skipping_destination() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, VAULT_Y),
+            &[Check::err(program_error(ErrorCode::InvalidDestination))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_into_account_taker_does_not_hold() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &custom_exchange_ix(CUSTODY_X, get_associated_token_address(&MAKER, &MINT_Y)),
+            &accounts(&mollusk, Pubkey::default()),
+            &[Check::err(program_error(ErrorCode::InvalidDestination))],
+        );
+    }
+
+    #[test]
+    fn test_batch_exchange_pays_destination() {
+        let mollusk = setup();
+
+        let mut instruction =
+            batch_exchange_ix(TAKER, MINT_X, MINT_Y, &[(MAKER, SEED)], RECEIVE, 1);
+        let last = instruction.accounts.len() - 1;
+        instruction.accounts[last].pubkey = VAULT_Y;
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, VAULT_Y),
+            &[Check::success()],
+        );
+
+        assert_eq!(amount(result.get_account(&VAULT_Y).unwrap()), RECEIVE);
+    }
+}
@@ -0,0 +1,203 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, PriceCurve},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account, instruction::Instruction, native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const START_AMOUNT: u64 = 10_000;
+    const FLOOR_AMOUNT: u64 = 2_000;
+    const START_TIME: i64 = 1_000;
+    const END_TIME: i64 = 1_800;
+    const DEPOSIT_AMOUNT: u64 = 5_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn auction(curve: PriceCurve) -> Escrow {
+        Escrow {
+            price_curve: curve,
+            floor_amount: FLOOR_AMOUNT,
+            start_time: START_TIME,
+            end_time: END_TIME,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, START_AMOUNT)
+        }
+    }
+
+    fn make_dutch_ix(curve: PriceCurve, floor: u64) -> Instruction {
+        let mut instruction = make_ix(MAKER, MINT_X, MINT_Y, SEED, START_AMOUNT, DEPOSIT_AMOUNT);
+        instruction.data = escrow::instruction::MakeDutch {
+            seed: SEED,
+            receive: START_AMOUNT,
+            floor,
+            start_time: START_TIME,
+            end_time: END_TIME,
+            curve,
+            deposit: DEPOSIT_AMOUNT,
+        }
+        .data();
+        instruction
+    }
+
+    fn make_accounts(mollusk: &Mollusk) -> Vec<(Pubkey, Account)> {
+        let escrow = escrow_pda(&MAKER, SEED).0;
+        vec![
+            (
+                MAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(mollusk, 100_000_000, 6)),
+            (MINT_Y, mint_account(mollusk, 100_000_000, 6)),
+            (
+                get_associated_token_address(&MAKER, &MINT_X),
+                token_account(mollusk, MINT_X, MAKER, DEPOSIT_AMOUNT),
+            ),
+            (escrow, Account::default()),
+            (
+                get_associated_token_address(&escrow, &MINT_X),
+                Account::default(),
+            ),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect()
+    }
+
+    #[test]
+    fn test_linear_price() {
+        let escrow = auction(PriceCurve::Linear);
+
+        assert_eq!(escrow.receive_amount_at(0), START_AMOUNT);
+        assert_eq!(escrow.receive_amount_at(START_TIME), START_AMOUNT);
+        assert_eq!(escrow.receive_amount_at(1_200), 8_000);
+        assert_eq!(escrow.receive_amount_at(1_400), 6_000);
+        assert_eq!(escrow.receive_amount_at(END_TIME - 1), 2_010);
+        assert_eq!(escrow.receive_amount_at(END_TIME), FLOOR_AMOUNT);
+        assert_eq!(escrow.receive_amount_at(i64::MAX), FLOOR_AMOUNT);
+    }
+
+    #[test]
+    fn test_exponential_price() {
+        let escrow = auction(PriceCurve::Exponential);
+
+        //8 halvings over 800 seconds, one every 100
+        assert_eq!(escrow.receive_amount_at(START_TIME), START_AMOUNT);
+        assert_eq!(escrow.receive_amount_at(1_050), 2_000 + 6_000);
+        assert_eq!(escrow.receive_amount_at(1_100), 2_000 + 4_000);
+        assert_eq!(escrow.receive_amount_at(1_200), 2_000 + 2_000);
+        assert_eq!(escrow.receive_amount_at(1_700), 2_000 + 62);
+        assert_eq!(escrow.receive_amount_at(END_TIME), FLOOR_AMOUNT);
+
+        let mut previous = u64::MAX;
+        for now in START_TIME..=END_TIME {
+            let price = escrow.receive_amount_at(now);
+            assert!(price <= previous, "price went up at {}", now);
+            previous = price;
+        }
+    }
+
+    #[test]
+    fn test_fixed_price_ignores_time() {
+        let escrow = escrow_state(MAKER, SEED, MINT_X, MINT_Y, START_AMOUNT);
+
+        assert_eq!(escrow.receive_amount_at(i64::MIN), START_AMOUNT);
+        assert_eq!(escrow.receive_amount_at(i64::MAX), START_AMOUNT);
+    }
+
+    #[test]
+    fn test_make_dutch() {
+        let mollusk = setup();
+        let escrow = escrow_pda(&MAKER, SEED).0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_dutch_ix(PriceCurve::Linear, FLOOR_AMOUNT),
+            &make_accounts(&mollusk),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.price_curve, PriceCurve::Linear);
+        assert_eq!(escrow.receive_amount, START_AMOUNT);
+        assert_eq!(escrow.floor_amount, FLOOR_AMOUNT);
+        assert_eq!(escrow.start_time, START_TIME);
+        assert_eq!(escrow.end_time, END_TIME);
+    }
+
+    #[test]
+    fn test_make_dutch_floor_above_start() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &make_dutch_ix(PriceCurve::Linear, START_AMOUNT + 1),
+            &make_accounts(&mollusk),
+            &[Check::err(program_error(ErrorCode::InvalidPriceCurve))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_pays_current_price() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_400;
+
+        let escrow = escrow_pda(&MAKER, SEED).0;
+        let maker_ata_y = get_associated_token_address(&MAKER, &MINT_Y);
+        let taker_ata_y = get_associated_token_address(&TAKER, &MINT_Y);
+
+        let accounts: Vec<(Pubkey, Account)> = vec![
+            (
+                TAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                MAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(&mollusk, 100_000_000, 6)),
+            (MINT_Y, mint_account(&mollusk, 100_000_000, 6)),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                taker_ata_y,
+                token_account(&mollusk, MINT_Y, TAKER, START_AMOUNT),
+            ),
+            (maker_ata_y, token_account(&mollusk, MINT_Y, MAKER, 0)),
+            (
+                escrow,
+                escrow_account(&mollusk, &auction(PriceCurve::Linear)),
+            ),
+            (
+                get_associated_token_address(&escrow, &MINT_X),
+                token_account(&mollusk, MINT_X, escrow, DEPOSIT_AMOUNT),
+            ),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts,
+            &[Check::success(), Check::account(&escrow).closed().build()],
+        );
+
+        let maker_y = result.get_account(&maker_ata_y).unwrap();
+        let maker_y = spl_token::state::Account::unpack(&maker_y.data).unwrap();
+        assert_eq!(maker_y.amount, 6_000);
+    }
+}
@@ -0,0 +1,449 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Auction, Bid, Escrow, EscrowKind},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RESERVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 1;
+    const END_TIME: i64 = 10_000;
+    const MIN_INCREMENT: u64 = 500;
+    const EXTENSION: i64 = 300;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const BIDDER_1: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const BIDDER_2: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn auction_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"auction", escrow().as_ref()], &ID)
+    }
+
+    fn bid_pda(bidder: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"bid", escrow().as_ref(), bidder.as_ref()], &ID)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn start_auction_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::StartAuction {
+                end_time: END_TIME,
+                min_increment: MIN_INCREMENT,
+                extension: EXTENSION,
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(auction_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //Optional accounts that are not passed are replaced by the program id
+    fn bid_ix(bidder: Pubkey, amount: u64, previous: Option<Pubkey>) -> Instruction {
+        let bid = bid_pda(&bidder).0;
+        let optional = |key: Option<Pubkey>| match key {
+            Some(key) => AccountMeta::new(key, false),
+            None => AccountMeta::new_readonly(ID, false),
+        };
+        let previous_bid = previous.map(|p| bid_pda(&p).0);
+
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::Bid { amount }.data(),
+            vec![
+                AccountMeta::new(bidder, true),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&bidder, &MINT_Y), false),
+                AccountMeta::new_readonly(escrow(), false),
+                AccountMeta::new(auction_pda().0, false),
+                AccountMeta::new(bid, false),
+                AccountMeta::new(get_associated_token_address(&bid, &MINT_Y), false),
+                optional(previous),
+                optional(previous_bid),
+                optional(previous_bid.map(|b| get_associated_token_address(&b, &MINT_Y))),
+                optional(previous.map(|p| get_associated_token_address(&p, &MINT_Y))),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn settle_ix(settler: Pubkey, winner: Pubkey) -> Instruction {
+        let bid = bid_pda(&winner).0;
+
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SettleAuction {}.data(),
+            vec![
+                AccountMeta::new(settler, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new(winner, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&winner, &MINT_X), false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(auction_pda().0, false),
+                AccountMeta::new(get_associated_token_address(&escrow(), &MINT_X), false),
+                AccountMeta::new(bid, false),
+                AccountMeta::new(get_associated_token_address(&bid, &MINT_Y), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn auction_state(top_bid: u64, top_bidder: Pubkey) -> Auction {
+        Auction {
+            escrow: escrow(),
+            min_increment: MIN_INCREMENT,
+            end_time: END_TIME,
+            extension: EXTENSION,
+            top_bid,
+            top_bidder,
+            bump: auction_pda().1,
+        }
+    }
+
+    //An auction of 1 X with a reserve of 10_000 Y, optionally holding a top bid
+    fn accounts(mollusk: &Mollusk, top_bid: Option<(Pubkey, u64)>) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            kind: EscrowKind::EnglishAuction,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE)
+        };
+        let (top_bidder, top_amount) = top_bid.unwrap_or_default();
+
+        let mut accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(mollusk, 1, 0)),
+            (MINT_Y, mint_account(mollusk, 100_000_000, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                auction_pda().0,
+                state_account(
+                    mollusk,
+                    &auction_state(top_amount, top_bidder),
+                    8 + Auction::INIT_SPACE,
+                ),
+            ),
+        ];
+        for bidder in [BIDDER_1, BIDDER_2] {
+            accounts.push((bidder, user()));
+            accounts.push((
+                get_associated_token_address(&bidder, &MINT_Y),
+                token_account(mollusk, MINT_Y, bidder, 50_000),
+            ));
+        }
+        if let Some((bidder, amount)) = top_bid {
+            let (bid, bump) = bid_pda(&bidder);
+            let bid_state = Bid {
+                escrow: escrow(),
+                bidder,
+                amount,
+                bump,
+            };
+            accounts.push((bid, state_account(mollusk, &bid_state, 8 + Bid::INIT_SPACE)));
+            accounts.push((
+                get_associated_token_address(&bid, &MINT_Y),
+                token_account(mollusk, MINT_Y, bid, amount),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn fill_missing(
+        instruction: &Instruction,
+        mut accounts: Vec<(Pubkey, Account)>,
+    ) -> Vec<(Pubkey, Account)> {
+        for meta in &instruction.accounts {
+            if meta.pubkey != ID && !accounts.iter().any(|(key, _)| *key == meta.pubkey) {
+                accounts.push((meta.pubkey, Account::default()));
+            }
+        }
+        accounts
+    }
+
+    #[test]
+    fn test_start_auction() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(&mollusk, DEPOSIT_AMOUNT, 6)),
+            (
+                escrow(),
+                escrow_account(
+                    &mollusk,
+                    &escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE),
+                ),
+            ),
+            (auction_pda().0, Account::default()),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+
+        let result = mollusk.process_and_validate_instruction(
+            &start_auction_ix(),
+            &accounts,
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.kind, EscrowKind::EnglishAuction);
+
+        let auction = result.get_account(&auction_pda().0).unwrap();
+        let auction = Auction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.end_time, END_TIME);
+        assert!(!auction.has_bids());
+    }
+
+    #[test]
+    fn test_first_bid_below_reserve() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let instruction = bid_ix(BIDDER_1, RESERVE - 1, None);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, None)),
+            &[Check::err(program_error(ErrorCode::BidTooLow))],
+        );
+    }
+
+    #[test]
+    fn test_outbid_refunds_previous_bid() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let previous_bid = bid_pda(&BIDDER_1).0;
+        let instruction = bid_ix(BIDDER_2, RESERVE + MIN_INCREMENT, Some(BIDDER_1));
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, Some((BIDDER_1, RESERVE)))),
+            &[
+                Check::success(),
+                Check::account(&previous_bid).closed().build(),
+                Check::account(&get_associated_token_address(&previous_bid, &MINT_Y))
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        let bidder_1 = result
+            .get_account(&get_associated_token_address(&BIDDER_1, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(bidder_1), 50_000 + RESERVE);
+
+        let auction = result.get_account(&auction_pda().0).unwrap();
+        let auction = Auction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.top_bidder, BIDDER_2);
+        assert_eq!(auction.top_bid, RESERVE + MIN_INCREMENT);
+        assert_eq!(auction.end_time, END_TIME);
+    }
+
+    #[test]
+    fn test_outbid_needs_min_increment() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let instruction = bid_ix(BIDDER_2, RESERVE + MIN_INCREMENT - 1, Some(BIDDER_1));
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, Some((BIDDER_1, RESERVE)))),
+            &[Check::err(program_error(ErrorCode::BidTooLow))],
+        );
+    }
+
+    #[test]
+    fn test_late_bid_extends_auction() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = END_TIME - 10;
+
+        let instruction = bid_ix(BIDDER_1, RESERVE, None);
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, None)),
+            &[Check::success()],
+        );
+
+        let auction = result.get_account(&auction_pda().0).unwrap();
+        let auction = Auction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.end_time, END_TIME - 10 + EXTENSION);
+    }
+
+    #[test]
+    fn test_settle_auction() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = END_TIME;
+
+        let bid = bid_pda(&BIDDER_1).0;
+        let instruction = settle_ix(BIDDER_2, BIDDER_1);
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, Some((BIDDER_1, 12_000)))),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&auction_pda().0).closed().build(),
+                Check::account(&bid).closed().build(),
+            ],
+        );
+
+        let winner_x = result
+            .get_account(&get_associated_token_address(&BIDDER_1, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(winner_x), DEPOSIT_AMOUNT);
+
+        let maker_y = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_y), 12_000);
+    }
+
+    #[test]
+    fn test_settle_auction_won_by_pda() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = END_TIME;
+
+        //BIDDER_1 bid through a program that owns it, like a multisig vault
+        let mut accounts = accounts(&mollusk, Some((BIDDER_1, 12_000)));
+        for (key, account) in accounts.iter_mut() {
+            if *key == BIDDER_1 {
+                account.owner = Pubkey::new_from_array([0x06; 32]);
+                account.data = vec![0; 64];
+            }
+        }
+        let instruction = settle_ix(BIDDER_2, BIDDER_1);
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts),
+            &[Check::success(), Check::account(&escrow()).closed().build()],
+        );
+
+        let winner_x = result
+            .get_account(&get_associated_token_address(&BIDDER_1, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(winner_x), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_start_auction_with_payment_options() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let escrow_state = Escrow {
+            accepts_other_mints: true,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE)
+        };
+        let accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(&mollusk, DEPOSIT_AMOUNT, 6)),
+            (escrow(), escrow_account(&mollusk, &escrow_state)),
+            (auction_pda().0, Account::default()),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+
+        mollusk.process_and_validate_instruction(
+            &start_auction_ix(),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::NotAnOffer))],
+        );
+    }
+
+    #[test]
+    fn test_start_auction_of_nft_owing_royalties() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        //MINT_X is an NFT and the offer enforces its royalties
+        let accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(&mollusk, 1, 0)),
+            (
+                escrow(),
+                escrow_account(
+                    &mollusk,
+                    &escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE),
+                ),
+            ),
+            (auction_pda().0, Account::default()),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+
+        mollusk.process_and_validate_instruction(
+            &start_auction_ix(),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::RoyaltiesOwed))],
+        );
+    }
+
+    #[test]
+    fn test_settle_before_end() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = END_TIME - 1;
+
+        let instruction = settle_ix(BIDDER_2, BIDDER_1);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, Some((BIDDER_1, 12_000)))),
+            &[Check::err(program_error(ErrorCode::AuctionNotEnded))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_rejects_auction() {
+        let mollusk = setup();
+
+        let instruction = exchange_ix(BIDDER_1, MAKER, MINT_X, MINT_Y, SEED);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, None)),
+            &[Check::err(program_error(ErrorCode::NotAnOffer))],
+        );
+    }
+}
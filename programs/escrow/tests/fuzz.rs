@@ -0,0 +1,361 @@
+//Property-based fuzzing of random make/exchange/refund sequences.
+//Every step runs through Mollusk against a shared account "world" and the
+//conservation invariants are checked after each one.
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use anchor_lang::AccountDeserialize;
+    use escrow::state::Escrow;
+    use mollusk_svm::Mollusk;
+    use proptest::prelude::*;
+    use solana_sdk::{
+        account::Account, instruction::Instruction, native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const USERS: [Pubkey; 3] = [
+        Pubkey::new_from_array([0x01; 32]),
+        Pubkey::new_from_array([0x02; 32]),
+        Pubkey::new_from_array([0x03; 32]),
+    ];
+    const MINTS: [Pubkey; 2] = [
+        Pubkey::new_from_array([0x11; 32]),
+        Pubkey::new_from_array([0x12; 32]),
+    ];
+
+    #[derive(Debug, Clone)]
+    struct Substitution {
+        slot: usize,
+        with: usize,
+    }
+
+    #[derive(Debug, Clone)]
+    enum Step {
+        Make {
+            maker: usize,
+            seed: u64,
+            flip: bool,
+            receive: u64,
+            deposit: u64,
+        },
+        Exchange {
+            taker: usize,
+            offer: usize,
+            substitution: Option<Substitution>,
+        },
+        Refund {
+            signer: usize,
+            offer: usize,
+            substitution: Option<Substitution>,
+        },
+    }
+
+    #[derive(Debug, Clone)]
+    struct Offer {
+        maker: Pubkey,
+        seed: u64,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        receive: u64,
+        deposit: u64,
+    }
+
+    impl Offer {
+        fn escrow(&self) -> Pubkey {
+            escrow_pda(&self.maker, self.seed).0
+        }
+
+        fn vault(&self) -> Pubkey {
+            vault_address(&self.maker, &self.mint_a, self.seed)
+        }
+    }
+
+    fn substitution() -> impl Strategy<Value = Option<Substitution>> {
+        prop::option::weighted(0.3, (0..16usize, 0..64usize))
+            .prop_map(|s| s.map(|(slot, with)| Substitution { slot, with }))
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            (
+                0..USERS.len(),
+                0..4u64,
+                any::<bool>(),
+                0..50_000u64,
+                0..60_000u64
+            )
+                .prop_map(|(maker, seed, flip, receive, deposit)| Step::Make {
+                    maker,
+                    seed,
+                    flip,
+                    receive,
+                    deposit,
+                }),
+            (0..USERS.len(), 0..16usize, substitution()).prop_map(
+                |(taker, offer, substitution)| Step::Exchange {
+                    taker,
+                    offer,
+                    substitution,
+                }
+            ),
+            (0..USERS.len(), 0..16usize, substitution()).prop_map(
+                |(signer, offer, substitution)| Step::Refund {
+                    signer,
+                    offer,
+                    substitution,
+                }
+            ),
+        ]
+    }
+
+    struct World {
+        mollusk: Mollusk,
+        accounts: BTreeMap<Pubkey, Account>,
+        offers: Vec<Offer>,
+        supplies: [u64; 2],
+        lamports: u64,
+    }
+
+    impl World {
+        fn new(decimals: [u8; 2], balances: &[[u64; 2]; 3]) -> Self {
+            let mollusk = setup();
+            let mut accounts = BTreeMap::new();
+            let mut supplies = [0u64; 2];
+
+            for (user, user_balances) in USERS.iter().zip(balances) {
+                accounts.insert(
+                    *user,
+                    Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+                );
+                for (i, mint) in MINTS.iter().enumerate() {
+                    accounts.insert(
+                        get_associated_token_address(user, mint),
+                        token_account(&mollusk, *mint, *user, user_balances[i]),
+                    );
+                    supplies[i] += user_balances[i];
+                }
+            }
+            for (i, mint) in MINTS.iter().enumerate() {
+                accounts.insert(*mint, mint_account(&mollusk, supplies[i], decimals[i]));
+            }
+
+            let lamports = accounts.values().map(|a| a.lamports).sum();
+
+            Self {
+                mollusk,
+                accounts,
+                offers: Vec::new(),
+                supplies,
+                lamports,
+            }
+        }
+
+        //Every address the fuzzer may substitute into an instruction
+        fn pool(&self) -> Vec<Pubkey> {
+            let mut pool: Vec<Pubkey> = self.accounts.keys().copied().collect();
+            for offer in &self.offers {
+                pool.push(offer.escrow());
+                pool.push(offer.vault());
+            }
+            pool
+        }
+
+        fn substitute(&self, ix: &mut Instruction, substitution: &Option<Substitution>) -> bool {
+            let Some(substitution) = substitution else {
+                return false;
+            };
+            let slots: Vec<usize> = (0..ix.accounts.len())
+                .filter(|i| !is_program(&ix.accounts[*i].pubkey))
+                .collect();
+            let pool = self.pool();
+            let slot = slots[substitution.slot % slots.len()];
+            let with = pool[substitution.with % pool.len()];
+            let changed = ix.accounts[slot].pubkey != with;
+            ix.accounts[slot].pubkey = with;
+            changed
+        }
+
+        fn process(&mut self, ix: &Instruction) -> bool {
+            let result = self
+                .mollusk
+                .process_instruction(ix, &accounts_for(ix, &self.accounts));
+            if result.program_result.is_err() {
+                return false;
+            }
+            for (key, account) in result.resulting_accounts {
+                if !is_program(&key) {
+                    self.accounts.insert(key, account);
+                }
+            }
+            true
+        }
+
+        fn is_open(&self, key: &Pubkey) -> bool {
+            self.accounts.get(key).is_some_and(|a| a.lamports > 0)
+        }
+
+        fn apply(&mut self, step: &Step) {
+            match step.clone() {
+                Step::Make {
+                    maker,
+                    seed,
+                    flip,
+                    receive,
+                    deposit,
+                } => {
+                    let (mint_a, mint_b) = if flip {
+                        (MINTS[1], MINTS[0])
+                    } else {
+                        (MINTS[0], MINTS[1])
+                    };
+                    let maker = USERS[maker];
+                    let ix = make_ix(maker, mint_a, mint_b, seed, receive, deposit);
+                    if self.process(&ix) {
+                        let offer = Offer {
+                            maker,
+                            seed,
+                            mint_a,
+                            mint_b,
+                            receive,
+                            deposit,
+                        };
+                        //A closed escrow's seed can be reused for a new offer
+                        self.offers.retain(|o| o.escrow() != offer.escrow());
+                        self.offers.push(offer);
+                    }
+                }
+                Step::Exchange {
+                    taker,
+                    offer,
+                    substitution,
+                } => {
+                    if self.offers.is_empty() {
+                        return;
+                    }
+                    let offer = self.offers[offer % self.offers.len()].clone();
+                    let mut ix = exchange_ix(
+                        USERS[taker],
+                        offer.maker,
+                        offer.mint_a,
+                        offer.mint_b,
+                        offer.seed,
+                    );
+                    let substituted = self.substitute(&mut ix, &substitution);
+                    let ok = self.process(&ix);
+
+                    assert!(
+                        !(ok && substituted),
+                        "exchange succeeded with a substituted account: {:?}",
+                        ix.accounts
+                    );
+                }
+                Step::Refund {
+                    signer,
+                    offer,
+                    substitution,
+                } => {
+                    if self.offers.is_empty() {
+                        return;
+                    }
+                    let offer = self.offers[offer % self.offers.len()].clone();
+                    let signer = USERS[signer];
+                    let mut ix = refund_ix(signer, offer.maker, offer.mint_a, offer.seed);
+                    let substituted = self.substitute(&mut ix, &substitution);
+                    let ok = self.process(&ix);
+
+                    assert!(
+                        !(ok && substituted),
+                        "refund succeeded with a substituted account: {:?}",
+                        ix.accounts
+                    );
+                    assert!(
+                        !ok || signer == offer.maker,
+                        "{} refunded an escrow made by {}",
+                        signer,
+                        offer.maker
+                    );
+                }
+            }
+        }
+
+        fn check_invariants(&self) {
+            //Token supply is conserved per mint
+            for (i, mint) in MINTS.iter().enumerate() {
+                let total: u64 = self
+                    .accounts
+                    .values()
+                    .filter(|a| {
+                        a.lamports > 0
+                            && a.owner == spl_token::ID
+                            && a.data.len() == spl_token::state::Account::LEN
+                    })
+                    .map(|a| spl_token::state::Account::unpack(&a.data).unwrap())
+                    .filter(|t| t.mint == *mint)
+                    .map(|t| t.amount)
+                    .sum();
+                assert_eq!(total, self.supplies[i], "supply of mint {} changed", mint);
+            }
+
+            //Lamports are conserved
+            let lamports: u64 = self.accounts.values().map(|a| a.lamports).sum();
+            assert_eq!(
+                lamports, self.lamports,
+                "lamports were created or destroyed"
+            );
+
+            //Every open escrow matches what was made and its vault holds the deposit
+            for offer in &self.offers {
+                let escrow_key = offer.escrow();
+                if !self.is_open(&escrow_key) {
+                    assert!(
+                        !self.is_open(&offer.vault()),
+                        "vault {} outlived its escrow",
+                        offer.vault()
+                    );
+                    continue;
+                }
+
+                let account = &self.accounts[&escrow_key];
+                assert_eq!(account.owner, ID);
+                let escrow = Escrow::try_deserialize(&mut account.data.as_slice()).unwrap();
+                assert_eq!(escrow.maker, offer.maker);
+                assert_eq!(escrow.seed, offer.seed);
+                assert_eq!(escrow.token_mint_a, offer.mint_a);
+                assert_eq!(escrow.token_mint_b, offer.mint_b);
+                assert_eq!(escrow.receive_amount, offer.receive);
+
+                let vault =
+                    spl_token::state::Account::unpack(&self.accounts[&offer.vault()].data).unwrap();
+                assert_eq!(vault.owner, escrow_key);
+                assert_eq!(vault.mint, offer.mint_a);
+                assert_eq!(vault.amount, offer.deposit);
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn fuzz_instruction_sequences(
+            decimals in [0..=9u8, 0..=9u8],
+            balances in [[0..100_000u64, 0..100_000u64], [0..100_000u64, 0..100_000u64], [0..100_000u64, 0..100_000u64]],
+            steps in prop::collection::vec(step(), 1..24),
+        ) {
+            let mut world = World::new(decimals, &balances);
+            world.check_invariants();
+
+            for step in &steps {
+                world.apply(step);
+                world.check_invariants();
+            }
+        }
+    }
+}
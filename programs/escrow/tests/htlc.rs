@@ -0,0 +1,310 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{solana_program::hash::hash, AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, EscrowKind, Htlc},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const TIMELOCK: i64 = 10_000;
+    const PREIMAGE: [u8; 32] = [0x42; 32];
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const STRANGER: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn htlc_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"htlc", escrow().as_ref()], &ID)
+    }
+
+    fn vault() -> Pubkey {
+        get_associated_token_address(&escrow(), &MINT_X)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn htlc() -> Htlc {
+        Htlc {
+            escrow: escrow(),
+            taker: TAKER,
+            hashlock: hash(&PREIMAGE).to_bytes(),
+            timelock: TIMELOCK,
+            bump: htlc_pda().1,
+        }
+    }
+
+    //An open offer when `locked` is false, otherwise the same offer behind `htlc()`
+    fn accounts(mollusk: &Mollusk, locked: bool) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            kind: if locked {
+                EscrowKind::Htlc
+            } else {
+                EscrowKind::Offer
+            },
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, 0)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (STRANGER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 8)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                vault(),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                htlc_pda().0,
+                if locked {
+                    state_account(mollusk, &htlc(), 8 + Htlc::INIT_SPACE)
+                } else {
+                    Account::default()
+                },
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_X),
+                token_account(mollusk, MINT_X, MAKER, 0),
+            ),
+        ];
+        for taker in [TAKER, STRANGER] {
+            accounts.push((
+                get_associated_token_address(&taker, &MINT_X),
+                Account::default(),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn start_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::StartHtlc {
+                taker: TAKER,
+                hashlock: hash(&PREIMAGE).to_bytes(),
+                timelock: TIMELOCK,
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(htlc_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn claim_ix(taker: Pubkey, preimage: [u8; 32]) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::ClaimHtlc { preimage }.data(),
+            vec![
+                AccountMeta::new(taker, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(get_associated_token_address(&taker, &MINT_X), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(htlc_pda().0, false),
+                AccountMeta::new(vault(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn refund_htlc_ix() -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::RefundHtlc {}.data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_X), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(htlc_pda().0, false),
+                AccountMeta::new(vault(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_unlocks() {
+        assert!(htlc().unlocks(&PREIMAGE));
+        assert!(!htlc().unlocks(&[0x43; 32]));
+    }
+
+    #[test]
+    fn test_start_htlc() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let result = mollusk.process_and_validate_instruction(
+            &start_ix(),
+            &accounts(&mollusk, false),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.kind, EscrowKind::Htlc);
+
+        let htlc = result.get_account(&htlc_pda().0).unwrap();
+        let htlc = Htlc::try_deserialize(&mut htlc.data.as_slice()).unwrap();
+        assert_eq!(htlc.taker, TAKER);
+        assert_eq!(htlc.timelock, TIMELOCK);
+    }
+
+    #[test]
+    fn test_claim_with_preimage() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK - 1;
+        let htlc = htlc_pda().0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &claim_ix(TAKER, PREIMAGE),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&htlc).closed().build(),
+                Check::account(&vault()).closed().build(),
+            ],
+        );
+
+        let taker = result
+            .get_account(&get_associated_token_address(&TAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(taker), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_claim_from_pda_maker() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK - 1;
+
+        //the maker is owned by a program, like a multisig vault
+        let mut accounts = accounts(&mollusk, true);
+        for (key, account) in accounts.iter_mut() {
+            if *key == MAKER {
+                account.owner = Pubkey::new_from_array([0x09; 32]);
+                account.data = vec![0; 64];
+            }
+        }
+
+        let result = mollusk.process_and_validate_instruction(
+            &claim_ix(TAKER, PREIMAGE),
+            &accounts,
+            &[Check::success(), Check::account(&escrow()).closed().build()],
+        );
+
+        let taker = result
+            .get_account(&get_associated_token_address(&TAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(taker), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_claim_wrong_preimage() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK - 1;
+
+        mollusk.process_and_validate_instruction(
+            &claim_ix(TAKER, [0x43; 32]),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::WrongPreimage))],
+        );
+    }
+
+    #[test]
+    fn test_claim_by_other_taker() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK - 1;
+
+        mollusk.process_and_validate_instruction(
+            &claim_ix(STRANGER, PREIMAGE),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(
+                anchor_lang::error::ErrorCode::ConstraintHasOne,
+            ))],
+        );
+    }
+
+    #[test]
+    fn test_claim_after_timelock() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK;
+
+        mollusk.process_and_validate_instruction(
+            &claim_ix(TAKER, PREIMAGE),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::TimelockExpired))],
+        );
+    }
+
+    #[test]
+    fn test_refund_after_timelock() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK;
+        let htlc = htlc_pda().0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &refund_htlc_ix(),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&htlc).closed().build(),
+            ],
+        );
+
+        let maker = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(maker), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_refund_before_timelock() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = TIMELOCK - 1;
+
+        mollusk.process_and_validate_instruction(
+            &refund_htlc_ix(),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::TimelockNotExpired))],
+        );
+    }
+}
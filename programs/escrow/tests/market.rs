@@ -0,0 +1,428 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::constants::MAX_BOOK_ORDERS;
+    use escrow::{
+        error::ErrorCode,
+        state::{Book, BookOrder, Escrow, Market, Side},
+    };
+    use mollusk_svm::{
+        program::{self, loader_keys},
+        result::Check,
+        Mollusk,
+    };
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const BASE: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const QUOTE: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const TICK_SIZE: u64 = 10;
+    const LOT_SIZE: u64 = 100;
+    const MIN_ORDER_SIZE: u64 = 200;
+    //two resting asks of 1_000 base, at 400 and 500 quote per lot
+    const CHEAP: u64 = 1;
+    const DEAR: u64 = 2;
+    const SIZE: u64 = 1_000;
+
+    fn market_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"market", BASE.as_ref(), QUOTE.as_ref()], &ID)
+    }
+
+    fn book_pda(side: Side) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Book::seed(side), market_pda().0.as_ref()], &ID)
+    }
+
+    fn market() -> Market {
+        Market {
+            base_mint: BASE,
+            quote_mint: QUOTE,
+            tick_size: TICK_SIZE,
+            lot_size: LOT_SIZE,
+            min_order_size: MIN_ORDER_SIZE,
+            bump: market_pda().1,
+        }
+    }
+
+    fn order(seed: u64, price: u64) -> BookOrder {
+        BookOrder {
+            escrow: escrow_pda(&MAKER, seed).0,
+            price,
+            size: SIZE,
+        }
+    }
+
+    fn book(side: Side, orders: Vec<BookOrder>) -> Book {
+        Book {
+            market: market_pda().0,
+            side,
+            orders,
+            bump: book_pda(side).1,
+        }
+    }
+
+    fn listed_ask(seed: u64, price: u64) -> Escrow {
+        Escrow {
+            market: market_pda().0,
+            ..escrow_state(MAKER, seed, BASE, QUOTE, price * SIZE / LOT_SIZE)
+        }
+    }
+
+    fn get_book(account: &Account) -> Book {
+        Book::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    fn with_book(mut instruction: Instruction, side: Side) -> Instruction {
+        instruction
+            .accounts
+            .push(AccountMeta::new(book_pda(side).0, false));
+        instruction
+    }
+
+    fn program_data_pda() -> Pubkey {
+        Pubkey::find_program_address(&[ID.as_ref()], &loader_keys::LOADER_V3).0
+    }
+
+    //The escrow program, upgradeable by `authority`
+    fn program_accounts_upgradeable_by(authority: Pubkey) -> Vec<(Pubkey, Account)> {
+        let mut program_data = program::create_program_data_account_loader_v3(&[]);
+        //after the enum tag and slot, `upgrade_authority_address` is an Option<Pubkey>
+        program_data.data[12] = 1;
+        program_data.data[13..45].copy_from_slice(authority.as_ref());
+        vec![
+            (ID, program::create_program_account_loader_v3(&ID)),
+            (program_data_pda(), program_data),
+        ]
+    }
+
+    fn create_market_ix(tick_size: u64) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::CreateMarket {
+                tick_size,
+                lot_size: LOT_SIZE,
+                min_order_size: MIN_ORDER_SIZE,
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(ID, false),
+                AccountMeta::new_readonly(program_data_pda(), false),
+                AccountMeta::new_readonly(BASE, false),
+                AccountMeta::new_readonly(QUOTE, false),
+                AccountMeta::new(market_pda().0, false),
+                AccountMeta::new(book_pda(Side::Ask).0, false),
+                AccountMeta::new(book_pda(Side::Bid).0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn list_ix(seed: u64, receive: u64) -> Instruction {
+        let mut instruction = make_ix(MAKER, BASE, QUOTE, seed, receive, SIZE);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(market_pda().0, false));
+        with_book(instruction, Side::Ask)
+    }
+
+    //The price feed and payment options are left out by passing the program id
+    fn take_ix(seed: u64) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, BASE, QUOTE, seed);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(ID, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(ID, false));
+        with_book(instruction, Side::Ask)
+    }
+
+    fn refund_from_book_ix(seed: u64) -> Instruction {
+        let mut instruction = refund_ix(MAKER, MAKER, BASE, seed);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(ID, false));
+        with_book(instruction, Side::Ask)
+    }
+
+    //The market with both asks resting when `listed`, otherwise an empty book
+    fn accounts(mollusk: &Mollusk, listed: bool) -> Vec<(Pubkey, Account)> {
+        let user = || Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID);
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (BASE, mint_account(mollusk, 3 * SIZE, 6)),
+            (QUOTE, mint_account(mollusk, 100_000, 6)),
+            (
+                market_pda().0,
+                state_account(mollusk, &market(), Market::LEN),
+            ),
+            (
+                book_pda(Side::Bid).0,
+                state_account(mollusk, &book(Side::Bid, vec![]), Book::LEN),
+            ),
+            (
+                get_associated_token_address(&MAKER, &BASE),
+                token_account(mollusk, BASE, MAKER, SIZE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &QUOTE),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &BASE),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &QUOTE),
+                token_account(mollusk, QUOTE, TAKER, 100_000),
+            ),
+        ];
+
+        let orders = if listed {
+            vec![order(CHEAP, 400), order(DEAR, 500)]
+        } else {
+            vec![]
+        };
+        accounts.push((
+            book_pda(Side::Ask).0,
+            state_account(mollusk, &book(Side::Ask, orders), Book::LEN),
+        ));
+        for (seed, price) in [(CHEAP, 400), (DEAR, 500)] {
+            let escrow = escrow_pda(&MAKER, seed).0;
+            let vault = get_associated_token_address(&escrow, &BASE);
+            if listed {
+                accounts.push((escrow, escrow_account(mollusk, &listed_ask(seed, price))));
+                accounts.push((vault, token_account(mollusk, BASE, escrow, SIZE)));
+            } else {
+                accounts.push((escrow, Account::default()));
+                accounts.push((vault, Account::default()));
+            }
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_market_price() {
+        let market = market();
+        assert_eq!(market.price(1_000, 5_000), Some(500));
+        //below the minimum
+        assert_eq!(market.price(100, 500), None);
+        //not whole lots
+        assert_eq!(market.price(250, 1_000), None);
+        //off the tick
+        assert_eq!(market.price(1_000, 5_050), None);
+    }
+
+    #[test]
+    fn test_book_orders_best_price_first() {
+        let mut asks = book(Side::Ask, vec![]);
+        assert!(asks.insert(order(1, 500)));
+        assert!(asks.insert(order(2, 400)));
+        assert!(asks.insert(order(3, 500)));
+        let seeds: Vec<Pubkey> = asks.orders.iter().map(|o| o.escrow).collect();
+        assert_eq!(
+            seeds,
+            [2, 1, 3].map(|seed| escrow_pda(&MAKER, seed).0).to_vec()
+        );
+
+        let mut bids = book(Side::Bid, vec![]);
+        assert!(bids.insert(order(1, 400)));
+        assert!(bids.insert(order(2, 500)));
+        assert_eq!(bids.best(), Some(&order(2, 500)));
+
+        assert!(bids.remove(&escrow_pda(&MAKER, 2).0));
+        assert!(!bids.remove(&escrow_pda(&MAKER, 2).0));
+        assert_eq!(bids.best(), Some(&order(1, 400)));
+    }
+
+    //The accounts of a market not created yet, `MAKER` acting as `authority`
+    fn create_market_accounts(mollusk: &Mollusk, authority: Pubkey) -> Vec<(Pubkey, Account)> {
+        let mut accounts = accounts(mollusk, false);
+        for key in [market_pda().0, book_pda(Side::Ask).0, book_pda(Side::Bid).0] {
+            accounts.iter_mut().find(|(k, _)| *k == key).unwrap().1 = Account::default();
+        }
+        accounts.extend(program_accounts_upgradeable_by(authority));
+        accounts
+    }
+
+    #[test]
+    fn test_book_evicts_worst_order_when_full() {
+        let mut asks = book(Side::Ask, vec![]);
+        for seed in 0..MAX_BOOK_ORDERS as u64 {
+            assert!(asks.insert(order(seed, 500 + seed * TICK_SIZE)));
+        }
+        let worst = *asks.orders.last().unwrap();
+
+        //not better than the worst resting order
+        assert!(!asks.insert(order(100, worst.price)));
+
+        assert!(asks.insert(order(101, 400)));
+        assert_eq!(asks.orders.len(), MAX_BOOK_ORDERS);
+        assert_eq!(asks.best(), Some(&order(101, 400)));
+        assert!(!asks.orders.contains(&worst));
+    }
+
+    #[test]
+    fn test_create_market() {
+        let mollusk = setup();
+        let accounts = create_market_accounts(&mollusk, MAKER);
+
+        let result = mollusk.process_and_validate_instruction(
+            &create_market_ix(TICK_SIZE),
+            &accounts,
+            &[Check::success()],
+        );
+
+        let market_account = result.get_account(&market_pda().0).unwrap();
+        let created = Market::try_deserialize(&mut market_account.data.as_slice()).unwrap();
+        assert_eq!(created.lot_size, LOT_SIZE);
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.side, Side::Ask);
+        assert!(asks.orders.is_empty());
+    }
+
+    #[test]
+    fn test_create_market_not_upgrade_authority() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &create_market_ix(TICK_SIZE),
+            &create_market_accounts(&mollusk, TAKER),
+            &[Check::err(program_error(ErrorCode::Unauthorized))],
+        );
+    }
+
+    #[test]
+    fn test_make_lists_on_market() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &list_ix(CHEAP, 4_000),
+            &accounts(&mollusk, false),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow_pda(&MAKER, CHEAP).0).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.market, market_pda().0);
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.orders, vec![order(CHEAP, 400)]);
+    }
+
+    #[test]
+    fn test_make_rejects_off_grid_price() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &list_ix(CHEAP, 4_005),
+            &accounts(&mollusk, false),
+            &[Check::err(program_error(ErrorCode::OffMarketGrid))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_takes_best_price() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &take_ix(CHEAP),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER, CHEAP).0)
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.orders, vec![order(DEAR, 500)]);
+    }
+
+    #[test]
+    fn test_exchange_takes_worse_price() {
+        let mollusk = setup();
+
+        //a cheaper order the taker can't fill doesn't block the others
+        let result = mollusk.process_and_validate_instruction(
+            &take_ix(DEAR),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER, DEAR).0).closed().build(),
+            ],
+        );
+
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.orders, vec![order(CHEAP, 400)]);
+    }
+
+    #[test]
+    fn test_exchange_takes_evicted_order() {
+        let mollusk = setup();
+        //DEAR was pushed out of the book by better orders
+        let mut accounts = accounts(&mollusk, true);
+        accounts
+            .iter_mut()
+            .find(|(k, _)| *k == book_pda(Side::Ask).0)
+            .unwrap()
+            .1 = state_account(
+            &mollusk,
+            &book(Side::Ask, vec![order(CHEAP, 400)]),
+            Book::LEN,
+        );
+
+        let result = mollusk.process_and_validate_instruction(
+            &take_ix(DEAR),
+            &accounts,
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER, DEAR).0).closed().build(),
+            ],
+        );
+
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.orders, vec![order(CHEAP, 400)]);
+    }
+
+    #[test]
+    fn test_exchange_listed_escrow_needs_book() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, BASE, QUOTE, CHEAP),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::ListedOnMarket))],
+        );
+    }
+
+    #[test]
+    fn test_refund_delists() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &refund_from_book_ix(DEAR),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER, DEAR).0).closed().build(),
+            ],
+        );
+
+        let asks = get_book(result.get_account(&book_pda(Side::Ask).0).unwrap());
+        assert_eq!(asks.orders, vec![order(CHEAP, 400)]);
+    }
+}
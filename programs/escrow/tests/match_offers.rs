@@ -0,0 +1,298 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, MatchConfig, MatchFill},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const MAKER_A: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const MAKER_B: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const KEEPER: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    //MAKER_A sells 1_000 X for 2_000 Y
+    const DEPOSIT_A: u64 = 1_000;
+    const RECEIVE_A: u64 = 2_000;
+
+    fn config_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"match_config"], &ID)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn match_offers_ix() -> Instruction {
+        let escrow_a = escrow_pda(&MAKER_A, SEED).0;
+        let escrow_b = escrow_pda(&MAKER_B, SEED).0;
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::MatchOffers {}.data(),
+            vec![
+                AccountMeta::new(KEEPER, true),
+                AccountMeta::new_readonly(MAKER_A, false),
+                AccountMeta::new_readonly(MAKER_B, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(escrow_a, false),
+                AccountMeta::new(get_associated_token_address(&escrow_a, &MINT_X), false),
+                AccountMeta::new(escrow_b, false),
+                AccountMeta::new(get_associated_token_address(&escrow_b, &MINT_Y), false),
+                AccountMeta::new(get_associated_token_address(&MAKER_A, &MINT_Y), false),
+                AccountMeta::new(get_associated_token_address(&MAKER_B, &MINT_X), false),
+                AccountMeta::new(get_associated_token_address(&KEEPER, &MINT_Y), false),
+                AccountMeta::new_readonly(config_pda().0, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //MAKER_B sells `deposit_b` Y for `receive_b` X
+    fn accounts(
+        mollusk: &Mollusk,
+        deposit_b: u64,
+        receive_b: u64,
+        keeper_bps: Option<u16>,
+    ) -> Vec<(Pubkey, Account)> {
+        let escrow_a = escrow_pda(&MAKER_A, SEED).0;
+        let escrow_b = escrow_pda(&MAKER_B, SEED).0;
+        let mut accounts = vec![
+            (KEEPER, user()),
+            (MAKER_A, user()),
+            (MAKER_B, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_A, 6)),
+            (MINT_Y, mint_account(mollusk, deposit_b, 6)),
+            (
+                escrow_a,
+                escrow_account(
+                    mollusk,
+                    &escrow_state(MAKER_A, SEED, MINT_X, MINT_Y, RECEIVE_A),
+                ),
+            ),
+            (
+                get_associated_token_address(&escrow_a, &MINT_X),
+                token_account(mollusk, MINT_X, escrow_a, DEPOSIT_A),
+            ),
+            (
+                escrow_b,
+                escrow_account(
+                    mollusk,
+                    &escrow_state(MAKER_B, SEED, MINT_Y, MINT_X, receive_b),
+                ),
+            ),
+            (
+                get_associated_token_address(&escrow_b, &MINT_Y),
+                token_account(mollusk, MINT_Y, escrow_b, deposit_b),
+            ),
+            (
+                get_associated_token_address(&MAKER_A, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&MAKER_B, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&KEEPER, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                config_pda().0,
+                match keeper_bps {
+                    Some(keeper_bps) => state_account(
+                        mollusk,
+                        &MatchConfig {
+                            keeper_bps,
+                            bump: config_pda().1,
+                        },
+                        8 + MatchConfig::INIT_SPACE,
+                    ),
+                    None => Account::default(),
+                },
+            ),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_match_fill() {
+        let escrow_a = escrow_state(MAKER_A, SEED, MINT_X, MINT_Y, RECEIVE_A);
+
+        //B pays 2.5 Y per X for all of A
+        let escrow_b = escrow_state(MAKER_B, SEED, MINT_Y, MINT_X, 1_000);
+        let fill = MatchFill::compute(&escrow_a, DEPOSIT_A, &escrow_b, 2_500).unwrap();
+        assert_eq!(
+            fill,
+            MatchFill {
+                amount_a: 1_000,
+                price_b: 2_000,
+                released_b: 2_500,
+            }
+        );
+        assert_eq!(fill.spread(), 500);
+        assert_eq!(fill.keeper_share(2_000), 100);
+
+        //B only wants 333 X, A's price rounds up
+        let escrow_b = escrow_state(MAKER_B, SEED, MINT_Y, MINT_X, 333);
+        let fill = MatchFill::compute(&escrow_a, DEPOSIT_A, &escrow_b, 700).unwrap();
+        assert_eq!(fill.amount_a, 333);
+        assert_eq!(fill.price_b, 666);
+        assert_eq!(fill.released_b, 700);
+
+        //B pays 1.5 Y per X, below A's ask
+        let escrow_b = escrow_state(MAKER_B, SEED, MINT_Y, MINT_X, 1_000);
+        assert_eq!(
+            MatchFill::compute(&escrow_a, DEPOSIT_A, &escrow_b, 1_500),
+            None
+        );
+    }
+
+    //The second vault is closed once its maker is paid in full, which releases all of it
+    #[test]
+    fn test_match_fill_empties_closed_vaults() {
+        let escrow_a = escrow_state(MAKER_A, SEED, MINT_X, MINT_Y, RECEIVE_A);
+        for (receive_b, vault_b) in [(333, 700), (999, 2_001), (1, 7), (1_000, 2_999)] {
+            let escrow_b = escrow_state(MAKER_B, SEED, MINT_Y, MINT_X, receive_b);
+            let fill = MatchFill::compute(&escrow_a, DEPOSIT_A, &escrow_b, vault_b).unwrap();
+            if fill.amount_a == receive_b {
+                assert_eq!(fill.released_b, vault_b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_match_offers_with_pda_maker() {
+        let mollusk = setup();
+
+        //MAKER_B is a vault owned by another program, like a multisig
+        let mut accounts = accounts(&mollusk, 2_500, 1_000, None);
+        for (key, account) in accounts.iter_mut() {
+            if *key == MAKER_B {
+                account.owner = Pubkey::new_unique();
+                account.data = vec![0; 64];
+            }
+        }
+
+        let result = mollusk.process_and_validate_instruction(
+            &match_offers_ix(),
+            &accounts,
+            &[Check::success()],
+        );
+
+        let maker_b_x = result
+            .get_account(&get_associated_token_address(&MAKER_B, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(maker_b_x), DEPOSIT_A);
+    }
+
+    #[test]
+    fn test_match_offers_keeper_keeps_spread() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &match_offers_ix(),
+            &accounts(&mollusk, 2_500, 1_000, None),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER_A, SEED).0)
+                    .closed()
+                    .build(),
+                Check::account(&escrow_pda(&MAKER_B, SEED).0)
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        let received = |owner: &Pubkey, mint: &Pubkey| {
+            amount(
+                result
+                    .get_account(&get_associated_token_address(owner, mint))
+                    .unwrap(),
+            )
+        };
+        assert_eq!(received(&MAKER_A, &MINT_Y), RECEIVE_A);
+        assert_eq!(received(&MAKER_B, &MINT_X), DEPOSIT_A);
+        assert_eq!(received(&KEEPER, &MINT_Y), 500);
+    }
+
+    #[test]
+    fn test_match_offers_splits_spread_per_config() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &match_offers_ix(),
+            &accounts(&mollusk, 2_500, 1_000, Some(2_000)),
+            &[Check::success()],
+        );
+
+        let maker_a = result
+            .get_account(&get_associated_token_address(&MAKER_A, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_a), RECEIVE_A + 400);
+        let keeper = result
+            .get_account(&get_associated_token_address(&KEEPER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(keeper), 100);
+    }
+
+    #[test]
+    fn test_match_offers_partially_fills_larger_offer() {
+        let mollusk = setup();
+        let escrow_a = escrow_pda(&MAKER_A, SEED).0;
+
+        let result = mollusk.process_and_validate_instruction(
+            &match_offers_ix(),
+            &accounts(&mollusk, 1_000, 400, None),
+            &[
+                Check::success(),
+                Check::account(&escrow_pda(&MAKER_B, SEED).0)
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        let escrow = result.get_account(&escrow_a).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.receive_amount, 1_200);
+
+        let vault = result
+            .get_account(&get_associated_token_address(&escrow_a, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(vault), 600);
+    }
+
+    #[test]
+    fn test_match_offers_rejects_uncrossed_prices() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &match_offers_ix(),
+            &accounts(&mollusk, 1_500, 1_000, None),
+            &[Check::err(program_error(ErrorCode::OffersDontCross))],
+        );
+    }
+}
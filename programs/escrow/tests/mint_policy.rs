@@ -0,0 +1,257 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, Space};
+    use anchor_spl::token_2022::spl_token_2022::{
+        extension::{
+            default_account_state::DefaultAccountState, non_transferable::NonTransferable,
+            permanent_delegate::PermanentDelegate, transfer_hook::TransferHook,
+            BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+        },
+        state::{AccountState, Mint},
+    };
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, MintPolicy},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::{Account, WritableAccount},
+        native_token::LAMPORTS_PER_SOL,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 5_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const ISSUER: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    //A Token-2022 mint carrying `extensions`, each set the risky way
+    fn mint_2022(extensions: &[ExtensionType]) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+        let mut data = vec![0; len];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        for extension in extensions {
+            match extension {
+                ExtensionType::PermanentDelegate => {
+                    mint.init_extension::<PermanentDelegate>(true)
+                        .unwrap()
+                        .delegate = Some(ISSUER).try_into().unwrap();
+                }
+                ExtensionType::DefaultAccountState => {
+                    mint.init_extension::<DefaultAccountState>(true)
+                        .unwrap()
+                        .state = AccountState::Frozen as u8;
+                }
+                ExtensionType::NonTransferable => {
+                    mint.init_extension::<NonTransferable>(true).unwrap();
+                }
+                ExtensionType::TransferHook => {
+                    mint.init_extension::<TransferHook>(true)
+                        .unwrap()
+                        .program_id = Some(ISSUER).try_into().unwrap();
+                }
+                extension => panic!("no risky setting for {extension:?}"),
+            }
+        }
+        mint.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    fn frozen_mint(mollusk: &Mollusk) -> Account {
+        let mut account = mint_account(mollusk, RECEIVE, 6);
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                mint_authority: COption::None,
+                supply: RECEIVE,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::Some(ISSUER),
+            },
+            account.data_as_mut_slice(),
+        )
+        .unwrap();
+        account
+    }
+
+    fn policy_account(mollusk: &Mollusk, reject: u8) -> Account {
+        state_account(
+            mollusk,
+            &MintPolicy {
+                reject,
+                bump: mint_policy_pda().1,
+            },
+            8 + MintPolicy::INIT_SPACE,
+        )
+    }
+
+    //MINT_Y has a freeze authority, `policy` is put ahead of the unconfigured default
+    fn accounts(mollusk: &Mollusk, policy: Option<Account>) -> Vec<(Pubkey, Account)> {
+        let escrow = escrow_pda(&MAKER, SEED).0;
+        policy
+            .map(|policy| (mint_policy_pda().0, policy))
+            .into_iter()
+            .chain([
+                (
+                    MAKER,
+                    Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+                ),
+                (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+                (MINT_Y, frozen_mint(mollusk)),
+                (
+                    get_associated_token_address(&MAKER, &MINT_X),
+                    token_account(mollusk, MINT_X, MAKER, DEPOSIT_AMOUNT),
+                ),
+                (escrow, Account::default()),
+                (
+                    get_associated_token_address(&escrow, &MINT_X),
+                    Account::default(),
+                ),
+            ])
+            .chain(program_accounts())
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_mints_are_safe() {
+        let mollusk = Mollusk::default();
+        let mint = mint_account(&mollusk, RECEIVE, 6);
+        assert_eq!(MintPolicy::risks(&mint.data).unwrap(), 0);
+        assert_eq!(MintPolicy::risks(&mint_2022(&[])).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_freeze_authority_is_flagged() {
+        let mollusk = Mollusk::default();
+        assert_eq!(
+            MintPolicy::risks(&frozen_mint(&mollusk).data).unwrap(),
+            MintPolicy::FREEZE_AUTHORITY
+        );
+    }
+
+    #[test]
+    fn test_extensions_are_flagged() {
+        let cases = [
+            (
+                ExtensionType::PermanentDelegate,
+                MintPolicy::PERMANENT_DELEGATE,
+            ),
+            (
+                ExtensionType::DefaultAccountState,
+                MintPolicy::DEFAULT_FROZEN,
+            ),
+            (ExtensionType::NonTransferable, MintPolicy::NON_TRANSFERABLE),
+            (ExtensionType::TransferHook, MintPolicy::TRANSFER_HOOK),
+        ];
+        for (extension, risk) in cases {
+            assert_eq!(MintPolicy::risks(&mint_2022(&[extension])).unwrap(), risk);
+        }
+
+        let all = mint_2022(&cases.map(|(extension, _)| extension));
+        assert_eq!(
+            MintPolicy::risks(&all).unwrap(),
+            MintPolicy::ALL & !MintPolicy::FREEZE_AUTHORITY
+        );
+    }
+
+    #[test]
+    fn test_unset_extensions_are_safe() {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::PermanentDelegate,
+            ExtensionType::DefaultAccountState,
+            ExtensionType::TransferHook,
+        ])
+        .unwrap();
+        let mut data = vec![0; len];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        mint.init_extension::<PermanentDelegate>(true).unwrap();
+        mint.init_extension::<DefaultAccountState>(true)
+            .unwrap()
+            .state = AccountState::Initialized as u8;
+        mint.init_extension::<TransferHook>(true).unwrap();
+        mint.base = Mint {
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+
+        assert_eq!(MintPolicy::risks(&data).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_make_flags_risky_mint() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT),
+            &accounts(&mollusk, None),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow_pda(&MAKER, SEED).0).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.mint_risks, MintPolicy::FREEZE_AUTHORITY);
+    }
+
+    #[test]
+    fn test_make_allows_risks_the_policy_accepts() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT),
+            &accounts(
+                &mollusk,
+                Some(policy_account(&mollusk, MintPolicy::PERMANENT_DELEGATE)),
+            ),
+            &[Check::success()],
+        );
+    }
+
+    #[test]
+    fn test_make_rejects_risky_mint() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT),
+            &accounts(&mollusk, Some(policy_account(&mollusk, MintPolicy::ALL))),
+            &[Check::err(program_error(ErrorCode::RiskyMint))],
+        );
+    }
+
+    #[test]
+    fn test_make_unconfigured_policy_rejects_default_risks() {
+        let mollusk = setup();
+
+        let mut accounts = accounts(&mollusk, None);
+        let (_, mint_y) = accounts.iter_mut().find(|(key, _)| *key == MINT_Y).unwrap();
+        let data = mint_2022(&[ExtensionType::PermanentDelegate]);
+        *mint_y = Account {
+            lamports: mollusk.sysvars.rent.minimum_balance(data.len()),
+            data,
+            owner: anchor_spl::token_2022::ID,
+            ..Account::default()
+        };
+
+        mollusk.process_and_validate_instruction(
+            &make_ix(MAKER, MINT_X, MINT_Y, SEED, RECEIVE, DEPOSIT_AMOUNT),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::RiskyMint))],
+        );
+    }
+}
@@ -7,7 +7,7 @@ mod tests {
     use anchor_lang::InstructionData;
     use anchor_lang::Space;
 
-    use escrow::state::Escrow;
+    use escrow::state::{Escrow, EscrowKind, PriceCurve, RoyaltyPolicy, Side, TriggerDirection};
     use mollusk_svm::{program, result::Check, Mollusk};
     use solana_sdk::{
         account::{Account, WritableAccount},
@@ -31,7 +31,6 @@ mod tests {
     const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
     const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
     const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
-    const VAULT: Pubkey = Pubkey::new_from_array([0x09; 32]);
 
     #[test]
     fn test_make() {
@@ -63,15 +62,17 @@ mod tests {
         );
 
         //get remanining pubkeys
-        let (escrow, escrow_bump) = solana_sdk::pubkey::Pubkey::find_program_address(
+        let (escrow, _escrow_bump) = solana_sdk::pubkey::Pubkey::find_program_address(
             &[(b"escrow"), &MAKER.to_bytes(), &SEED.to_le_bytes()],
             &ID,
         );
         let maker_ata_pubkey = get_associated_token_address(&MAKER, &MINT_X);
         let vault_pubkey = get_associated_token_address(&escrow, &MINT_X);
+        let (mint_policy, _) =
+            solana_sdk::pubkey::Pubkey::find_program_address(&[b"mint_policy"], &ID);
 
         //Make your Accounts DB
-        let maker_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
+        let maker_account = Account::new(LAMPORTS_PER_SOL, 0, &system_program);
         let mut mint_x_account = Account::new(
             mollusk
                 .sysvars
@@ -96,7 +97,7 @@ mod tests {
             spl_token::state::Account::LEN,
             &token_program,
         );
-        let mut vault_account = Account::new(0, 0, &system_program);
+        let vault_account = Account::new(0, 0, &system_program);
         let escrow_account = Account::new(0, 0, &system_program);
 
         //Inject the data in to the accounts
@@ -157,6 +158,7 @@ mod tests {
             AccountMeta::new_readonly(token_program, false),
             AccountMeta::new_readonly(associated_program, false),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new_readonly(mint_policy, false),
         ];
 
         //Make Instructiom
@@ -173,6 +175,7 @@ mod tests {
             (associated_program, associated_account.clone()),
             (token_program, token_account.clone()),
             (system_program, system_account.clone()),
+            (mint_policy, Account::default()),
         ];
 
         //Test
@@ -220,8 +223,8 @@ mod tests {
         let vault_pubkey = get_associated_token_address(&escrow, &MINT_X);
 
         //Make your Accounts DB
-        let taker_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
-        let maker_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
+        let taker_account = Account::new(LAMPORTS_PER_SOL, 0, &system_program);
+        let maker_account = Account::new(LAMPORTS_PER_SOL, 0, &system_program);
 
         let mut mint_x_account = Account::new(
             mollusk
@@ -371,6 +374,30 @@ mod tests {
             token_mint_b: MINT_Y,
             receive_amount: RECEIVE_AMOUNT,
             bump: escrow_bump,
+            version: Escrow::VERSION,
+            price_curve: PriceCurve::Fixed,
+            floor_amount: 0,
+            start_time: 0,
+            end_time: 0,
+            kind: EscrowKind::Offer,
+            oracle: Pubkey::default(),
+            spread_bps: 0,
+            max_age: 0,
+            max_confidence_bps: 0,
+            trigger_price: 0,
+            trigger_expo: 0,
+            trigger_direction: TriggerDirection::Below,
+            trigger_tip: 0,
+            accepts_other_mints: false,
+            side: Side::Ask,
+            market: Pubkey::default(),
+            allowlisted: false,
+            gated: false,
+            mint_risks: 0,
+            royalty_policy: RoyaltyPolicy::Enforced,
+            split: false,
+            destination: Pubkey::default(),
+            reserved: [0; 94],
         };
 
         let mut escrow_writable_acc = escrow_account.data_as_mut_slice();
@@ -0,0 +1,441 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, PriceCurve, PriceFeed},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const DEPOSIT_AMOUNT: u64 = 2_000_000_000;
+    const SPREAD_BPS: u16 = 100;
+    const MAX_AGE: i64 = 60;
+    const MAX_CONFIDENCE_BPS: u16 = 50;
+    const NOW: i64 = 1_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const FEED: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    //150.25 Y per X
+    fn feed(publish_time: i64) -> PriceFeed {
+        PriceFeed {
+            price: 15_025,
+            conf: 5,
+            expo: -2,
+            publish_time,
+        }
+    }
+
+    fn offer() -> Escrow {
+        Escrow {
+            price_curve: PriceCurve::Oracle,
+            oracle: FEED,
+            spread_bps: SPREAD_BPS,
+            max_age: MAX_AGE,
+            max_confidence_bps: MAX_CONFIDENCE_BPS,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, 0)
+        }
+    }
+
+    fn feed_account(mollusk: &Mollusk, feed: &PriceFeed) -> Account {
+        price_feed_account(mollusk, feed)
+    }
+
+    //Loads `account` as the program does, outside the runtime
+    fn load(account: &Account) -> anchor_lang::Result<PriceFeed> {
+        let key = FEED;
+        let mut lamports = account.lamports;
+        let mut data = account.data.clone();
+        let info = solana_sdk::account_info::AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &account.owner,
+            false,
+            0,
+        );
+        PriceFeed::load(&info)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn make_oracle_ix() -> Instruction {
+        let mut instruction = make_ix(MAKER, MINT_X, MINT_Y, SEED, 0, DEPOSIT_AMOUNT);
+        instruction.data = escrow::instruction::MakeOracle {
+            seed: SEED,
+            oracle: FEED,
+            spread_bps: SPREAD_BPS,
+            max_age: MAX_AGE,
+            max_confidence_bps: MAX_CONFIDENCE_BPS,
+            deposit: DEPOSIT_AMOUNT,
+        }
+        .data();
+        instruction
+    }
+
+    fn exchange_with_feed_ix(feed: Pubkey) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(feed, false));
+        instruction
+    }
+
+    //2 X (9 decimals) offered against Y (6 decimals)
+    fn exchange_accounts(mollusk: &Mollusk, feed: &PriceFeed) -> Vec<(Pubkey, Account)> {
+        let escrow = escrow_pda(&MAKER, SEED).0;
+        vec![
+            (
+                TAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                MAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 9)),
+            (MINT_Y, mint_account(mollusk, 1_000_000_000, 6)),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, 1_000_000_000),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (escrow, escrow_account(mollusk, &offer())),
+            (
+                get_associated_token_address(&escrow, &MINT_X),
+                token_account(mollusk, MINT_X, escrow, DEPOSIT_AMOUNT),
+            ),
+            (FEED, feed_account(mollusk, feed)),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect()
+    }
+
+    #[test]
+    fn test_oracle_price_with_spread() {
+        //2 X at 150.25 Y plus 1%
+        let pay = offer()
+            .oracle_receive_amount(&feed(NOW), DEPOSIT_AMOUNT, 9, 6, NOW)
+            .unwrap();
+        assert_eq!(pay, 303_505_000);
+    }
+
+    #[test]
+    fn test_oracle_price_rounds_up() {
+        let escrow = Escrow {
+            spread_bps: 0,
+            ..offer()
+        };
+        //one base unit of X is worth a fraction of a base unit of Y
+        let pay = escrow
+            .oracle_receive_amount(&feed(NOW), 1, 9, 6, NOW)
+            .unwrap();
+        assert_eq!(pay, 1);
+    }
+
+    #[test]
+    fn test_oracle_price_limits() {
+        let stale =
+            offer().oracle_receive_amount(&feed(NOW - MAX_AGE - 1), DEPOSIT_AMOUNT, 9, 6, NOW);
+        assert_eq!(stale.unwrap_err(), ErrorCode::StalePrice.into());
+
+        let uncertain = PriceFeed {
+            conf: 100,
+            ..feed(NOW)
+        };
+        let uncertain = offer().oracle_receive_amount(&uncertain, DEPOSIT_AMOUNT, 9, 6, NOW);
+        assert_eq!(uncertain.unwrap_err(), ErrorCode::PriceTooUncertain.into());
+
+        let negative = PriceFeed {
+            price: -1,
+            ..feed(NOW)
+        };
+        let negative = offer().oracle_receive_amount(&negative, DEPOSIT_AMOUNT, 9, 6, NOW);
+        assert_eq!(negative.unwrap_err(), ErrorCode::InvalidPriceFeed.into());
+    }
+
+    #[test]
+    fn test_feed_layout_matches_pyth_receiver() {
+        let loaded = load(&feed_account(&Mollusk::default(), &feed(NOW))).unwrap();
+        assert_eq!(loaded.price, 15_025);
+        assert_eq!(loaded.conf, 5);
+        assert_eq!(loaded.expo, -2);
+        assert_eq!(loaded.publish_time, NOW);
+    }
+
+    #[test]
+    fn test_feed_rejects_partially_verified_update() {
+        let mut account = feed_account(&Mollusk::default(), &feed(NOW));
+        //`VerificationLevel::Partial { num_signatures: 5 }` right after the write authority
+        account.data.splice(40..41, [0, 5]);
+
+        assert_eq!(
+            load(&account).unwrap_err(),
+            ErrorCode::InvalidPriceFeed.into()
+        );
+    }
+
+    #[test]
+    fn test_feed_rejects_other_owner() {
+        let mut account = feed_account(&Mollusk::default(), &feed(NOW));
+        account.owner = MAKER;
+
+        assert_eq!(
+            load(&account).unwrap_err(),
+            ErrorCode::InvalidPriceFeed.into()
+        );
+    }
+
+    #[cfg(feature = "mock-oracle")]
+    #[test]
+    fn test_feed_layout_matches_mock_oracle() {
+        let account = mock_price_feed_account(&Mollusk::default(), &feed(NOW), MAKER);
+
+        let loaded = load(&account).unwrap();
+        assert_eq!(loaded.price, 15_025);
+        assert_eq!(loaded.expo, -2);
+        assert_eq!(loaded.publish_time, NOW);
+    }
+
+    #[cfg(not(feature = "mock-oracle"))]
+    #[test]
+    fn test_feed_rejects_mock_oracle() {
+        let account = mock_price_feed_account(&Mollusk::default(), &feed(NOW), MAKER);
+
+        assert_eq!(
+            load(&account).unwrap_err(),
+            ErrorCode::InvalidPriceFeed.into()
+        );
+    }
+
+    #[test]
+    fn test_make_oracle() {
+        let mollusk = setup();
+        let escrow = escrow_pda(&MAKER, SEED).0;
+
+        let accounts: Vec<(Pubkey, Account)> = vec![
+            (
+                MAKER,
+                Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID),
+            ),
+            (MINT_X, mint_account(&mollusk, DEPOSIT_AMOUNT, 9)),
+            (MINT_Y, mint_account(&mollusk, 1_000_000_000, 6)),
+            (
+                get_associated_token_address(&MAKER, &MINT_X),
+                token_account(&mollusk, MINT_X, MAKER, DEPOSIT_AMOUNT),
+            ),
+            (escrow, Account::default()),
+            (
+                get_associated_token_address(&escrow, &MINT_X),
+                Account::default(),
+            ),
+        ]
+        .into_iter()
+        .chain(program_accounts())
+        .collect();
+
+        let result = mollusk.process_and_validate_instruction(
+            &make_oracle_ix(),
+            &accounts,
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.price_curve, PriceCurve::Oracle);
+        assert_eq!(escrow.oracle, FEED);
+        assert_eq!(escrow.spread_bps, SPREAD_BPS);
+    }
+
+    #[test]
+    fn test_exchange_at_oracle_price() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_with_feed_ix(FEED),
+            &exchange_accounts(&mollusk, &feed(NOW - 10)),
+            &[Check::success()],
+        );
+
+        let maker_y = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_y), 303_505_000);
+    }
+
+    #[test]
+    fn test_exchange_stale_price() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        mollusk.process_and_validate_instruction(
+            &exchange_with_feed_ix(FEED),
+            &exchange_accounts(&mollusk, &feed(NOW - MAX_AGE - 1)),
+            &[Check::err(program_error(ErrorCode::StalePrice))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_without_feed() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &exchange_accounts(&mollusk, &feed(NOW)),
+            &[Check::err(program_error(ErrorCode::InvalidPriceFeed))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_other_feed() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let other = Pubkey::new_unique();
+        let mut accounts = exchange_accounts(&mollusk, &feed(NOW));
+        accounts.push((other, feed_account(&mollusk, &feed(NOW))));
+
+        mollusk.process_and_validate_instruction(
+            &exchange_with_feed_ix(other),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::InvalidPriceFeed))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_feed_of_other_program() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        //laid out like a feed, but owned by a program the maker may control
+        let mut accounts = exchange_accounts(&mollusk, &feed(NOW));
+        for (key, account) in accounts.iter_mut() {
+            if *key == FEED {
+                account.owner = Pubkey::new_unique();
+            }
+        }
+
+        mollusk.process_and_validate_instruction(
+            &exchange_with_feed_ix(FEED),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::InvalidPriceFeed))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_at_max_pay() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let mut instruction = exchange_with_feed_ix(FEED);
+        instruction.data = escrow::instruction::ExchangeWithMaxPay {
+            max_pay: 303_505_000,
+        }
+        .data();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &exchange_accounts(&mollusk, &feed(NOW - 10)),
+            &[Check::success()],
+        );
+    }
+
+    #[test]
+    fn test_exchange_above_max_pay() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let mut instruction = exchange_with_feed_ix(FEED);
+        instruction.data = escrow::instruction::ExchangeWithMaxPay {
+            max_pay: 303_504_999,
+        }
+        .data();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &exchange_accounts(&mollusk, &feed(NOW - 10)),
+            &[Check::err(program_error(ErrorCode::MaxPayExceeded))],
+        );
+    }
+
+    //The mock oracle moves the price, the exchange in the same chain pays the new one.
+    //Needs escrow.so built with `--features mock-oracle`.
+    #[cfg(feature = "mock-oracle")]
+    #[test]
+    fn test_exchange_after_price_update() {
+        const FEED_AUTHORITY: Pubkey = Pubkey::new_from_array([0x07; 32]);
+        let mut mollusk = setup();
+        mollusk.add_program(
+            &mock_oracle::ID,
+            "../../target/deploy/mock_oracle",
+            &mollusk_svm::program::loader_keys::LOADER_V3,
+        );
+        mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let set_price = Instruction::new_with_bytes(
+            mock_oracle::ID,
+            &mock_oracle::instruction::SetPrice {
+                price: 20_000,
+                conf: 5,
+                expo: -2,
+            }
+            .data(),
+            vec![
+                AccountMeta::new_readonly(FEED_AUTHORITY, true),
+                AccountMeta::new(FEED, false),
+            ],
+        );
+
+        let mut accounts = exchange_accounts(&mollusk, &feed(NOW - MAX_AGE - 1));
+        accounts.iter_mut().find(|(key, _)| *key == FEED).unwrap().1 =
+            mock_price_feed_account(&mollusk, &feed(NOW - MAX_AGE - 1), FEED_AUTHORITY);
+        accounts.push((FEED_AUTHORITY, Account::default()));
+        accounts.push((
+            mock_oracle::ID,
+            mollusk_svm::program::create_program_account_loader_v3(&mock_oracle::ID),
+        ));
+
+        let result =
+            mollusk.process_instruction_chain(&[set_price, exchange_with_feed_ix(FEED)], &accounts);
+        assert!(!result.program_result.is_err());
+
+        //2 X at 200 Y plus 1%
+        let maker_y = result
+            .resulting_accounts
+            .iter()
+            .find(|(key, _)| *key == get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(&maker_y.1), 404_000_000);
+    }
+}
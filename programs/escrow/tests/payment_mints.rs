@@ -0,0 +1,312 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, Leg, PaymentOptions},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 5_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    //USDC, then USDT and SOL as alternatives
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const ACCEPTED: [Leg; 2] = [
+        Leg {
+            mint: Pubkey::new_from_array([0x05; 32]),
+            amount: 10_010,
+        },
+        Leg {
+            mint: Pubkey::new_from_array([0x06; 32]),
+            amount: 70,
+        },
+    ];
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn payments_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"payments", escrow().as_ref()], &ID)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn options() -> PaymentOptions {
+        PaymentOptions {
+            escrow: escrow(),
+            accepted: ACCEPTED.to_vec(),
+            bump: payments_pda().1,
+        }
+    }
+
+    fn set_payment_mints_ix(accepted: &[Leg]) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SetPaymentMints {
+                accepted: accepted.to_vec(),
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(payments_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //Pays with `mint`, the price feed is left out by passing the program id
+    fn exchange_with_ix(mint: Pubkey) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, mint, SEED);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(ID, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new(payments_pda().0, false));
+        instruction
+    }
+
+    fn accounts(mollusk: &Mollusk, accepts_other_mints: bool) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            accepts_other_mints,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+        ];
+        let mints = [MINT_Y, ACCEPTED[0].mint, ACCEPTED[1].mint];
+        for mint in mints {
+            accounts.push((mint, mint_account(mollusk, 100_000, 6)));
+            accounts.push((
+                get_associated_token_address(&TAKER, &mint),
+                token_account(mollusk, mint, TAKER, 100_000),
+            ));
+            accounts.push((
+                get_associated_token_address(&MAKER, &mint),
+                Account::default(),
+            ));
+        }
+        accounts.push((
+            payments_pda().0,
+            if accepts_other_mints {
+                state_account(mollusk, &options(), PaymentOptions::LEN)
+            } else {
+                Account::default()
+            },
+        ));
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_amount_for() {
+        let options = options();
+        assert_eq!(options.amount_for(&ACCEPTED[1].mint), Some(70));
+        assert_eq!(options.amount_for(&MINT_Y), None);
+    }
+
+    #[test]
+    fn test_set_payment_mints() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &set_payment_mints_ix(&ACCEPTED),
+            &accounts(&mollusk, false),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert!(escrow.accepts_other_mints);
+
+        let options = result.get_account(&payments_pda().0).unwrap();
+        let options = PaymentOptions::try_deserialize(&mut options.data.as_slice()).unwrap();
+        assert_eq!(options.accepted, ACCEPTED.to_vec());
+    }
+
+    #[test]
+    fn test_set_payment_mints_rejects_token_mint_b() {
+        let mollusk = setup();
+
+        let accepted = [
+            ACCEPTED[0],
+            Leg {
+                mint: MINT_Y,
+                amount: 1,
+            },
+        ];
+        mollusk.process_and_validate_instruction(
+            &set_payment_mints_ix(&accepted),
+            &accounts(&mollusk, false),
+            &[Check::err(program_error(ErrorCode::InvalidPaymentMints))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_other_mint() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_with_ix(ACCEPTED[1].mint),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&payments_pda().0).closed().build(),
+            ],
+        );
+
+        let maker = result
+            .get_account(&get_associated_token_address(&MAKER, &ACCEPTED[1].mint))
+            .unwrap();
+        assert_eq!(amount(maker), ACCEPTED[1].amount);
+
+        let taker_x = result
+            .get_account(&get_associated_token_address(&TAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(taker_x), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_exchange_with_token_mint_b() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_with_ix(MINT_Y),
+            &accounts(&mollusk, true),
+            &[
+                Check::success(),
+                Check::account(&payments_pda().0).closed().build(),
+            ],
+        );
+
+        let maker = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker), RECEIVE);
+    }
+
+    #[test]
+    fn test_exchange_without_payment_options() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::PaymentOptionsMissing))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_other_mint_above_max_pay() {
+        let mollusk = setup();
+
+        //the maker raised the price of the alternate mint after the taker quoted 60
+        let mut instruction = exchange_with_ix(ACCEPTED[1].mint);
+        instruction.data = escrow::instruction::ExchangeWithMaxPay { max_pay: 60 }.data();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::MaxPayExceeded))],
+        );
+    }
+
+    #[test]
+    fn test_refund_closes_payment_options() {
+        let mollusk = setup();
+
+        let mut accounts = accounts(&mollusk, true);
+        accounts.push((
+            get_associated_token_address(&MAKER, &MINT_X),
+            token_account(&mollusk, MINT_X, MAKER, 0),
+        ));
+
+        mollusk.process_and_validate_instruction(
+            &refund_ix(MAKER, MAKER, MINT_X, SEED),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::PaymentOptionsMissing))],
+        );
+
+        let mut instruction = refund_ix(MAKER, MAKER, MINT_X, SEED);
+        instruction
+            .accounts
+            .push(AccountMeta::new(payments_pda().0, false));
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts,
+            &[
+                Check::success(),
+                Check::account(&payments_pda().0).closed().build(),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_unlisted_mint() {
+        let mollusk = setup();
+
+        let mut accounts = accounts(&mollusk, false);
+        accounts.retain(|(key, _)| *key != payments_pda().0);
+        accounts.push((
+            payments_pda().0,
+            state_account(&mollusk, &options(), PaymentOptions::LEN),
+        ));
+
+        //the options account is stale, the escrow doesn't accept other mints
+        mollusk.process_and_validate_instruction(
+            &exchange_with_ix(ACCEPTED[0].mint),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::PaymentMintNotAccepted))],
+        );
+    }
+
+    #[test]
+    fn test_batch_exchange_rejects_payment_options() {
+        let mollusk = setup();
+
+        let instruction = batch_exchange_ix(TAKER, MINT_X, MINT_Y, &[(MAKER, SEED)], RECEIVE, 0);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, true),
+            &[Check::err(program_error(ErrorCode::HasPaymentOptions))],
+        );
+    }
+}
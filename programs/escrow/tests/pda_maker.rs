@@ -0,0 +1,141 @@
+//Makers that are PDAs of other programs, such as a multisig vault, sign `make`
+//through CPI. Their escrows are set up directly here.
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account, native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const MULTISIG_PROGRAM: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const STRANGER: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    //the treasury vault of a multisig, off the curve and owned by its program
+    fn vault_maker() -> Pubkey {
+        Pubkey::find_program_address(&[b"vault", &[0]], &MULTISIG_PROGRAM).0
+    }
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&vault_maker(), SEED).0
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn accounts(mollusk: &Mollusk, maker_token_account_b: Account) -> Vec<(Pubkey, Account)> {
+        let maker = vault_maker();
+        let escrow_state = escrow_state(maker, SEED, MINT_X, MINT_Y, RECEIVE);
+        let mut accounts = vec![
+            (
+                maker,
+                Account {
+                    lamports: LAMPORTS_PER_SOL,
+                    data: vec![0; 64],
+                    owner: MULTISIG_PROGRAM,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            ),
+            (TAKER, user()),
+            (STRANGER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&maker, &MINT_Y),
+                maker_token_account_b,
+            ),
+            (
+                get_associated_token_address(&STRANGER, &MINT_Y),
+                Account::default(),
+            ),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_exchange_with_pda_maker() {
+        let mollusk = setup();
+        let maker_y = get_associated_token_address(&vault_maker(), &MINT_Y);
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, vault_maker(), MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, Account::default()),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                //the vault itself is left as its program owns it
+                Check::account(&vault_maker())
+                    .owner(&MULTISIG_PROGRAM)
+                    .lamports(LAMPORTS_PER_SOL)
+                    .build(),
+            ],
+        );
+
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), RECEIVE);
+    }
+
+    #[test]
+    fn test_exchange_with_pda_maker_account() {
+        let mollusk = setup();
+        let maker_y = get_associated_token_address(&vault_maker(), &MINT_Y);
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, vault_maker(), MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, token_account(&mollusk, MINT_Y, vault_maker(), 5)),
+            &[Check::success()],
+        );
+
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), RECEIVE + 5);
+    }
+
+    #[test]
+    fn test_exchange_with_other_maker() {
+        let mollusk = setup();
+
+        //STRANGER's accounts with the vault's escrow
+        let mut instruction = exchange_ix(TAKER, STRANGER, MINT_X, MINT_Y, SEED);
+        instruction.accounts[7].pubkey = escrow();
+        instruction.accounts[8].pubkey = get_associated_token_address(&escrow(), &MINT_X);
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, Account::default()),
+            &[Check::err(program_error(
+                anchor_lang::error::ErrorCode::ConstraintAddress,
+            ))],
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//The Token Metadata program is loaded from a local dump, which isn't checked in, so the
+//tests running it are ignored by default. Fetch it from programs/escrow and run them with
+//`cargo test --test pnft -- --ignored`:
+//solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s tests/elf/token_metadata.so
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, AnchorSerialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{
+            edition_address, token_record_address, Escrow, EscrowKind, Metadata, TransferPnft,
+            PROGRAMMABLE_NON_FUNGIBLE, TOKEN_METADATA_PROGRAM,
+        },
+    };
+    use mollusk_svm::{program, result::Check, Mollusk};
+    use solana_sdk::{
+        account::{Account, WritableAccount},
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar::instructions::{
+            construct_instructions_data, BorrowedAccountMeta, BorrowedInstruction,
+        },
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::AccountState;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const PRICE: u64 = 10_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const NFT: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const SYSVAR_INSTRUCTIONS: Pubkey = solana_sdk::sysvar::instructions::ID;
+
+    fn setup_pnft() -> Mollusk {
+        let mut mollusk = setup();
+        mollusk.add_program(
+            &TOKEN_METADATA_PROGRAM,
+            "tests/elf/token_metadata",
+            &mollusk_svm::program::loader_keys::LOADER_V3,
+        );
+        mollusk
+    }
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn vault() -> Pubkey {
+        get_associated_token_address(&escrow(), &NFT)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn metadata(token_standard: u8) -> Metadata {
+        Metadata {
+            key: Metadata::KEY,
+            update_authority: MAKER,
+            mint: NFT,
+            name: "Deal #1".to_string(),
+            symbol: "OTC".to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: true,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(token_standard),
+            collection: None,
+        }
+    }
+
+    //Followed by no uses, no collection details and a `ProgrammableConfig::V1` without a rule set
+    fn metadata_account(metadata: &Metadata) -> Account {
+        let mut data = metadata.try_to_vec().unwrap();
+        data.extend_from_slice(&[0, 0, 1, 0, 0]);
+        data.resize(679, 0);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: TOKEN_METADATA_PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    //`MasterEditionV2` with a supply of 1 and no prints
+    fn edition_account() -> Account {
+        let mut data = vec![6];
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.resize(282, 0);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: TOKEN_METADATA_PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    //An unlocked `TokenRecord` without delegate
+    fn token_record_account(token: &Pubkey) -> Account {
+        let bump = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                TOKEN_METADATA_PROGRAM.as_ref(),
+                NFT.as_ref(),
+                b"token_record",
+                token.as_ref(),
+            ],
+            &TOKEN_METADATA_PROGRAM,
+        )
+        .1;
+        let mut data = vec![14, bump, 0, 0, 0, 0, 0];
+        data.resize(80, 0);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: TOKEN_METADATA_PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    //The master edition mints and freezes the pNFT
+    fn nft_mint(mollusk: &Mollusk) -> Account {
+        let mut account = mint_account(mollusk, 1, 0);
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                mint_authority: COption::Some(edition_address(&NFT)),
+                supply: 1,
+                decimals: 0,
+                is_initialized: true,
+                freeze_authority: COption::Some(edition_address(&NFT)),
+            },
+            account.data_as_mut_slice(),
+        )
+        .unwrap();
+        account
+    }
+
+    //pNFTs always sit frozen in their token account
+    fn frozen_nft_account(mollusk: &Mollusk, owner: Pubkey) -> Account {
+        let mut account = token_account(mollusk, NFT, owner, 1);
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.state = AccountState::Frozen;
+        spl_token::state::Account::pack(state, account.data_as_mut_slice()).unwrap();
+        account
+    }
+
+    fn instructions_sysvar(instruction: &Instruction) -> Account {
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| BorrowedAccountMeta {
+                pubkey: &meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect();
+        let data = construct_instructions_data(&[BorrowedInstruction {
+            program_id: &instruction.program_id,
+            accounts,
+            data: &instruction.data,
+        }]);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: solana_sdk::sysvar::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    //The pNFT accounts every instruction passes, moving it from `from` to `to`
+    fn pnft_metas(from: Pubkey, to: Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(Metadata::address(&NFT), false),
+            AccountMeta::new_readonly(edition_address(&NFT), false),
+            AccountMeta::new(token_record_address(&NFT, &from), false),
+            AccountMeta::new(token_record_address(&NFT, &to), false),
+            AccountMeta::new_readonly(SYSVAR_INSTRUCTIONS, false),
+            AccountMeta::new_readonly(TOKEN_METADATA_PROGRAM, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ]
+    }
+
+    fn make_pnft_ix() -> Instruction {
+        let maker_nft = get_associated_token_address(&MAKER, &NFT);
+        let mut accounts = vec![
+            AccountMeta::new(MAKER, true),
+            AccountMeta::new_readonly(NFT, false),
+            AccountMeta::new_readonly(MINT_Y, false),
+            AccountMeta::new(maker_nft, false),
+            AccountMeta::new(escrow(), false),
+            AccountMeta::new(vault(), false),
+        ];
+        accounts.extend(pnft_metas(maker_nft, vault()));
+        accounts.push(AccountMeta::new_readonly(mint_policy_pda().0, false));
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::MakePnft {
+                seed: SEED,
+                receive: PRICE,
+            }
+            .data(),
+            accounts,
+        )
+    }
+
+    fn exchange_pnft_ix() -> Instruction {
+        let taker_nft = get_associated_token_address(&TAKER, &NFT);
+        let mut accounts = vec![
+            AccountMeta::new(TAKER, true),
+            AccountMeta::new(MAKER, false),
+            AccountMeta::new_readonly(NFT, false),
+            AccountMeta::new_readonly(MINT_Y, false),
+            AccountMeta::new(taker_nft, false),
+            AccountMeta::new(get_associated_token_address(&TAKER, &MINT_Y), false),
+            AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+            AccountMeta::new(escrow(), false),
+            AccountMeta::new(vault(), false),
+        ];
+        accounts.extend(pnft_metas(vault(), taker_nft));
+        Instruction::new_with_bytes(ID, &escrow::instruction::ExchangePnft {}.data(), accounts)
+    }
+
+    fn refund_pnft_ix() -> Instruction {
+        let maker_nft = get_associated_token_address(&MAKER, &NFT);
+        let mut accounts = vec![
+            AccountMeta::new(MAKER, true),
+            AccountMeta::new_readonly(NFT, false),
+            AccountMeta::new(maker_nft, false),
+            AccountMeta::new(escrow(), false),
+            AccountMeta::new(vault(), false),
+        ];
+        accounts.extend(pnft_metas(vault(), maker_nft));
+        Instruction::new_with_bytes(ID, &escrow::instruction::RefundPnft {}.data(), accounts)
+    }
+
+    fn pnft_escrow() -> Escrow {
+        Escrow {
+            kind: EscrowKind::ProgrammableNft,
+            ..escrow_state(MAKER, SEED, NFT, MINT_Y, PRICE)
+        }
+    }
+
+    //The pNFT with the maker, or in the vault of an open escrow when `open`
+    fn accounts(
+        mollusk: &Mollusk,
+        instruction: &Instruction,
+        token_standard: u8,
+        open: bool,
+    ) -> Vec<(Pubkey, Account)> {
+        let holder = if open { escrow() } else { MAKER };
+        let holding = get_associated_token_address(&holder, &NFT);
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (NFT, nft_mint(mollusk)),
+            (MINT_Y, mint_account(mollusk, PRICE, 6)),
+            (holding, frozen_nft_account(mollusk, holder)),
+            (
+                token_record_address(&NFT, &holding),
+                token_record_account(&holding),
+            ),
+            (
+                Metadata::address(&NFT),
+                metadata_account(&metadata(token_standard)),
+            ),
+            (edition_address(&NFT), edition_account()),
+            (SYSVAR_INSTRUCTIONS, instructions_sysvar(instruction)),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, PRICE),
+            ),
+            (
+                escrow(),
+                if open {
+                    escrow_account(mollusk, &pnft_escrow())
+                } else {
+                    Account::default()
+                },
+            ),
+            (
+                TOKEN_METADATA_PROGRAM,
+                program::create_program_account_loader_v3(&TOKEN_METADATA_PROGRAM),
+            ),
+        ];
+        for meta in &instruction.accounts {
+            if !accounts.iter().any(|(key, _)| *key == meta.pubkey) && !is_program(&meta.pubkey) {
+                accounts.push((meta.pubkey, Account::default()));
+            }
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    #[test]
+    fn test_transfer_data() {
+        let data = TransferPnft::data(1);
+        assert_eq!(data, [49, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_is_programmable() {
+        assert!(metadata(PROGRAMMABLE_NON_FUNGIBLE).is_programmable());
+        assert!(!metadata(0).is_programmable());
+        let legacy = Metadata {
+            token_standard: None,
+            ..metadata(0)
+        };
+        assert!(!legacy.is_programmable());
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_make_pnft() {
+        let mollusk = setup_pnft();
+        let instruction = make_pnft_ix();
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, false),
+            &[Check::success()],
+        );
+
+        assert_eq!(amount(result.get_account(&vault()).unwrap()), 1);
+        let state = result.get_account(&escrow()).unwrap();
+        let state = Escrow::try_deserialize(&mut state.data.as_slice()).unwrap();
+        assert_eq!(state.kind, EscrowKind::ProgrammableNft);
+        assert_eq!(state.receive_amount, PRICE);
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_make_pnft_rejects_plain_nft() {
+        let mollusk = setup_pnft();
+        let instruction = make_pnft_ix();
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, &instruction, 0, false),
+            &[Check::err(program_error(ErrorCode::NotAProgrammableNft))],
+        );
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_make_pnft_rejects_token_2022_mint_b() {
+        let mollusk = setup_pnft();
+        let instruction = make_pnft_ix();
+        let mut accounts = accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, false);
+        let (_, mint_y) = accounts.iter_mut().find(|(key, _)| *key == MINT_Y).unwrap();
+        mint_y.owner = anchor_spl::token_2022::ID;
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts,
+            &[Check::err(program_error(
+                anchor_lang::error::ErrorCode::ConstraintMintTokenProgram,
+            ))],
+        );
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_exchange_pnft() {
+        let mollusk = setup_pnft();
+        let instruction = exchange_pnft_ix();
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, true),
+            &[Check::success()],
+        );
+
+        let taker_nft = get_associated_token_address(&TAKER, &NFT);
+        assert_eq!(amount(result.get_account(&taker_nft).unwrap()), 1);
+        let maker_y = get_associated_token_address(&MAKER, &MINT_Y);
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), PRICE);
+        assert_eq!(result.get_account(&escrow()).unwrap().lamports, 0);
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_exchange_pnft_with_pda_maker() {
+        let mollusk = setup_pnft();
+        let instruction = exchange_pnft_ix();
+
+        //the maker is owned by a program, like a multisig vault
+        let mut accounts = accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, true);
+        for (key, account) in accounts.iter_mut() {
+            if *key == MAKER {
+                account.owner = Pubkey::new_from_array([0x09; 32]);
+                account.data = vec![0; 64];
+            }
+        }
+
+        let result =
+            mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+
+        let maker_y = get_associated_token_address(&MAKER, &MINT_Y);
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), PRICE);
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_exchange_rejects_pnft_escrow() {
+        let mollusk = setup_pnft();
+        let instruction = exchange_ix(TAKER, MAKER, NFT, MINT_Y, SEED);
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, true),
+            &[Check::err(program_error(ErrorCode::NotAnOffer))],
+        );
+    }
+
+    #[test]
+    #[ignore = "needs tests/elf/token_metadata.so"]
+    fn test_refund_pnft() {
+        let mollusk = setup_pnft();
+        let instruction = refund_pnft_ix();
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts(&mollusk, &instruction, PROGRAMMABLE_NON_FUNGIBLE, true),
+            &[Check::success()],
+        );
+
+        let maker_nft = get_associated_token_address(&MAKER, &NFT);
+        assert_eq!(amount(result.get_account(&maker_nft).unwrap()), 1);
+        assert_eq!(result.get_account(&escrow()).unwrap().lamports, 0);
+    }
+}
@@ -0,0 +1,358 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Escrow, Recipient, Recipients},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_001;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    //a team split with the DAO treasury
+    const TEAM: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const TREASURY: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn recipients_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"recipients", escrow().as_ref()], &ID)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    //70% to the team, 30% to the treasury
+    fn team_split() -> Vec<Recipient> {
+        vec![
+            Recipient {
+                wallet: TEAM,
+                share_bps: 7_000,
+            },
+            Recipient {
+                wallet: TREASURY,
+                share_bps: 3_000,
+            },
+        ]
+    }
+
+    fn recipients(recipients: Vec<Recipient>) -> Recipients {
+        Recipients {
+            escrow: escrow(),
+            recipients,
+            bump: recipients_pda().1,
+        }
+    }
+
+    fn set_recipients_ix(recipients: Vec<Recipient>) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SetRecipients { recipients }.data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(recipients_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //The optional accounts before the recipients are left out by passing the program id,
+    //the wallets in `paid` and their ATAs of mint Y are passed in the remaining accounts
+    fn split_exchange_ix(paid: &[Pubkey]) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+        for _ in 0..7 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new(recipients_pda().0, false));
+        for wallet in paid {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(*wallet, false));
+            instruction.accounts.push(AccountMeta::new(
+                get_associated_token_address(wallet, &MINT_Y),
+                false,
+            ));
+        }
+        instruction
+    }
+
+    //An offer of X for RECEIVE of Y, split by `split` if any
+    fn accounts(mollusk: &Mollusk, split: Option<Recipients>) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            split: split.is_some(),
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (TEAM, user()),
+            (TREASURY, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TEAM, &MINT_Y),
+                token_account(mollusk, MINT_Y, TEAM, 0),
+            ),
+            (
+                get_associated_token_address(&TREASURY, &MINT_Y),
+                token_account(mollusk, MINT_Y, TREASURY, 0),
+            ),
+            (
+                recipients_pda().0,
+                match split {
+                    Some(split) => state_account(mollusk, &split, Recipients::LEN),
+                    None => Account::default(),
+                },
+            ),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_split_dust_goes_to_first() {
+        let split = recipients(team_split());
+        assert_eq!(split.split(10_000), vec![(TEAM, 7_000), (TREASURY, 3_000)]);
+        //30% of 10_001 rounds down, its dust goes to the team
+        assert_eq!(split.split(RECEIVE), vec![(TEAM, 7_001), (TREASURY, 3_000)]);
+        assert_eq!(split.split(1), vec![(TEAM, 1), (TREASURY, 0)]);
+    }
+
+    #[test]
+    fn test_set_recipients() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &set_recipients_ix(team_split()),
+            &accounts(&mollusk, None),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert!(escrow.split);
+
+        let split = result.get_account(&recipients_pda().0).unwrap();
+        let split = Recipients::try_deserialize(&mut split.data.as_slice()).unwrap();
+        assert_eq!(split.recipients, team_split());
+    }
+
+    #[test]
+    fn test_set_recipients_with_destination() {
+        let mollusk = setup();
+
+        let destination = Escrow {
+            destination: get_associated_token_address(&MAKER, &MINT_Y),
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let mut accounts = accounts(&mollusk, None);
+        accounts
+            .iter_mut()
+            .find(|(key, _)| *key == escrow())
+            .unwrap()
+            .1 = escrow_account(&mollusk, &destination);
+
+        mollusk.process_and_validate_instruction(
+            &set_recipients_ix(team_split()),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::SplitWithDestination))],
+        );
+    }
+
+    #[test]
+    fn test_set_invalid_recipients() {
+        let mollusk = setup();
+
+        let short = vec![Recipient {
+            wallet: TEAM,
+            share_bps: 9_999,
+        }];
+        let duplicated = vec![
+            Recipient {
+                wallet: TEAM,
+                share_bps: 5_000,
+            },
+            Recipient {
+                wallet: TEAM,
+                share_bps: 5_000,
+            },
+        ];
+        let empty_share = vec![
+            Recipient {
+                wallet: TEAM,
+                share_bps: 10_000,
+            },
+            Recipient {
+                wallet: TREASURY,
+                share_bps: 0,
+            },
+        ];
+
+        for invalid in [vec![], short, duplicated, empty_share] {
+            mollusk.process_and_validate_instruction(
+                &set_recipients_ix(invalid),
+                &accounts(&mollusk, None),
+                &[Check::err(program_error(ErrorCode::InvalidRecipients))],
+            );
+        }
+    }
+
+    #[test]
+    fn test_exchange_splits_proceeds() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &split_exchange_ix(&[TREASURY, TEAM]),
+            &accounts(&mollusk, Some(recipients(team_split()))),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&recipients_pda().0).closed().build(),
+            ],
+        );
+
+        let balance = |owner: &Pubkey| {
+            amount(
+                result
+                    .get_account(&get_associated_token_address(owner, &MINT_Y))
+                    .unwrap(),
+            )
+        };
+        assert_eq!(balance(&TEAM), 7_001);
+        assert_eq!(balance(&TREASURY), 3_000);
+        assert_eq!(balance(&TAKER), 0);
+    }
+
+    #[test]
+    fn test_exchange_creates_recipient_account() {
+        let mollusk = setup();
+
+        let treasury_y = get_associated_token_address(&TREASURY, &MINT_Y);
+        let mut accounts = accounts(&mollusk, Some(recipients(team_split())));
+        accounts
+            .iter_mut()
+            .find(|(key, _)| *key == treasury_y)
+            .unwrap()
+            .1 = Account::default();
+
+        let result = mollusk.process_and_validate_instruction(
+            &split_exchange_ix(&[TREASURY, TEAM]),
+            &accounts,
+            &[Check::success()],
+        );
+
+        assert_eq!(amount(result.get_account(&treasury_y).unwrap()), 3_000);
+    }
+
+    #[test]
+    fn test_exchange_without_recipients() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED),
+            &accounts(&mollusk, Some(recipients(team_split()))),
+            &[Check::err(program_error(ErrorCode::RecipientsMissing))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_missing_recipient_account() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &split_exchange_ix(&[TEAM]),
+            &accounts(&mollusk, Some(recipients(team_split()))),
+            &[Check::err(program_error(ErrorCode::RecipientsMissing))],
+        );
+    }
+
+    #[test]
+    fn test_batch_exchange_rejects_split() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &batch_exchange_ix(TAKER, MINT_X, MINT_Y, &[(MAKER, SEED)], RECEIVE, 1),
+            &accounts(&mollusk, Some(recipients(team_split()))),
+            &[Check::err(program_error(ErrorCode::SplitsProceeds))],
+        );
+    }
+
+    #[test]
+    fn test_refund_closes_recipients() {
+        let mollusk = setup();
+
+        let mut instruction = refund_ix(MAKER, MAKER, MINT_X, SEED);
+        for _ in 0..4 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new(recipients_pda().0, false));
+
+        let mut accounts = accounts(&mollusk, Some(recipients(team_split())));
+        accounts.push((
+            get_associated_token_address(&MAKER, &MINT_X),
+            token_account(&mollusk, MINT_X, MAKER, 0),
+        ));
+
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &accounts,
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&recipients_pda().0).closed().build(),
+            ],
+        );
+    }
+}
@@ -0,0 +1,362 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, AnchorSerialize, InstructionData};
+    use anchor_spl::token_interface::Mint;
+    use escrow::{
+        error::ErrorCode,
+        state::{Creator, Escrow, Metadata, RoyaltyPolicy, TOKEN_METADATA_PROGRAM},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const PRICE: u64 = 10_000;
+    //5% royalties, split 60/40 between the two creators
+    const SELLER_FEE_BPS: u16 = 500;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const NFT: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    const ARTIST: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const STUDIO: Pubkey = Pubkey::new_from_array([0x06; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            key: Metadata::KEY,
+            update_authority: STUDIO,
+            mint: NFT,
+            name: "Piece #1".to_string(),
+            symbol: "ART".to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: SELLER_FEE_BPS,
+            creators: Some(vec![
+                Creator {
+                    address: ARTIST,
+                    verified: true,
+                    share: 60,
+                },
+                Creator {
+                    address: STUDIO,
+                    verified: false,
+                    share: 40,
+                },
+            ]),
+            primary_sale_happened: true,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+        }
+    }
+
+    fn metadata_account(metadata: &Metadata) -> Account {
+        let mut data = metadata.try_to_vec().unwrap();
+        data.resize(679, 0);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: TOKEN_METADATA_PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    //The optional accounts before the metadata are left out by passing the program id,
+    //followed by a [creator, ATA] pair for each of `creators`
+    fn royalty_exchange_ix(creators: &[Pubkey]) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, NFT, MINT_Y, SEED);
+        for _ in 0..6 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(Metadata::address(&NFT), false));
+        for creator in creators {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(*creator, false));
+            instruction.accounts.push(AccountMeta::new(
+                get_associated_token_address(creator, &MINT_Y),
+                false,
+            ));
+        }
+        instruction
+    }
+
+    fn set_royalty_policy_ix(policy: RoyaltyPolicy) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SetRoyaltyPolicy { policy }.data(),
+            vec![
+                AccountMeta::new_readonly(MAKER, true),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new_readonly(Metadata::address(&NFT), false),
+            ],
+        )
+    }
+
+    //An NFT offered for PRICE of Y, with the artist's ATA of Y missing
+    fn accounts(mollusk: &Mollusk, policy: RoyaltyPolicy) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            royalty_policy: policy,
+            ..escrow_state(MAKER, SEED, NFT, MINT_Y, PRICE)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (ARTIST, user()),
+            (STUDIO, user()),
+            (NFT, mint_account(mollusk, 1, 0)),
+            (MINT_Y, mint_account(mollusk, PRICE, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &NFT),
+                token_account(mollusk, NFT, escrow(), 1),
+            ),
+            (
+                get_associated_token_address(&TAKER, &NFT),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, PRICE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&ARTIST, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&STUDIO, &MINT_Y),
+                token_account(mollusk, MINT_Y, STUDIO, 0),
+            ),
+            (Metadata::address(&NFT), metadata_account(&metadata())),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_royalties_split_by_share() {
+        assert_eq!(
+            metadata().royalties(PRICE),
+            vec![(ARTIST, 300), (STUDIO, 200)]
+        );
+        //rounded down, the dust stays with the maker
+        assert_eq!(metadata().royalties(99), vec![(ARTIST, 2), (STUDIO, 1)]);
+
+        let no_creators = Metadata {
+            creators: None,
+            ..metadata()
+        };
+        assert!(no_creators.royalties(PRICE).is_empty());
+    }
+
+    #[test]
+    fn test_is_sole_creator() {
+        //STUDIO still has a share
+        assert!(!metadata().is_sole_creator(&ARTIST));
+        assert!(!metadata().is_sole_creator(&STUDIO));
+        assert!(!metadata().is_sole_creator(&MAKER));
+
+        let solo = |verified: bool| Metadata {
+            creators: Some(vec![
+                Creator {
+                    address: ARTIST,
+                    verified,
+                    share: 100,
+                },
+                Creator {
+                    address: STUDIO,
+                    verified: true,
+                    share: 0,
+                },
+            ]),
+            ..metadata()
+        };
+        assert!(solo(true).is_sole_creator(&ARTIST));
+        assert!(!solo(false).is_sole_creator(&ARTIST));
+        //the update authority isn't owed anything
+        assert!(!solo(true).is_sole_creator(&STUDIO));
+
+        let no_creators = Metadata {
+            creators: None,
+            ..metadata()
+        };
+        assert!(!no_creators.is_sole_creator(&STUDIO));
+    }
+
+    #[test]
+    fn test_owes_royalties() {
+        let mint = |supply: u64, decimals: u8| {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                supply,
+                decimals,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            Mint::try_deserialize(&mut data.as_slice()).unwrap()
+        };
+        let nft = mint(1, 0);
+        let fungible = mint(PRICE, 6);
+        let offer = escrow_state(MAKER, SEED, NFT, MINT_Y, PRICE);
+        let waived = Escrow {
+            royalty_policy: RoyaltyPolicy::Waived,
+            ..offer.clone()
+        };
+
+        assert!(offer.owes_royalties(&nft));
+        assert!(!offer.owes_royalties(&fungible));
+        assert!(!waived.owes_royalties(&nft));
+    }
+
+    #[test]
+    fn test_exchange_pays_royalties() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &royalty_exchange_ix(&[ARTIST, STUDIO]),
+            &accounts(&mollusk, RoyaltyPolicy::Enforced),
+            &[Check::success()],
+        );
+
+        let balance = |owner: &Pubkey| {
+            amount(
+                result
+                    .get_account(&get_associated_token_address(owner, &MINT_Y))
+                    .unwrap(),
+            )
+        };
+        assert_eq!(balance(&ARTIST), 300);
+        assert_eq!(balance(&STUDIO), 200);
+        assert_eq!(balance(&MAKER), PRICE - 500);
+        assert_eq!(balance(&TAKER), 0);
+    }
+
+    #[test]
+    fn test_exchange_without_metadata() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, NFT, MINT_Y, SEED),
+            &accounts(&mollusk, RoyaltyPolicy::Enforced),
+            &[Check::err(program_error(ErrorCode::RoyaltiesOwed))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_missing_creator() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &royalty_exchange_ix(&[ARTIST]),
+            &accounts(&mollusk, RoyaltyPolicy::Enforced),
+            &[Check::err(program_error(ErrorCode::RoyaltiesOwed))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_waived_royalties() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &exchange_ix(TAKER, MAKER, NFT, MINT_Y, SEED),
+            &accounts(&mollusk, RoyaltyPolicy::Waived),
+            &[Check::success()],
+        );
+
+        let maker_y = get_associated_token_address(&MAKER, &MINT_Y);
+        assert_eq!(amount(result.get_account(&maker_y).unwrap()), PRICE);
+    }
+
+    #[test]
+    fn test_batch_exchange_rejects_royalties() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &batch_exchange_ix(TAKER, NFT, MINT_Y, &[(MAKER, SEED)], PRICE, 1),
+            &accounts(&mollusk, RoyaltyPolicy::Enforced),
+            &[Check::err(program_error(ErrorCode::RoyaltiesOwed))],
+        );
+    }
+
+    #[test]
+    fn test_only_creators_waive_royalties() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &set_royalty_policy_ix(RoyaltyPolicy::Waived),
+            &accounts(&mollusk, RoyaltyPolicy::Enforced),
+            &[Check::err(program_error(ErrorCode::NotACreator))],
+        );
+
+        //the update authority of a piece other creators have a share of
+        let mut shared_accounts = accounts(&mollusk, RoyaltyPolicy::Enforced);
+        let shared = Metadata {
+            update_authority: MAKER,
+            ..metadata()
+        };
+        shared_accounts.retain(|(key, _)| *key != Metadata::address(&NFT));
+        shared_accounts.push((Metadata::address(&NFT), metadata_account(&shared)));
+
+        mollusk.process_and_validate_instruction(
+            &set_royalty_policy_ix(RoyaltyPolicy::Waived),
+            &shared_accounts,
+            &[Check::err(program_error(ErrorCode::NotACreator))],
+        );
+
+        //the artist selling their own piece
+        let mut accounts = accounts(&mollusk, RoyaltyPolicy::Enforced);
+        let own = Metadata {
+            creators: Some(vec![Creator {
+                address: MAKER,
+                verified: true,
+                share: 100,
+            }]),
+            ..metadata()
+        };
+        accounts.retain(|(key, _)| *key != Metadata::address(&NFT));
+        accounts.push((Metadata::address(&NFT), metadata_account(&own)));
+
+        let result = mollusk.process_and_validate_instruction(
+            &set_royalty_policy_ix(RoyaltyPolicy::Waived),
+            &accounts,
+            &[Check::success()],
+        );
+        let state = result.get_account(&escrow()).unwrap();
+        let state = Escrow::try_deserialize(&mut state.data.as_slice()).unwrap();
+        assert_eq!(state.royalty_policy, RoyaltyPolicy::Waived);
+    }
+}
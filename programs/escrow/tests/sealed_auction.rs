@@ -0,0 +1,620 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, InstructionData, Space};
+    use escrow::{
+        error::ErrorCode,
+        state::{Commitment, Escrow, EscrowKind, SealedAuction, SealedPricing},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RESERVE: u64 = 10_000;
+    const COLLATERAL: u64 = 5_000;
+    const FORFEIT_BPS: u16 = 2_000;
+    const DEPOSIT_AMOUNT: u64 = 1;
+    const COMMIT_END: i64 = 10_000;
+    const REVEAL_END: i64 = 20_000;
+    const SALT: [u8; 32] = [0x42; 32];
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const BIDDER_1: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const BIDDER_2: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn sealed_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"sealed", escrow().as_ref()], &ID)
+    }
+
+    fn commitment_pda(bidder: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"commitment", sealed_pda().0.as_ref(), bidder.as_ref()],
+            &ID,
+        )
+    }
+
+    fn commitment_vault(bidder: &Pubkey) -> Pubkey {
+        get_associated_token_address(&commitment_pda(bidder).0, &MINT_Y)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn sealed_state(
+        pricing: SealedPricing,
+        bids: &[(Pubkey, u64)],
+        commitments: u32,
+    ) -> SealedAuction {
+        let mut auction = SealedAuction {
+            escrow: escrow(),
+            maker: MAKER,
+            token_mint_b: MINT_Y,
+            reserve: RESERVE,
+            collateral: COLLATERAL,
+            commit_end: COMMIT_END,
+            reveal_end: REVEAL_END,
+            pricing,
+            forfeit_bps: FORFEIT_BPS,
+            top_bid: 0,
+            top_bidder: Pubkey::default(),
+            second_bid: 0,
+            commitments,
+            settled: false,
+            bump: sealed_pda().1,
+        };
+        for (bidder, bid) in bids {
+            auction.record_bid(*bidder, *bid);
+        }
+        auction
+    }
+
+    //A commitment of `bid` by `bidder`, revealed or not, with its vault holding `locked`
+    fn commitment_accounts(
+        mollusk: &Mollusk,
+        bidder: Pubkey,
+        bid: u64,
+        revealed: bool,
+        locked: u64,
+    ) -> Vec<(Pubkey, Account)> {
+        let (commitment, bump) = commitment_pda(&bidder);
+        let state = Commitment {
+            auction: sealed_pda().0,
+            bidder,
+            hash: Commitment::hash(bid, &SALT, &bidder),
+            revealed,
+            bid: if revealed { bid } else { 0 },
+            bump,
+        };
+        vec![
+            (
+                commitment,
+                state_account(mollusk, &state, 8 + Commitment::INIT_SPACE),
+            ),
+            (
+                commitment_vault(&bidder),
+                token_account(mollusk, MINT_Y, commitment, locked),
+            ),
+        ]
+    }
+
+    //A sealed auction of 1 X with a reserve of 10_000 Y
+    fn accounts(mollusk: &Mollusk, auction: &SealedAuction) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            kind: EscrowKind::SealedAuction,
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE)
+        };
+
+        let mut accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(mollusk, 1, 0)),
+            (MINT_Y, mint_account(mollusk, 100_000_000, 6)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                sealed_pda().0,
+                state_account(mollusk, auction, 8 + SealedAuction::INIT_SPACE),
+            ),
+        ];
+        for bidder in [BIDDER_1, BIDDER_2] {
+            accounts.push((bidder, user()));
+            accounts.push((
+                get_associated_token_address(&bidder, &MINT_Y),
+                token_account(mollusk, MINT_Y, bidder, 50_000),
+            ));
+        }
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn fill_missing(
+        instruction: &Instruction,
+        mut accounts: Vec<(Pubkey, Account)>,
+    ) -> Vec<(Pubkey, Account)> {
+        for meta in &instruction.accounts {
+            if meta.pubkey != ID && !accounts.iter().any(|(key, _)| *key == meta.pubkey) {
+                accounts.push((meta.pubkey, Account::default()));
+            }
+        }
+        accounts
+    }
+
+    fn start_ix(pricing: SealedPricing) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::StartSealedAuction {
+                commit_end: COMMIT_END,
+                reveal_end: REVEAL_END,
+                collateral: COLLATERAL,
+                pricing,
+                forfeit_bps: FORFEIT_BPS,
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn commit_ix(bidder: Pubkey, bid: u64) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::CommitBid {
+                hash: Commitment::hash(bid, &SALT, &bidder),
+            }
+            .data(),
+            vec![
+                AccountMeta::new(bidder, true),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&bidder, &MINT_Y), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new(commitment_pda(&bidder).0, false),
+                AccountMeta::new(commitment_vault(&bidder), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn reveal_ix(bidder: Pubkey, bid: u64, salt: [u8; 32]) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::RevealBid { bid, salt }.data(),
+            vec![
+                AccountMeta::new_readonly(bidder, true),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&bidder, &MINT_Y), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new(commitment_pda(&bidder).0, false),
+                AccountMeta::new(commitment_vault(&bidder), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ],
+        )
+    }
+
+    fn settle_ix(settler: Pubkey, winner: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SettleSealedAuction {}.data(),
+            vec![
+                AccountMeta::new(settler, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(winner, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&winner, &MINT_X), false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new(get_associated_token_address(&escrow(), &MINT_X), false),
+                AccountMeta::new_readonly(commitment_pda(&winner).0, false),
+                AccountMeta::new(commitment_vault(&winner), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn claim_ix(bidder: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::ClaimCommitment {}.data(),
+            vec![
+                AccountMeta::new(bidder, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(MINT_Y, false),
+                AccountMeta::new(get_associated_token_address(&bidder, &MINT_Y), false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_Y), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new(commitment_pda(&bidder).0, false),
+                AccountMeta::new(commitment_vault(&bidder), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn expire_ix(closer: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::ExpireSealedAuction {}.data(),
+            vec![
+                AccountMeta::new(closer, true),
+                AccountMeta::new(MAKER, false),
+                AccountMeta::new_readonly(MINT_X, false),
+                AccountMeta::new(get_associated_token_address(&MAKER, &MINT_X), false),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(sealed_pda().0, false),
+                AccountMeta::new(get_associated_token_address(&escrow(), &MINT_X), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_clearing_price() {
+        let bids = [(BIDDER_1, 12_000), (BIDDER_2, 15_000)];
+
+        let first = sealed_state(SealedPricing::FirstPrice, &bids, 2);
+        assert_eq!(first.top_bidder, BIDDER_2);
+        assert_eq!(first.clearing_price(), 15_000);
+
+        let second = sealed_state(SealedPricing::SecondPrice, &bids, 2);
+        assert_eq!(second.clearing_price(), 12_000);
+
+        //a lone bidder pays the reserve under second-price
+        let alone = sealed_state(SealedPricing::SecondPrice, &bids[1..], 1);
+        assert_eq!(alone.clearing_price(), RESERVE);
+    }
+
+    #[test]
+    fn test_commitment_hash_binds_bidder() {
+        let hash = Commitment::hash(12_000, &SALT, &BIDDER_1);
+        assert_ne!(hash, Commitment::hash(12_001, &SALT, &BIDDER_1));
+        assert_ne!(hash, Commitment::hash(12_000, &[0; 32], &BIDDER_1));
+        assert_ne!(hash, Commitment::hash(12_000, &SALT, &BIDDER_2));
+    }
+
+    #[test]
+    fn test_start_sealed_auction() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let accounts = vec![
+            (MAKER, user()),
+            (MINT_X, mint_account(&mollusk, DEPOSIT_AMOUNT, 6)),
+            (
+                escrow(),
+                escrow_account(
+                    &mollusk,
+                    &escrow_state(MAKER, SEED, MINT_X, MINT_Y, RESERVE),
+                ),
+            ),
+            (sealed_pda().0, Account::default()),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+
+        let result = mollusk.process_and_validate_instruction(
+            &start_ix(SealedPricing::SecondPrice),
+            &accounts,
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert_eq!(escrow.kind, EscrowKind::SealedAuction);
+
+        let auction = result.get_account(&sealed_pda().0).unwrap();
+        let auction = SealedAuction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.reserve, RESERVE);
+        assert_eq!(auction.pricing, SealedPricing::SecondPrice);
+    }
+
+    #[test]
+    fn test_commit_locks_collateral() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = 1_000;
+
+        let instruction = commit_ix(BIDDER_1, 12_000);
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(
+                &instruction,
+                accounts(&mollusk, &sealed_state(SealedPricing::FirstPrice, &[], 0)),
+            ),
+            &[Check::success()],
+        );
+
+        assert_eq!(
+            amount(result.get_account(&commitment_vault(&BIDDER_1)).unwrap()),
+            COLLATERAL
+        );
+        let auction = result.get_account(&sealed_pda().0).unwrap();
+        let auction = SealedAuction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.commitments, 1);
+    }
+
+    #[test]
+    fn test_commit_after_commit_end() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = COMMIT_END;
+
+        let instruction = commit_ix(BIDDER_1, 12_000);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(
+                &instruction,
+                accounts(&mollusk, &sealed_state(SealedPricing::FirstPrice, &[], 0)),
+            ),
+            &[Check::err(program_error(ErrorCode::CommitPhaseOver))],
+        );
+    }
+
+    #[test]
+    fn test_reveal_tops_up_bid() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = COMMIT_END;
+
+        let mut accounts = accounts(&mollusk, &sealed_state(SealedPricing::FirstPrice, &[], 1));
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_1, 12_000, false, COLLATERAL,
+        ));
+
+        let result = mollusk.process_and_validate_instruction(
+            &reveal_ix(BIDDER_1, 12_000, SALT),
+            &accounts,
+            &[Check::success()],
+        );
+
+        assert_eq!(
+            amount(result.get_account(&commitment_vault(&BIDDER_1)).unwrap()),
+            12_000
+        );
+        let auction = result.get_account(&sealed_pda().0).unwrap();
+        let auction = SealedAuction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert_eq!(auction.top_bidder, BIDDER_1);
+        assert_eq!(auction.top_bid, 12_000);
+    }
+
+    #[test]
+    fn test_reveal_wrong_salt() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = COMMIT_END;
+
+        let mut accounts = accounts(&mollusk, &sealed_state(SealedPricing::FirstPrice, &[], 1));
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_1, 12_000, false, COLLATERAL,
+        ));
+
+        mollusk.process_and_validate_instruction(
+            &reveal_ix(BIDDER_1, 12_000, [0; 32]),
+            &accounts,
+            &[Check::err(program_error(ErrorCode::CommitmentMismatch))],
+        );
+    }
+
+    #[test]
+    fn test_settle_second_price() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = REVEAL_END;
+
+        let auction = sealed_state(
+            SealedPricing::SecondPrice,
+            &[(BIDDER_1, 12_000), (BIDDER_2, 15_000)],
+            2,
+        );
+        let mut accounts = accounts(&mollusk, &auction);
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_2, 15_000, true, 15_000,
+        ));
+
+        let instruction = settle_ix(BIDDER_1, BIDDER_2);
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts),
+            &[Check::success(), Check::account(&escrow()).closed().build()],
+        );
+
+        let winner_x = result
+            .get_account(&get_associated_token_address(&BIDDER_2, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(winner_x), DEPOSIT_AMOUNT);
+
+        let maker_y = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_y), 12_000);
+
+        //the difference stays in the commitment until the winner claims it
+        assert_eq!(
+            amount(result.get_account(&commitment_vault(&BIDDER_2)).unwrap()),
+            3_000
+        );
+    }
+
+    #[test]
+    fn test_settle_during_reveal() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = REVEAL_END - 1;
+
+        let auction = sealed_state(SealedPricing::FirstPrice, &[(BIDDER_2, 15_000)], 1);
+        let mut accounts = accounts(&mollusk, &auction);
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_2, 15_000, true, 15_000,
+        ));
+
+        let instruction = settle_ix(BIDDER_1, BIDDER_2);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts),
+            &[Check::err(program_error(ErrorCode::AuctionNotEnded))],
+        );
+    }
+
+    #[test]
+    fn test_unrevealed_claim_forfeits_collateral() {
+        let mollusk = setup();
+
+        //the last outstanding commitment, so the auction is closed with it
+        let auction = SealedAuction {
+            settled: true,
+            ..sealed_state(SealedPricing::FirstPrice, &[(BIDDER_2, 15_000)], 1)
+        };
+        let mut accounts = accounts(&mollusk, &auction);
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_1, 12_000, false, COLLATERAL,
+        ));
+
+        let instruction = claim_ix(BIDDER_1);
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts),
+            &[
+                Check::success(),
+                Check::account(&commitment_pda(&BIDDER_1).0)
+                    .closed()
+                    .build(),
+                Check::account(&commitment_vault(&BIDDER_1))
+                    .closed()
+                    .build(),
+                Check::account(&sealed_pda().0).closed().build(),
+            ],
+        );
+
+        let forfeit = COLLATERAL * FORFEIT_BPS as u64 / 10_000;
+        let maker_y = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(maker_y), forfeit);
+
+        let bidder_y = result
+            .get_account(&get_associated_token_address(&BIDDER_1, &MINT_Y))
+            .unwrap();
+        assert_eq!(amount(bidder_y), 50_000 + COLLATERAL - forfeit);
+    }
+
+    #[test]
+    fn test_claim_before_settlement() {
+        let mollusk = setup();
+
+        let auction = sealed_state(SealedPricing::FirstPrice, &[(BIDDER_1, 12_000)], 1);
+        let mut accounts = accounts(&mollusk, &auction);
+        accounts.extend(commitment_accounts(
+            &mollusk, BIDDER_1, 12_000, true, 12_000,
+        ));
+
+        let instruction = claim_ix(BIDDER_1);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts),
+            &[Check::err(program_error(ErrorCode::AuctionNotSettled))],
+        );
+    }
+
+    #[test]
+    fn test_expire_unrevealed_auction() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = REVEAL_END;
+
+        //nobody revealed, a bidder closes it so both commitments can be claimed
+        let auction = sealed_state(SealedPricing::FirstPrice, &[], 2);
+        let instruction = expire_ix(BIDDER_1);
+
+        let result = mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, &auction)),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&get_associated_token_address(&escrow(), &MINT_X))
+                    .closed()
+                    .build(),
+            ],
+        );
+
+        let maker_x = result
+            .get_account(&get_associated_token_address(&MAKER, &MINT_X))
+            .unwrap();
+        assert_eq!(amount(maker_x), DEPOSIT_AMOUNT);
+
+        let auction = result.get_account(&sealed_pda().0).unwrap();
+        let auction = SealedAuction::try_deserialize(&mut auction.data.as_slice()).unwrap();
+        assert!(auction.settled);
+    }
+
+    #[test]
+    fn test_expire_during_reveal() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = REVEAL_END - 1;
+
+        let auction = sealed_state(SealedPricing::FirstPrice, &[], 2);
+        let instruction = expire_ix(BIDDER_1);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, &auction)),
+            &[Check::err(program_error(ErrorCode::AuctionNotEnded))],
+        );
+    }
+
+    #[test]
+    fn test_expire_with_revealed_bid() {
+        let mut mollusk = setup();
+        mollusk.sysvars.clock.unix_timestamp = REVEAL_END;
+
+        let auction = sealed_state(SealedPricing::FirstPrice, &[(BIDDER_2, 15_000)], 2);
+        let instruction = expire_ix(BIDDER_1);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(&instruction, accounts(&mollusk, &auction)),
+            &[Check::err(program_error(ErrorCode::AuctionHasBids))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_rejects_sealed_auction() {
+        let mollusk = setup();
+
+        let instruction = exchange_ix(BIDDER_1, MAKER, MINT_X, MINT_Y, SEED);
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &fill_missing(
+                &instruction,
+                accounts(&mollusk, &sealed_state(SealedPricing::FirstPrice, &[], 0)),
+            ),
+            &[Check::err(program_error(ErrorCode::NotAnOffer))],
+        );
+    }
+}
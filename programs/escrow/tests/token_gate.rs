@@ -0,0 +1,323 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, InstructionData};
+    use escrow::{
+        error::ErrorCode,
+        state::{Collection, Escrow, GateKind, Metadata, TokenGate, TOKEN_METADATA_PROGRAM},
+    };
+    use mollusk_svm::{result::Check, Mollusk};
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    use crate::common::*;
+
+    const SEED: u64 = 1;
+    const RECEIVE: u64 = 10_000;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+    const MIN_AMOUNT: u64 = 100;
+    const MAKER: Pubkey = Pubkey::new_from_array([0x01; 32]);
+    const TAKER: Pubkey = Pubkey::new_from_array([0x02; 32]);
+    const MINT_X: Pubkey = Pubkey::new_from_array([0x03; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([0x04; 32]);
+    //the DAO's governance token, and its membership NFT collection
+    const GATE_MINT: Pubkey = Pubkey::new_from_array([0x05; 32]);
+    const COLLECTION: Pubkey = Pubkey::new_from_array([0x06; 32]);
+    const NFT: Pubkey = Pubkey::new_from_array([0x07; 32]);
+
+    fn escrow() -> Pubkey {
+        escrow_pda(&MAKER, SEED).0
+    }
+
+    fn gate_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"gate", escrow().as_ref()], &ID)
+    }
+
+    fn user() -> Account {
+        Account::new(LAMPORTS_PER_SOL, 0, &solana_sdk::system_program::ID)
+    }
+
+    fn metadata(collection: Option<Collection>) -> Metadata {
+        Metadata {
+            key: Metadata::KEY,
+            update_authority: MAKER,
+            mint: NFT,
+            name: "Member #1".to_string(),
+            symbol: "DAO".to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: true,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection,
+        }
+    }
+
+    //Metaplex accounts are longer than the fields read here
+    fn metadata_account(metadata: &Metadata) -> Account {
+        let mut data = metadata.try_to_vec().unwrap();
+        data.resize(679, 0);
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: TOKEN_METADATA_PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn set_token_gate_ix(kind: GateKind, mint: Pubkey, min_amount: u64) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &escrow::instruction::SetTokenGate {
+                kind,
+                mint,
+                min_amount,
+            }
+            .data(),
+            vec![
+                AccountMeta::new(MAKER, true),
+                AccountMeta::new(escrow(), false),
+                AccountMeta::new(gate_pda().0, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    //The optional accounts before the gate are left out by passing the program id,
+    //`holding` is passed in the remaining accounts
+    fn gated_exchange_ix(holding: &[Pubkey]) -> Instruction {
+        let mut instruction = exchange_ix(TAKER, MAKER, MINT_X, MINT_Y, SEED);
+        for _ in 0..5 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(ID, false));
+        }
+        instruction
+            .accounts
+            .push(AccountMeta::new(gate_pda().0, false));
+        for key in holding {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(*key, false));
+        }
+        instruction
+    }
+
+    //An offer behind `gate`, if any, with the taker holding `balance` of GATE_MINT and one NFT
+    fn accounts(
+        mollusk: &Mollusk,
+        gate: Option<TokenGate>,
+        balance: u64,
+        nft_metadata: &Metadata,
+    ) -> Vec<(Pubkey, Account)> {
+        let escrow_state = Escrow {
+            gated: gate.is_some(),
+            ..escrow_state(MAKER, SEED, MINT_X, MINT_Y, RECEIVE)
+        };
+        let mut accounts = vec![
+            (MAKER, user()),
+            (TAKER, user()),
+            (MINT_X, mint_account(mollusk, DEPOSIT_AMOUNT, 6)),
+            (MINT_Y, mint_account(mollusk, RECEIVE, 6)),
+            (GATE_MINT, mint_account(mollusk, balance, 6)),
+            (NFT, mint_account(mollusk, 1, 0)),
+            (escrow(), escrow_account(mollusk, &escrow_state)),
+            (
+                get_associated_token_address(&escrow(), &MINT_X),
+                token_account(mollusk, MINT_X, escrow(), DEPOSIT_AMOUNT),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_X),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &MINT_Y),
+                token_account(mollusk, MINT_Y, TAKER, RECEIVE),
+            ),
+            (
+                get_associated_token_address(&MAKER, &MINT_Y),
+                Account::default(),
+            ),
+            (
+                get_associated_token_address(&TAKER, &GATE_MINT),
+                token_account(mollusk, GATE_MINT, TAKER, balance),
+            ),
+            (
+                get_associated_token_address(&TAKER, &NFT),
+                token_account(mollusk, NFT, TAKER, 1),
+            ),
+            (Metadata::address(&NFT), metadata_account(nft_metadata)),
+            (
+                gate_pda().0,
+                match gate {
+                    Some(gate) => state_account(mollusk, &gate, TokenGate::LEN),
+                    None => Account::default(),
+                },
+            ),
+        ];
+        accounts.extend(program_accounts());
+        accounts
+    }
+
+    fn gate(kind: GateKind) -> TokenGate {
+        TokenGate {
+            escrow: escrow(),
+            kind,
+            mint: match kind {
+                GateKind::Balance => GATE_MINT,
+                GateKind::Collection => COLLECTION,
+            },
+            min_amount: MIN_AMOUNT,
+            bump: gate_pda().1,
+        }
+    }
+
+    fn verified() -> Metadata {
+        metadata(Some(Collection {
+            verified: true,
+            key: COLLECTION,
+        }))
+    }
+
+    #[test]
+    fn test_in_collection() {
+        assert!(verified().in_collection(&COLLECTION));
+        assert!(!verified().in_collection(&GATE_MINT));
+        let unverified = metadata(Some(Collection {
+            verified: false,
+            key: COLLECTION,
+        }));
+        assert!(!unverified.in_collection(&COLLECTION));
+        assert!(!metadata(None).in_collection(&COLLECTION));
+
+        //the padding after the fields read is ignored
+        let account = metadata_account(&verified());
+        let loaded = Metadata::deserialize(&mut account.data.as_slice()).unwrap();
+        assert!(loaded.in_collection(&COLLECTION));
+    }
+
+    #[test]
+    fn test_set_token_gate() {
+        let mollusk = setup();
+
+        let result = mollusk.process_and_validate_instruction(
+            &set_token_gate_ix(GateKind::Balance, GATE_MINT, MIN_AMOUNT),
+            &accounts(&mollusk, None, 0, &verified()),
+            &[Check::success()],
+        );
+
+        let escrow = result.get_account(&escrow()).unwrap();
+        let escrow = Escrow::try_deserialize(&mut escrow.data.as_slice()).unwrap();
+        assert!(escrow.gated);
+
+        let gate = result.get_account(&gate_pda().0).unwrap();
+        let gate = TokenGate::try_deserialize(&mut gate.data.as_slice()).unwrap();
+        assert_eq!(gate.mint, GATE_MINT);
+        assert_eq!(gate.min_amount, MIN_AMOUNT);
+    }
+
+    #[test]
+    fn test_set_balance_gate_without_amount() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &set_token_gate_ix(GateKind::Balance, GATE_MINT, 0),
+            &accounts(&mollusk, None, 0, &verified()),
+            &[Check::err(program_error(ErrorCode::InvalidTokenGate))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_gate_balance() {
+        let mollusk = setup();
+        let gate_key = gate_pda().0;
+
+        mollusk.process_and_validate_instruction(
+            &gated_exchange_ix(&[get_associated_token_address(&TAKER, &GATE_MINT)]),
+            &accounts(
+                &mollusk,
+                Some(gate(GateKind::Balance)),
+                MIN_AMOUNT,
+                &verified(),
+            ),
+            &[
+                Check::success(),
+                Check::account(&escrow()).closed().build(),
+                Check::account(&gate_key).closed().build(),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_exchange_below_gate_balance() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &gated_exchange_ix(&[get_associated_token_address(&TAKER, &GATE_MINT)]),
+            &accounts(
+                &mollusk,
+                Some(gate(GateKind::Balance)),
+                MIN_AMOUNT - 1,
+                &verified(),
+            ),
+            &[Check::err(program_error(ErrorCode::GateNotMet))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_collection_nft() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &gated_exchange_ix(&[
+                get_associated_token_address(&TAKER, &NFT),
+                Metadata::address(&NFT),
+            ]),
+            &accounts(&mollusk, Some(gate(GateKind::Collection)), 0, &verified()),
+            &[Check::success()],
+        );
+    }
+
+    #[test]
+    fn test_exchange_with_unverified_collection_nft() {
+        let mollusk = setup();
+        let unverified = metadata(Some(Collection {
+            verified: false,
+            key: COLLECTION,
+        }));
+
+        mollusk.process_and_validate_instruction(
+            &gated_exchange_ix(&[
+                get_associated_token_address(&TAKER, &NFT),
+                Metadata::address(&NFT),
+            ]),
+            &accounts(&mollusk, Some(gate(GateKind::Collection)), 0, &unverified),
+            &[Check::err(program_error(ErrorCode::GateNotMet))],
+        );
+    }
+
+    #[test]
+    fn test_exchange_without_holding() {
+        let mollusk = setup();
+
+        mollusk.process_and_validate_instruction(
+            &gated_exchange_ix(&[]),
+            &accounts(
+                &mollusk,
+                Some(gate(GateKind::Balance)),
+                MIN_AMOUNT,
+                &verified(),
+            ),
+            &[Check::err(program_error(ErrorCode::GateNotMet))],
+        );
+    }
+}
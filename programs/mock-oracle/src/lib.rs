@@ -0,0 +1,68 @@
+//A price feed whose authority can set any price. Its account starts with the
+//price fields the escrow reads, so tests and local validators can drive
+//oracle-priced offers with it on escrow builds with the `mock-oracle` feature.
+use anchor_lang::prelude::*;
+
+declare_id!("7gfYkN3NfEQPaE35GvbmLJgZLmSvgdAXVV37TSRHCtJ6");
+
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, price: i64, conf: u64, expo: i32) -> Result<()> {
+        ctx.accounts.price_feed.set_inner(PriceFeed {
+            price,
+            conf,
+            expo,
+            publish_time: Clock::get()?.unix_timestamp,
+            authority: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    pub fn set_price(ctx: Context<SetPrice>, price: i64, conf: u64, expo: i32) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.price = price;
+        price_feed.conf = conf;
+        price_feed.expo = expo;
+        price_feed.publish_time = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceFeed::INIT_SPACE,
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+}
+
+//`price * 10^expo` units of the quote per unit of the base, give or take `conf`
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub authority: Pubkey,
+}